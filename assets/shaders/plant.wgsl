@@ -0,0 +1,44 @@
+#import bevy_pbr::{
+    mesh_functions,
+    forward_io::{Vertex, VertexOutput},
+    mesh_view_bindings::globals,
+    view_transformations::position_world_to_clip,
+}
+
+@group(2) @binding(100) var<uniform> sway_strength: f32;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+    var world_position = mesh_functions::mesh_position_local_to_world(model, vec4<f32>(vertex.position, 1.0));
+
+    // the top of the plant sways while its base stays rooted, out of step with its neighbours
+    let height = vertex.position.y + 0.5;
+    let phase = globals.time * 1.7 + world_position.x * 0.6 + world_position.z * 0.4;
+    world_position.x += sin(phase) * sway_strength * height;
+    world_position.z += cos(phase * 0.8) * sway_strength * height;
+
+    out.world_position = world_position;
+    out.position = position_world_to_clip(world_position.xyz);
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+
+#ifdef VERTEX_UVS
+    out.uv = vertex.uv;
+#endif
+
+#ifdef VERTEX_UVS_B
+    out.uv_b = vertex.uv_b;
+#endif
+
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+
+    return out;
+}
@@ -1,13 +1,51 @@
-#import bevy_pbr::{forward_io::VertexOutput, mesh_view_bindings as view_bindings}
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings as view_bindings,
+    pbr_functions::apply_fog,
+}
 
 @group(2) @binding(0) var<uniform> material_color: vec4<f32>;
 @group(2) @binding(1) var material_color_texture: texture_2d<f32>;
 @group(2) @binding(2) var material_color_sampler: sampler;
+@group(2) @binding(3) var<uniform> material_fade: f32;
+@group(2) @binding(4) var<uniform> material_lod_fade: vec2<f32>;
+@group(2) @binding(5) var<uniform> material_view_distance: f32;
+// average colour of each tile of the block atlas
+@group(2) @binding(6) var<uniform> material_tile_colors: array<vec4<f32>, #{ATLAS_TILES}>;
+// heights between which the colored render style shades terrain from dark to bright
+const SHADE_LOW: f32 = 0.0;
+const SHADE_HIGH: f32 = 128.0;
 
 struct FragmentOutput {
   @location(0) color: vec4<f32>
 }
 
+// ordered dither threshold for the pixel, used to dissolve chunks in while they fade in
+fn dither_threshold(position: vec2<f32>) -> f32 {
+    var bayer = array<f32, 16>(
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0
+    );
+    let x = u32(position.x) % 4u;
+    let y = u32(position.y) % 4u;
+    return (bayer[y * 4u + x] + 0.5) / 16.0;
+}
+
+// samples the block atlas, snapping uv to the middle of its texel wherever the atlas is
+// magnified. anisotropic filtering needs the sampler to magnify linearly, and this keeps
+// close blocks pixelated anyway. the gradients of the unsnapped uv are passed on so the
+// mip level and anisotropy are picked as usual
+fn sample_atlas(uv: vec2<f32>) -> vec4<f32> {
+    let size = vec2<f32>(textureDimensions(material_color_texture));
+    let uv_dx = dpdx(uv);
+    let uv_dy = dpdy(uv);
+    let magnified = max(length(uv_dx * size), length(uv_dy * size)) < 1.0;
+    let snapped = (floor(uv * size) + 0.5) / size;
+    return textureSampleGrad(material_color_texture, material_color_sampler, select(uv, snapped, magnified), uv_dx, uv_dy);
+}
+
 @fragment
 fn fragment(
     in: VertexOutput,
@@ -18,16 +56,72 @@ fn fragment(
     let view_to_world = world_position - view_position;
 
     let dist = length(view_to_world);
-    if dist > 496.0 {
+    if dist > material_view_distance {
       discard;
     }
 
-    let brightness = dot(normalize(in.world_normal), normalize(vec3(-0.2, 0.7, 0.2)));
+    if material_fade < dither_threshold(in.position.xy) {
+      discard;
+    }
+
+    let normal = normalize(in.world_normal);
+    let light_direction = normalize(vec3(-0.2, 0.7, 0.2));
+    let brightness = dot(normal, light_direction);
 
-    let color_lit = material_color * textureSample(material_color_texture, material_color_sampler, in.uv);
+    // how much of the vertex colour's biome tint applies, less than 1 only on grass sides
+    var tint_amount = 1.0;
+    // the material colour tints water alone, leaving glass in the same mesh its own colour
+    let tile = floor(in.uv.x * f32(#{ATLAS_TILES}));
+    let block_color = select(vec4(1.0), material_color, tile == f32(#{WATER_TILE}));
+    let flat_color = block_color * material_tile_colors[min(u32(tile), u32(#{ATLAS_TILES}) - 1u)];
+#ifdef FLAT_SHADED
+    var color_lit = flat_color;
+    // flat grass sides are plain dirt, without the overlay the tint belongs to
+    tint_amount = select(1.0, 0.0, tile == f32(#{GRASS_SIDE_TILE}));
+#else ifdef BLOCK_COLORS
+    // block colours are baked into the vertex colours, brightening towards the peaks
+    let height_shade = mix(0.75, 1.1, smoothstep(SHADE_LOW, SHADE_HIGH, world_position.y));
+    var color_lit = vec4(block_color.rgb * height_shade, block_color.a);
+#else
+    var color_lit = block_color * sample_atlas(in.uv);
+    // grass sides blend the overlay in the next tile over the dirt, and only the overlay is
+    // tinted. it is sampled for every tile to keep the sampling in uniform control flow
+    let overlay_uv = in.uv + vec2(1.0 / f32(#{ATLAS_TILES}), 0.0);
+    let overlay = sample_atlas(overlay_uv);
+    let grass_side = tile == f32(#{GRASS_SIDE_TILE});
+    let coverage = select(0.0, overlay.a, grass_side);
+    color_lit = vec4(mix(color_lit.rgb, overlay.rgb, coverage), color_lit.a);
+    tint_amount = select(1.0, coverage, grass_side);
+    // blend distant terrain towards the average colour of each block
+    let lod = smoothstep(material_lod_fade.x, material_lod_fade.y, dist);
+    color_lit = vec4(mix(color_lit.rgb, flat_color.rgb, lod), color_lit.a);
+#endif
+    var block_light = 0.0;
+#ifdef VERTEX_COLORS
+    // rgb is a biome tint, e.g. for grass, and alpha is the block light level
+    color_lit = vec4(color_lit.rgb * mix(vec3(1.0), in.color.rgb, tint_amount), color_lit.a);
+    block_light = in.color.a;
+#endif
 
     let dark = color_lit * 0.7;
-    let color = mix(dark, color_lit, brightness);
+    var color = mix(dark, color_lit, brightness);
+    // light emitting blocks like lava glow and light up what is around them
+    color = vec4(max(color.rgb, color_lit.rgb * block_light), color.a);
+
+#ifdef VERTEX_UVS_B
+    // per block roughness and metallic, giving smooth surfaces like water a highlight
+    let roughness = in.uv_b.x;
+    let metallic = in.uv_b.y;
+    let view_direction = normalize(-view_to_world);
+    let half_direction = normalize(light_direction + view_direction);
+    let shininess = exp2(10.0 * (1.0 - roughness) + 1.0);
+    let highlight = pow(max(dot(normal, half_direction), 0.0), shininess) * (1.0 - roughness);
+    let specular = mix(vec3(0.3), color_lit.rgb, metallic);
+    color = vec4(color.rgb * (1.0 - metallic * 0.5) + specular * highlight, color.a);
+#endif
+
+    // only set while the camera is underwater
+    color = apply_fog(view_bindings::fog, color, world_position, view_position);
 
     var output: FragmentOutput;
     output.color = color;
@@ -0,0 +1,89 @@
+use bevy::math::{I64Vec3, Quat, Vec3};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rustcraft::{
+    chunks::{
+        chunk::ChunkCoordinate,
+        chunk_loader::{ChunkIterator, MAX_CHUNKS_PER_FRAME},
+    },
+    world::World,
+};
+
+const RENDER_DISTANCES: [u32; 3] = [16, 32, 64];
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// Runs a search from the origin to completion, pulling chunks a frame's worth at a time
+/// like `gather_chunks` does. Returns how many chunks it visited.
+fn drain(iterator: &mut ChunkIterator, distance: u32, world: &mut World) -> u64 {
+    let mut count = 0;
+    while let Some(chunks) = iterator.next_chunks(MAX_CHUNKS_PER_FRAME, distance, world) {
+        count += chunks.len() as u64;
+    }
+    count
+}
+
+fn fresh_iterator() -> ChunkIterator {
+    let mut iterator = ChunkIterator::new();
+    iterator.update(ChunkCoordinate(I64Vec3::ZERO), Vec3::Z);
+    iterator
+}
+
+fn next_chunks(c: &mut Criterion) {
+    let mut world = World::with_seed(0);
+    let mut group = c.benchmark_group("next_chunks");
+    group.sample_size(10);
+    for distance in RENDER_DISTANCES {
+        let chunks = drain(&mut fresh_iterator(), distance, &mut world);
+        group.throughput(Throughput::Elements(chunks));
+        group.bench_function(format!("render_distance_{}", distance), |b| {
+            b.iter_batched(
+                fresh_iterator,
+                |mut iterator| drain(&mut iterator, distance, &mut world),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// A camera moving forward at `speed` blocks per second while turning at `turn_rate`
+/// radians per second, sampled once per frame.
+fn camera_path(speed: f32, turn_rate: f32, frames: usize) -> Vec<(ChunkCoordinate, Vec3)> {
+    let world = World::with_seed(0);
+    let mut position = Vec3::new(8.0, 64.0, 8.0);
+    let mut forward = Vec3::Z;
+    let rotation = Quat::from_rotation_y(turn_rate * FRAME_TIME);
+    (0..frames)
+        .map(|_| {
+            position += forward * speed * FRAME_TIME;
+            forward = rotation * forward;
+            let chunk = world.block_to_chunk_coordinate(position.floor().as_i64vec3());
+            (chunk, forward)
+        })
+        .collect()
+}
+
+fn update(c: &mut Criterion) {
+    let paths = [
+        ("walking", camera_path(4.3, 0.0, 3600)),
+        ("flying", camera_path(20.0, 0.0, 3600)),
+        ("looking_around", camera_path(0.0, 1.5, 3600)),
+        ("walking_and_turning", camera_path(4.3, 0.5, 3600)),
+    ];
+
+    let mut group = c.benchmark_group("update");
+    for (name, path) in paths.iter() {
+        group.throughput(Throughput::Elements(path.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let mut iterator = ChunkIterator::new();
+                path.iter()
+                    .filter(|(chunk, forward)| iterator.update(*chunk, *forward))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, next_chunks, update);
+criterion_main!(benches);
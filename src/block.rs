@@ -1,17 +1,364 @@
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+use std::str::FromStr;
+
+use bevy::math::I64Vec3;
+
+use crate::item::ToolTier;
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum BlockType {
+    #[default]
     Air,
     Stone,
     Grass,
     Sand,
     Water,
     Snow,
+    StoneSlab,
+    TallGrass,
+    Lava,
+    Glass,
+    RedGlass,
+    GreenGlass,
+    BlueGlass,
+    Bedrock,
+    /// What stone breaks up into when mined.
+    Cobblestone,
+}
+
+/// How many times longer blocks take to break without the tool they require.
+const INADEQUATE_TOOL_PENALTY: f32 = 5.0;
+
+/// Every block type, indexed by its numeric id.
+const BLOCKS: [BlockType; 15] = [
+    BlockType::Air,
+    BlockType::Stone,
+    BlockType::Grass,
+    BlockType::Sand,
+    BlockType::Water,
+    BlockType::Snow,
+    BlockType::StoneSlab,
+    BlockType::TallGrass,
+    BlockType::Lava,
+    BlockType::Glass,
+    BlockType::RedGlass,
+    BlockType::GreenGlass,
+    BlockType::BlueGlass,
+    BlockType::Bedrock,
+    BlockType::Cobblestone,
+];
+
+/// The geometry a block is meshed with.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BlockShape {
+    Cube,
+    /// The bottom half of a cube.
+    Slab,
+    /// Two intersecting quads, used for plants.
+    Cross,
 }
 
-impl Default for BlockType {
-    fn default() -> Self {
-        Self::Air
+impl BlockShape {
+    /// Height of the part of the cell bodies collide with, from the bottom of the cell.
+    pub fn collision_height(&self) -> f32 {
+        match self {
+            Self::Cube | Self::Cross => 1.0,
+            Self::Slab => 0.5,
+        }
+    }
+
+    /// Whether the shape completely fills the face of its cell in `direction`, hiding
+    /// the face of any neighbouring block behind it.
+    pub fn covers_face(&self, direction: I64Vec3) -> bool {
+        match self {
+            Self::Cube => true,
+            Self::Slab => direction == I64Vec3::NEG_Y,
+            Self::Cross => false,
+        }
+    }
+
+    /// Whether the shape has a face lying on the boundary of its cell in `direction`,
+    /// so that a neighbouring block can hide it.
+    pub fn touches_face(&self, direction: I64Vec3) -> bool {
+        match self {
+            Self::Cube => true,
+            Self::Slab => direction != I64Vec3::Y,
+            Self::Cross => false,
+        }
     }
 }
 
-pub const BLOCK_COUNT: usize = 6;
+impl BlockType {
+    /// Numeric id of the block, as used in saves and console commands.
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        BLOCKS.get(id as usize).copied()
+    }
+
+    /// Whether the block fully occludes what is behind it.
+    pub fn is_solid(&self) -> bool {
+        !self.is_transparent()
+    }
+
+    /// Whether faces behind the block can be seen through it.
+    pub fn is_transparent(&self) -> bool {
+        matches!(self, Self::Air | Self::Water | Self::TallGrass) || self.is_glass()
+    }
+
+    /// Whether the block is partly see-through, so it is drawn blended and back to front.
+    pub fn is_translucent(&self) -> bool {
+        matches!(self, Self::Water) || self.is_glass()
+    }
+
+    /// Whether the block is clear or stained glass, which is see-through but still
+    /// collidable.
+    pub fn is_glass(&self) -> bool {
+        matches!(
+            self,
+            Self::Glass | Self::RedGlass | Self::GreenGlass | Self::BlueGlass
+        )
+    }
+
+    /// Whether bodies collide with the block. Fluids and plants are waded through instead,
+    /// slowing movement by their [`speed_multiplier`](Self::speed_multiplier).
+    pub fn is_collidable(&self) -> bool {
+        !matches!(self, Self::Air | Self::Water | Self::Lava | Self::TallGrass)
+    }
+
+    /// Multiplier on walking speed while moving through the block.
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            Self::Water => 0.5,
+            Self::Lava => 0.3,
+            Self::TallGrass => 0.8,
+            _ => 1.0,
+        }
+    }
+
+    /// Seconds the block takes to break with the tool it requires.
+    pub fn hardness(&self) -> f32 {
+        match self {
+            Self::Stone | Self::StoneSlab => 1.5,
+            Self::Cobblestone => 2.0,
+            Self::Grass => 0.6,
+            Self::Sand => 0.5,
+            Self::Snow => 0.2,
+            Self::Glass | Self::RedGlass | Self::GreenGlass | Self::BlueGlass => 0.3,
+            // the floor of the world can't be mined through
+            Self::Bedrock => f32::INFINITY,
+            _ => 0.0,
+        }
+    }
+
+    /// The weakest tool that makes the block drop when broken. It still breaks with a
+    /// weaker tool, just slowly and without dropping anything.
+    pub fn required_tool(&self) -> ToolTier {
+        match self {
+            Self::Stone | Self::StoneSlab | Self::Cobblestone => ToolTier::Pickaxe,
+            _ => ToolTier::Hand,
+        }
+    }
+
+    /// Whether breaking the block with `tool` drops it.
+    pub fn harvestable_with(&self, tool: ToolTier) -> bool {
+        tool >= self.required_tool()
+    }
+
+    /// The block that breaking this one drops. Stone breaks up into cobblestone.
+    pub fn drop(&self) -> BlockType {
+        match self {
+            Self::Stone => Self::Cobblestone,
+            _ => *self,
+        }
+    }
+
+    /// Seconds the block takes to break with `tool`.
+    pub fn break_time(&self, tool: ToolTier) -> f32 {
+        if self.harvestable_with(tool) {
+            self.hardness()
+        } else {
+            self.hardness() * INADEQUATE_TOOL_PENALTY
+        }
+    }
+
+    /// Whether the block can be targeted for breaking.
+    pub fn is_targetable(&self) -> bool {
+        !matches!(self, Self::Air | Self::Water)
+    }
+
+    pub fn shape(&self) -> BlockShape {
+        match self {
+            Self::StoneSlab => BlockShape::Slab,
+            Self::TallGrass => BlockShape::Cross,
+            _ => BlockShape::Cube,
+        }
+    }
+
+    /// Level of block light the block emits, from 0 to [`MAX_LIGHT`].
+    pub fn light_emission(&self) -> u8 {
+        match self {
+            Self::Lava => MAX_LIGHT,
+            _ => 0,
+        }
+    }
+
+    /// Roughness and metallic values used to light the block's surface.
+    pub fn surface(&self) -> [f32; 2] {
+        match self {
+            Self::Water => [0.1, 0.0],
+            Self::Glass | Self::RedGlass | Self::GreenGlass | Self::BlueGlass => [0.05, 0.0],
+            Self::Snow => [0.5, 0.0],
+            _ => [0.9, 0.0],
+        }
+    }
+
+    /// Colour of the block in the colored render style, which draws without the block
+    /// atlas. Grass is white as it takes its colour from the biome tint.
+    pub fn base_color(&self) -> [f32; 3] {
+        match self {
+            Self::Air => [0.0, 0.0, 0.0],
+            Self::Stone | Self::StoneSlab => [0.5, 0.5, 0.52],
+            Self::Cobblestone => [0.42, 0.42, 0.44],
+            Self::Grass | Self::TallGrass => [1.0, 1.0, 1.0],
+            Self::Sand => [0.86, 0.78, 0.55],
+            Self::Water => [0.2, 0.4, 0.8],
+            Self::Snow => [0.95, 0.95, 0.98],
+            Self::Lava => [0.9, 0.35, 0.05],
+            Self::Bedrock => [0.2, 0.2, 0.22],
+            Self::Glass | Self::RedGlass | Self::GreenGlass | Self::BlueGlass => [0.85, 0.9, 0.95],
+        }
+    }
+
+    /// Colour the block's texture is multiplied by. Stained glass shares the clear glass
+    /// tile and takes its colour from this, while grass is tinted by its biome instead.
+    pub fn tint(&self) -> [f32; 3] {
+        match self {
+            Self::RedGlass => [0.9, 0.25, 0.2],
+            Self::GreenGlass => [0.3, 0.8, 0.3],
+            Self::BlueGlass => [0.25, 0.4, 0.9],
+            _ => [1.0; 3],
+        }
+    }
+
+    /// Index of the block's tile in the block atlas, or `None` for air, which has none.
+    pub fn texture_index(&self) -> Option<usize> {
+        match self {
+            Self::Air => None,
+            Self::Stone | Self::StoneSlab => Some(0),
+            Self::Grass => Some(1),
+            Self::Sand => Some(2),
+            Self::Water => Some(WATER_TILE),
+            Self::Snow => Some(4),
+            Self::TallGrass => Some(5),
+            Self::Lava => Some(6),
+            Self::Glass | Self::RedGlass | Self::GreenGlass | Self::BlueGlass => Some(GLASS_TILE),
+            Self::Bedrock => Some(10),
+            Self::Cobblestone => Some(11),
+        }
+    }
+
+    /// Index of the tile for the block's face with the given normal. Grass sides are dirt,
+    /// with the grass overlay tile after them drawn over the top edge, and the bottom is
+    /// plain dirt.
+    pub fn face_texture_index(&self, normal: [f32; 3]) -> Option<usize> {
+        match self {
+            Self::Grass if normal[1] <= 0.0 => Some(GRASS_SIDE_TILE),
+            _ => self.texture_index(),
+        }
+    }
+}
+
+impl FromStr for BlockType {
+    type Err = String;
+
+    /// Parses a block from either its name (`stone`) or its numeric id (`1`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = s.parse::<u8>() {
+            return Self::from_id(id).ok_or_else(|| format!("unknown block id {}", id));
+        }
+
+        match s.to_lowercase().as_str() {
+            "air" => Ok(Self::Air),
+            "stone" => Ok(Self::Stone),
+            "grass" => Ok(Self::Grass),
+            "sand" => Ok(Self::Sand),
+            "water" => Ok(Self::Water),
+            "snow" => Ok(Self::Snow),
+            "stone_slab" => Ok(Self::StoneSlab),
+            "tall_grass" => Ok(Self::TallGrass),
+            "lava" => Ok(Self::Lava),
+            "glass" => Ok(Self::Glass),
+            "red_glass" => Ok(Self::RedGlass),
+            "green_glass" => Ok(Self::GreenGlass),
+            "blue_glass" => Ok(Self::BlueGlass),
+            "bedrock" => Ok(Self::Bedrock),
+            "cobblestone" => Ok(Self::Cobblestone),
+            _ => Err(format!("unknown block '{}'", s)),
+        }
+    }
+}
+
+/// Number of tiles in the block atlas, laid out in a single row.
+pub const ATLAS_TILES: usize = 12;
+
+/// Tile of the dirt under the side of a grass block. The tile after it is the grass overlay,
+/// which the shader tints and blends over it.
+pub const GRASS_SIDE_TILE: usize = 7;
+/// Tile shared by clear and stained glass.
+pub const GLASS_TILE: usize = 9;
+/// Tile of water, the only block the translucent material's colour applies to.
+pub const WATER_TILE: usize = 3;
+
+/// Brightest block light level, which falls off by one per block.
+pub const MAX_LIGHT: u8 = 15;
+
+#[cfg(test)]
+mod tests {
+    use crate::item::ToolTier;
+
+    use super::{BlockShape, BlockType, BLOCKS};
+
+    #[test]
+    fn test_ids_round_trip() {
+        for block in BLOCKS {
+            assert_eq!(Some(block), BlockType::from_id(block.id()));
+        }
+        assert_eq!(None, BlockType::from_id(BLOCKS.len() as u8));
+    }
+
+    #[test]
+    fn test_stone_needs_pickaxe() {
+        let stone = BlockType::Stone;
+        assert!(!stone.harvestable_with(ToolTier::Hand));
+        assert!(stone.harvestable_with(ToolTier::Pickaxe));
+        assert!(stone.break_time(ToolTier::Hand) > stone.break_time(ToolTier::Pickaxe));
+
+        assert!(BlockType::Grass.harvestable_with(ToolTier::Hand));
+    }
+
+    #[test]
+    fn test_stone_drops_cobblestone() {
+        assert_eq!(BlockType::Cobblestone, BlockType::Stone.drop());
+        assert_eq!(BlockType::Cobblestone, BlockType::Cobblestone.drop());
+        assert_eq!(BlockType::Sand, BlockType::Sand.drop());
+        assert!(!BlockType::Cobblestone.harvestable_with(ToolTier::Hand));
+    }
+
+    #[test]
+    fn test_collision_is_independent_of_transparency() {
+        assert!(BlockType::Stone.is_collidable());
+        assert!(BlockType::Glass.is_transparent() && BlockType::Glass.is_collidable());
+        assert!(BlockType::TallGrass.is_transparent() && !BlockType::TallGrass.is_collidable());
+        assert!(!BlockType::Lava.is_transparent() && !BlockType::Lava.is_collidable());
+    }
+
+    #[test]
+    fn test_air_has_no_texture() {
+        assert_eq!(None, BlockType::Air.texture_index());
+        assert_eq!(None, BlockType::Air.face_texture_index([0.0, 1.0, 0.0]));
+        assert_eq!(Some(0), BlockType::StoneSlab.texture_index());
+        assert_eq!(0.5, BlockShape::Slab.collision_height());
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 
 use bevy::{
     math::{I64Vec3, U16Vec3, Vec3},
@@ -23,23 +23,93 @@ impl From<I64Vec3> for ChunkCoordinate {
     }
 }
 
+/// Offsets to each face neighbour of a chunk, in the order faces are indexed everywhere.
+pub const ADJACENT_OFFSETS: [I64Vec3; 6] = [
+    I64Vec3::Z,
+    I64Vec3::NEG_Z,
+    I64Vec3::X,
+    I64Vec3::NEG_X,
+    I64Vec3::Y,
+    I64Vec3::NEG_Y,
+];
+
 impl ChunkCoordinate {
     pub fn adjacent(&self) -> Vec<ChunkCoordinate> {
-        vec![
-            ChunkCoordinate(self.0 + I64Vec3::new(0, 0, 1)),
-            ChunkCoordinate(self.0 + I64Vec3::new(0, 0, -1)),
-            ChunkCoordinate(self.0 + I64Vec3::new(1, 0, 0)),
-            ChunkCoordinate(self.0 + I64Vec3::new(-1, 0, 0)),
-            ChunkCoordinate(self.0 + I64Vec3::new(0, 1, 0)),
-            ChunkCoordinate(self.0 + I64Vec3::new(0, -1, 0)),
-        ]
+        ADJACENT_OFFSETS
+            .iter()
+            .map(|offset| ChunkCoordinate(self.0 + *offset))
+            .collect()
     }
 }
 
 type BlockPalette = HashMap<U16Vec3, BlockType>;
 
+/// Records which faces of a chunk can see each other through non-solid blocks, used to
+/// cull chunks hidden behind solid terrain. Faces are indexed in the same order as
+/// `ChunkCoordinate::adjacent`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChunkVisibility([u8; 6]);
+
+impl ChunkVisibility {
+    pub const ALL: Self = Self([0b111111; 6]);
+    pub const NONE: Self = Self([0; 6]);
+
+    pub fn connected(&self, from: usize, to: usize) -> bool {
+        self.0[from] & (1 << to) != 0
+    }
+
+    fn connect_all(&mut self, faces: u8) {
+        for face in 0..6 {
+            if faces & (1 << face) != 0 {
+                self.0[face] |= faces;
+            }
+        }
+    }
+}
+
+/// Height in blocks of each horizontal slice of a chunk that can be re-meshed on its own.
+pub const SLICE_HEIGHT: u16 = 4;
+
+/// A set of horizontal slices of a chunk, so that an edit only re-meshes the slices it
+/// can affect rather than the whole chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChunkSlices(u16);
+
+impl ChunkSlices {
+    pub const ALL: Self = Self(u16::MAX);
+    pub const NONE: Self = Self(0);
+
+    /// The slices whose meshes can change when the block at local height `y` changes,
+    /// which includes the rows just above and below it. `y` may lie just outside the
+    /// chunk, for edits in the chunk above or below.
+    pub fn around(y: i64, size: u16) -> Self {
+        let max = size as i64 - 1;
+        ((y - 1).max(0)..=(y + 1).min(max)).fold(Self::NONE, |slices, y| {
+            slices.union(Self(1 << (y as u16 / SLICE_HEIGHT)))
+        })
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Whether the slice holding local height `y` is in the set.
+    pub fn contains(&self, y: u16) -> bool {
+        self.0 & (1 << (y / SLICE_HEIGHT)) != 0
+    }
+}
+
+/// Index of the face opposite `face`, e.g. the `-x` face for `+x`.
+pub fn opposite_face(face: usize) -> usize {
+    face ^ 1
+}
+
+#[derive(Clone)]
 pub struct ChunkData {
     blocks: BlockPalette,
+    /// Per-column biome temperature, indexed by `z * size + x`.
+    temperatures: Vec<f32>,
+    visibility: ChunkVisibility,
     pub size: u16,
     pub dirty: bool,
 }
@@ -50,6 +120,8 @@ impl Default for ChunkData {
     fn default() -> Self {
         Self {
             blocks: HashMap::new(),
+            temperatures: Vec::new(),
+            visibility: ChunkVisibility::ALL,
             size: CHUNK_SIZE,
             dirty: false,
         }
@@ -58,7 +130,7 @@ impl Default for ChunkData {
 
 impl ChunkData {
     fn is_block_in_chunk(&self, block_coord: U16Vec3) -> bool {
-        return block_coord.x < self.size && block_coord.y < self.size && block_coord.z < self.size;
+        block_coord.x < self.size && block_coord.y < self.size && block_coord.z < self.size
     }
 
     pub fn empty(&self) -> bool {
@@ -69,51 +141,242 @@ impl ChunkData {
         &self.blocks
     }
 
-    pub fn get_block_at(&self, block_coord: U16Vec3) -> BlockType {
+    /// Returns the block at a position within the chunk, where empty positions are air, or
+    /// `None` if the position is outside the chunk.
+    pub fn get_block_at(&self, block_coord: U16Vec3) -> Option<BlockType> {
         if !self.is_block_in_chunk(block_coord) {
-            panic!("get block {:?} not in chunk", block_coord);
+            return None;
         }
 
-        return *self.blocks.get(&block_coord).unwrap_or(&BlockType::Air);
+        Some(self.block(block_coord))
     }
 
-    pub fn set_block_at(&mut self, block_coord: U16Vec3, block_type: BlockType) {
+    /// Replaces the block at a position within the chunk. Returns `false`, leaving the chunk
+    /// unchanged, if the position is outside the chunk.
+    pub fn set_block_at(&mut self, block_coord: U16Vec3, block_type: BlockType) -> bool {
         if !self.is_block_in_chunk(block_coord) {
-            panic!("set block {:?} not in chunk", block_coord);
+            return false;
         }
 
-        self.blocks.insert(block_coord, block_type);
+        if block_type == BlockType::Air {
+            self.blocks.remove(&block_coord);
+        } else {
+            self.blocks.insert(block_coord, block_type);
+        }
         self.dirty = true;
+        true
+    }
+
+    /// Looks up a block at a position already known to be within the chunk.
+    fn block(&self, block_coord: U16Vec3) -> BlockType {
+        self.blocks.get(&block_coord).copied().unwrap_or_default()
+    }
+
+    pub fn visibility(&self) -> ChunkVisibility {
+        self.visibility
+    }
+
+    /// Recomputes face visibility by flood filling each connected region of non-solid
+    /// blocks and connecting every chunk face that region touches.
+    pub fn update_visibility(&mut self) {
+        if self.blocks.is_empty() {
+            self.visibility = ChunkVisibility::ALL;
+            return;
+        }
+
+        let size = self.size;
+        let index = |c: U16Vec3| ((c.y * size + c.z) * size + c.x) as usize;
+        let mut visited = vec![false; size as usize * size as usize * size as usize];
+        let mut visibility = ChunkVisibility::NONE;
+
+        for start in self.block_coords() {
+            if visited[index(start)] || self.block(start).is_solid() {
+                continue;
+            }
+
+            let mut faces = 0u8;
+            let mut stack = vec![start];
+            visited[index(start)] = true;
+            while let Some(c) = stack.pop() {
+                faces |= self.boundary_faces(c);
+
+                for (axis, delta) in [(0, 1i32), (0, -1), (1, 1), (1, -1), (2, 1), (2, -1)] {
+                    let mut next = c.as_ivec3();
+                    next[axis] += delta;
+                    if next.min_element() < 0 || next.max_element() >= size as i32 {
+                        continue;
+                    }
+
+                    let next = next.as_u16vec3();
+                    if !visited[index(next)] && !self.block(next).is_solid() {
+                        visited[index(next)] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+            visibility.connect_all(faces);
+        }
+
+        self.visibility = visibility;
+    }
+
+    /// Flood fills block light out from light emitting blocks through non-solid blocks,
+    /// returning the light level of every lit block.
+    pub fn block_light(&self) -> HashMap<U16Vec3, u8> {
+        self.block_light_with(std::iter::empty())
+    }
+
+    /// Like `block_light`, but also spreads `incoming` light, such as light entering
+    /// through the chunk's border from its neighbours. Incoming light on solid blocks is
+    /// ignored.
+    pub fn block_light_with(
+        &self,
+        incoming: impl IntoIterator<Item = (U16Vec3, u8)>,
+    ) -> HashMap<U16Vec3, u8> {
+        let mut light: HashMap<U16Vec3, u8> = HashMap::new();
+        let mut queue: VecDeque<U16Vec3> = VecDeque::new();
+        for (coord, block) in self.blocks.iter() {
+            let emission = block.light_emission();
+            if emission > 0 {
+                light.insert(*coord, emission);
+                queue.push_back(*coord);
+            }
+        }
+        for (coord, level) in incoming {
+            if self.block(coord).is_solid() || light.get(&coord).is_some_and(|l| *l >= level) {
+                continue;
+            }
+            light.insert(coord, level);
+            queue.push_back(coord);
+        }
+
+        let size = self.size as i32;
+        while let Some(c) = queue.pop_front() {
+            let level = light[&c];
+            if level <= 1 {
+                continue;
+            }
+
+            for (axis, delta) in [(0, 1i32), (0, -1), (1, 1), (1, -1), (2, 1), (2, -1)] {
+                let mut next = c.as_ivec3();
+                next[axis] += delta;
+                if next.min_element() < 0 || next.max_element() >= size {
+                    continue;
+                }
+
+                let next = next.as_u16vec3();
+                if self.block(next).is_solid() || light.get(&next).is_some_and(|l| *l >= level - 1)
+                {
+                    continue;
+                }
+                light.insert(next, level - 1);
+                queue.push_back(next);
+            }
+        }
+
+        light
+    }
+
+    fn block_coords(&self) -> impl Iterator<Item = U16Vec3> {
+        let size = self.size;
+        (0..size).flat_map(move |y| {
+            (0..size).flat_map(move |z| (0..size).map(move |x| U16Vec3::new(x, y, z)))
+        })
+    }
+
+    /// Bitmask of the chunk faces a block lies on.
+    fn boundary_faces(&self, c: U16Vec3) -> u8 {
+        let max = self.size - 1;
+        let mut faces = 0;
+        for (face, on_face) in [
+            c.z == max,
+            c.z == 0,
+            c.x == max,
+            c.x == 0,
+            c.y == max,
+            c.y == 0,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if on_face {
+                faces |= 1 << face;
+            }
+        }
+        faces
+    }
+
+    /// Rough number of bytes this chunk's data takes up in memory.
+    pub fn memory_size(&self) -> usize {
+        // each palette entry also has a control byte in the hash table
+        let entry = std::mem::size_of::<(U16Vec3, BlockType)>() + 1;
+        std::mem::size_of::<Self>()
+            + self.blocks.capacity() * entry
+            + self.temperatures.capacity() * std::mem::size_of::<f32>()
+    }
+
+    pub fn temperatures(&self) -> &[f32] {
+        &self.temperatures
+    }
+
+    pub fn temperature_at(&self, x: u16, z: u16) -> f32 {
+        self.temperatures
+            .get((z * self.size + x) as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_temperature_at(&mut self, x: u16, z: u16, temperature: f32) {
+        if self.temperatures.is_empty() {
+            self.temperatures = vec![0.0; self.size as usize * self.size as usize];
+        }
+        self.temperatures[(z * self.size + x) as usize] = temperature;
     }
 }
 
-pub struct ChunkOctree {
+/// Number of chunks along each axis of a single octree region.
+const REGION_CHUNKS: i64 = 32;
+
+/// An octree covering a fixed cube of `REGION_CHUNKS` chunks, along with a cache of
+/// chunk coordinate to octree node for fast lookup.
+struct ChunkRegion {
     octree: Octree<ChunkData>,
     cache: HashMap<ChunkCoordinate, usize>,
+    chunk_count: usize,
+}
+
+/// Stores chunk data in a map of octree regions keyed by region coordinate, so that the
+/// world has no size limit and each octree only ever addresses a small local area.
+pub struct ChunkOctree {
+    regions: HashMap<I64Vec3, ChunkRegion>,
     pub chunk_size: u16,
+    /// Total memory taken by the stored chunk data, in bytes.
+    memory: usize,
 }
 
 impl Default for ChunkOctree {
     fn default() -> Self {
         let chunk_size = 16;
         Self {
-            octree: Octree::new(4096.0, 9),
-            cache: HashMap::new(),
+            regions: HashMap::new(),
             chunk_size,
+            memory: 0,
         }
     }
 }
 
 impl ChunkOctree {
     pub fn get_chunk_data(&mut self, coord: ChunkCoordinate) -> Option<Arc<ChunkData>> {
-        let octant = if self.cache.contains_key(&coord) {
-            self.octree.get_node_by_id(*self.cache.get(&coord).unwrap())
-        } else {
-            self.octree.query_octant(self.chunk_centre(coord))
+        let local_centre = self.region_local_centre(coord);
+        let region = self.regions.get_mut(&Self::region_coordinate(coord))?;
+
+        let octant = match region.cache.get(&coord) {
+            Some(id) => region.octree.get_node_by_id(*id),
+            None => region.octree.query_octant(local_centre),
         };
 
         let read = octant.read().unwrap();
-        self.cache.insert(coord, read.id());
+        region.cache.insert(coord, read.id());
         read.get_data()
     }
 
@@ -122,20 +385,69 @@ impl ChunkOctree {
         coord: ChunkCoordinate,
         chunk_data: ChunkData,
     ) -> Arc<ChunkData> {
-        let chunk_octant = self.octree.query_octant(self.chunk_centre(coord));
+        let local_centre = self.region_local_centre(coord);
+        let depth = self.region_depth();
+        let half_extent = self.region_half_extent();
+        let region = self
+            .regions
+            .entry(Self::region_coordinate(coord))
+            .or_insert_with(|| ChunkRegion {
+                octree: Octree::new(half_extent, depth),
+                cache: HashMap::new(),
+                chunk_count: 0,
+            });
+
+        let chunk_octant = region.octree.query_octant(local_centre);
 
         let chunk_data = Arc::new(chunk_data);
         let mut write = chunk_octant.write().unwrap();
+        match write.get_data() {
+            Some(previous) => self.memory -= previous.memory_size(),
+            None => region.chunk_count += 1,
+        }
+        self.memory += chunk_data.memory_size();
         write.set_data(chunk_data.clone());
+        region.cache.insert(coord, write.id());
         chunk_data
     }
 
     pub fn clear_chunk(&mut self, coord: ChunkCoordinate) {
-        let chunk_octant = self.octree.query_octant(self.chunk_centre(coord));
+        let local_centre = self.region_local_centre(coord);
+        let region_coord = Self::region_coordinate(coord);
+        let Some(region) = self.regions.get_mut(&region_coord) else {
+            return;
+        };
 
+        let chunk_octant = region.octree.query_octant(local_centre);
         let mut write = chunk_octant.write().unwrap();
+        if let Some(previous) = write.get_data() {
+            region.chunk_count -= 1;
+            self.memory -= previous.memory_size();
+        }
         write.clear_data();
-        self.cache.remove(&coord);
+        region.cache.remove(&coord);
+        drop(write);
+
+        if region.chunk_count == 0 {
+            self.regions.remove(&region_coord);
+        }
+    }
+
+    /// Every stored chunk along with its data, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkCoordinate, Arc<ChunkData>)> + '_ {
+        // every stored chunk is cached when it's set, alongside lookups of empty chunks
+        self.regions.values().flat_map(|region| {
+            region.cache.iter().filter_map(|(coord, id)| {
+                let octant = region.octree.get_node_by_id(*id);
+                let data = octant.read().unwrap().get_data()?;
+                Some((*coord, data))
+            })
+        })
+    }
+
+    /// Total memory taken by the stored chunk data, in bytes.
+    pub fn memory(&self) -> usize {
+        self.memory
     }
 
     pub fn chunk_centre(&self, chunk_coord: ChunkCoordinate) -> Vec3 {
@@ -146,6 +458,43 @@ impl ChunkOctree {
             chunk_coord.0.z as f32 * chunk_size + chunk_size / 2.0,
         )
     }
+
+    fn region_coordinate(chunk_coord: ChunkCoordinate) -> I64Vec3 {
+        chunk_coord.0.div_euclid(I64Vec3::splat(REGION_CHUNKS))
+    }
+
+    /// Octree depth at which a leaf covers exactly one chunk.
+    fn region_depth(&self) -> u8 {
+        REGION_CHUNKS.trailing_zeros() as u8
+    }
+
+    fn region_half_extent(&self) -> f32 {
+        (REGION_CHUNKS * self.chunk_size as i64) as f32 / 2.0
+    }
+
+    /// Centre of a chunk relative to the centre of its region. Computed with integers so
+    /// precision doesn't degrade far from the origin.
+    fn region_local_centre(&self, chunk_coord: ChunkCoordinate) -> Vec3 {
+        let chunk_size = self.chunk_size as i64;
+        let local_chunk = chunk_coord.0.rem_euclid(I64Vec3::splat(REGION_CHUNKS));
+        let local = local_chunk * chunk_size + chunk_size / 2 - REGION_CHUNKS * chunk_size / 2;
+        Vec3::new(local.x as f32, local.y as f32, local.z as f32)
+    }
+}
+
+/// A chunk filled with stone, shared by tests that need terrain blocking everything.
+#[cfg(test)]
+pub fn solid_chunk() -> ChunkData {
+    let mut chunk_data = ChunkData::default();
+    for y in 0..chunk_data.size {
+        for z in 0..chunk_data.size {
+            for x in 0..chunk_data.size {
+                chunk_data.set_block_at(U16Vec3::new(x, y, z), BlockType::Stone);
+            }
+        }
+    }
+    chunk_data.update_visibility();
+    chunk_data
 }
 
 #[cfg(test)]
@@ -154,20 +503,124 @@ mod tests {
 
     use crate::block::BlockType;
 
-    use super::{ChunkCoordinate, ChunkData, ChunkOctree};
+    use super::{
+        solid_chunk, ChunkCoordinate, ChunkData, ChunkOctree, ChunkSlices, ChunkVisibility,
+    };
+
+    #[test]
+    fn test_octree_iterates_stored_chunks() {
+        let mut octree = ChunkOctree::default();
+        let stored = [
+            ChunkCoordinate(I64Vec3::new(0, 0, 0)),
+            ChunkCoordinate(I64Vec3::new(-5, 2, 1)),
+            ChunkCoordinate(I64Vec3::new(100, 0, -100)),
+        ];
+        for coord in stored {
+            octree.set_chunk_data(coord, ChunkData::default());
+        }
+        // neither looked up empty chunks nor cleared ones are listed
+        octree.get_chunk_data(ChunkCoordinate(I64Vec3::new(1, 0, 0)));
+        octree.set_chunk_data(ChunkCoordinate(I64Vec3::new(2, 0, 0)), ChunkData::default());
+        octree.clear_chunk(ChunkCoordinate(I64Vec3::new(2, 0, 0)));
+
+        let mut chunks: Vec<_> = octree
+            .chunks()
+            .map(|(coord, _)| coord.0.to_array())
+            .collect();
+        chunks.sort();
+        let mut expected: Vec<_> = stored.iter().map(|coord| coord.0.to_array()).collect();
+        expected.sort();
+        assert_eq!(expected, chunks);
+    }
+
+    #[test]
+    fn test_octree_tracks_chunk_memory() {
+        let mut octree = ChunkOctree::default();
+        let coord = ChunkCoordinate(I64Vec3::new(3, -1, 40));
+
+        octree.set_chunk_data(coord, ChunkData::default());
+        let empty = octree.memory();
+        assert!(empty > 0);
+
+        let solid = solid_chunk();
+        let solid_size = solid.memory_size();
+        octree.set_chunk_data(coord, solid);
+        assert_eq!(solid_size, octree.memory());
+        assert!(solid_size > empty);
+
+        octree.clear_chunk(coord);
+        assert_eq!(0, octree.memory());
+    }
+
+    #[test]
+    fn test_set_air_removes_block() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 3), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 3), BlockType::Air);
+        assert!(chunk_data.empty());
+        assert_eq!(
+            Some(BlockType::Air),
+            chunk_data.get_block_at(U16Vec3::new(1, 2, 3))
+        );
+    }
+
+    #[test]
+    fn test_block_light_falls_off_and_is_blocked() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(2, 2, 2), BlockType::Lava);
+        chunk_data.set_block_at(U16Vec3::new(2, 2, 4), BlockType::Stone);
+
+        let light = chunk_data.block_light();
+        assert_eq!(Some(&15), light.get(&U16Vec3::new(2, 2, 2)));
+        assert_eq!(Some(&14), light.get(&U16Vec3::new(2, 2, 3)));
+        assert_eq!(Some(&12), light.get(&U16Vec3::new(5, 2, 2)));
+        assert_eq!(None, light.get(&U16Vec3::new(2, 2, 4)));
+        // light reaches behind the stone by going around it
+        assert_eq!(Some(&10), light.get(&U16Vec3::new(2, 2, 5)));
+    }
+
+    #[test]
+    fn test_visibility_empty_chunk_all_connected() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.update_visibility();
+        assert_eq!(ChunkVisibility::ALL, chunk_data.visibility());
+    }
+
+    #[test]
+    fn test_visibility_solid_chunk_none_connected() {
+        let mut chunk_data = solid_chunk();
+        chunk_data.update_visibility();
+        assert_eq!(ChunkVisibility::NONE, chunk_data.visibility());
+    }
+
+    #[test]
+    fn test_visibility_tunnel_connects_its_ends() {
+        let mut chunk_data = solid_chunk();
+        for x in 0..chunk_data.size {
+            chunk_data.set_block_at(U16Vec3::new(x, 5, 5), BlockType::Air);
+        }
+        chunk_data.update_visibility();
+
+        let visibility = chunk_data.visibility();
+        assert!(visibility.connected(2, 3));
+        assert!(visibility.connected(3, 2));
+        assert!(!visibility.connected(2, 4));
+        assert!(!visibility.connected(0, 1));
+    }
 
     #[test]
-    #[should_panic]
     fn test_get_block_at_checks_limit() {
-        let chunk_data = ChunkData::default();
-        chunk_data.get_block_at(U16Vec3::new(16, 0, 16));
+        let mut chunk_data = ChunkData::default();
+        assert_eq!(None, chunk_data.get_block_at(U16Vec3::new(16, 0, 16)));
+        assert!(!chunk_data.set_block_at(U16Vec3::new(0, 16, 0), BlockType::Stone));
+        assert!(chunk_data.empty());
     }
 
     #[test]
     fn test_get_block_at_returns_air_when_empty() {
         let chunk_data = ChunkData::default();
         let block = chunk_data.get_block_at(U16Vec3::new(4, 12, 5));
-        assert_eq!(BlockType::Air, block);
+        assert_eq!(Some(BlockType::Air), block);
     }
 
     #[test]
@@ -204,15 +657,57 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            BlockType::Sand,
+            Some(BlockType::Sand),
             queried_chunk_data.get_block_at(U16Vec3::new(5, 4, 9))
         );
         assert_eq!(
-            BlockType::Air,
+            Some(BlockType::Air),
             queried_chunk_data.get_block_at(U16Vec3::new(0, 4, 9))
         );
     }
 
+    #[test]
+    fn test_set_get_chunk_data_far_from_origin() {
+        let mut octree = ChunkOctree::default();
+
+        let coords = [
+            ChunkCoordinate(I64Vec3::new(100_000, 0, 0)),
+            ChunkCoordinate(I64Vec3::new(100_001, 0, 0)),
+            ChunkCoordinate(I64Vec3::new(-100_000, 5, -3)),
+            ChunkCoordinate(I64Vec3::new(-100_000, 5, -4)),
+        ];
+        for (i, coord) in coords.iter().enumerate() {
+            let mut chunk_data = ChunkData::default();
+            chunk_data.set_block_at(U16Vec3::new(i as u16, 0, 0), BlockType::Stone);
+            octree.set_chunk_data(*coord, chunk_data);
+        }
+
+        for (i, coord) in coords.iter().enumerate() {
+            let chunk_data = octree.get_chunk_data(*coord).unwrap();
+            assert_eq!(1, chunk_data.blocks.len());
+            assert_eq!(
+                Some(BlockType::Stone),
+                chunk_data.get_block_at(U16Vec3::new(i as u16, 0, 0))
+            );
+        }
+        assert!(octree
+            .get_chunk_data(ChunkCoordinate(I64Vec3::new(100_002, 0, 0)))
+            .is_none());
+    }
+
+    #[test]
+    fn test_clear_chunk_removes_empty_region() {
+        let mut octree = ChunkOctree::default();
+
+        let coord = ChunkCoordinate(I64Vec3::new(40, -2, 7));
+        octree.set_chunk_data(coord, ChunkData::default());
+        assert_eq!(1, octree.regions.len());
+
+        octree.clear_chunk(coord);
+        assert!(octree.regions.is_empty());
+        assert!(octree.get_chunk_data(coord).is_none());
+    }
+
     #[test]
     fn test_chunk_centre() {
         let octree = ChunkOctree::default();
@@ -230,4 +725,25 @@ mod tests {
             octree.chunk_centre(ChunkCoordinate(I64Vec3::new(-43, 22, -100)))
         )
     }
+
+    #[test]
+    fn test_slices_around_edit() {
+        // the middle of a slice only affects that slice
+        let slices = ChunkSlices::around(5, 16);
+        assert!(slices.contains(5));
+        assert!(!slices.contains(3));
+        assert!(!slices.contains(8));
+
+        // the edge of a slice also affects the neighbouring slice
+        let slices = ChunkSlices::around(4, 16);
+        assert!(slices.contains(3));
+        assert!(slices.contains(4));
+        assert!(!slices.contains(8));
+
+        // edits just outside the chunk only affect the nearest row
+        assert_eq!(ChunkSlices::around(0, 16), ChunkSlices::around(-1, 16));
+        assert_eq!(ChunkSlices::around(15, 16), ChunkSlices::around(16, 16));
+        assert_eq!(ChunkSlices::NONE, ChunkSlices::around(-2, 16));
+        assert_eq!(ChunkSlices::NONE, ChunkSlices::around(17, 16));
+    }
 }
@@ -1,107 +1,585 @@
 use std::{
-    collections::{HashMap, HashSet},
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
     vec::IntoIter,
 };
 
 use bevy::{
+    app::AppExit,
     asset::{Assets, Handle},
     ecs::{
+        bundle::Bundle,
         component::Component,
         entity::Entity,
-        query::{With, Without},
-        system::{Commands, Query, ResMut, Resource},
+        event::EventReader,
+        query::{Has, With, Without},
+        system::{Commands, Query, Res, ResMut, Resource},
     },
-    hierarchy::Parent,
-    math::{I64Vec3, Vec3},
+    hierarchy::{BuildChildren, Children, DespawnRecursiveExt, HierarchyQueryExt},
+    input::{keyboard::KeyCode, ButtonInput},
+    log::{error, info},
+    math::{I64Vec3, IVec3, Vec3},
     pbr::MaterialMeshBundle,
     render::{camera::Camera, mesh::Mesh, primitives::Aabb, view::Visibility},
-    tasks::{AsyncComputeTaskPool, Task},
+    tasks::{AsyncComputeTaskPool, Task, TaskPool, TaskPoolBuilder},
+    time::Time,
     transform::components::{GlobalTransform, Transform},
     utils::futures,
 };
 use priority_queue::PriorityQueue;
+use serde::Deserialize;
 
 use super::{
-    chunk::{ChunkCoordinate, ChunkData},
-    generate::generator::{generate_chunk, generate_chunk_mesh},
-    material::ChunkMaterial,
+    chunk::{opposite_face, ChunkCoordinate, ChunkData, ChunkSlices, CHUNK_SIZE},
+    generate::generator::{
+        generate_chunk, generate_chunk_geometry, plant_mesh, sort_quads_back_to_front,
+        ChunkGeometry, ChunkMeshes,
+    },
+    material::{ChunkMaterial, PlantMaterial, RenderStyle},
+};
+use crate::{
+    block::BlockType,
+    settings::RendererSettings,
+    world::{World, WorldError},
 };
-use crate::{player::PlayerLook, world::World};
 
 #[derive(Component)]
 pub struct Chunk {
     coord: ChunkCoordinate,
 }
 
+impl Chunk {
+    pub fn coord(&self) -> ChunkCoordinate {
+        self.coord
+    }
+}
+
 #[derive(Component)]
 pub struct DirtyChunk {}
 
 #[derive(Component)]
 pub struct GenerateChunkData {
-    task: Task<ChunkData>,
+    task: Task<Result<ChunkData, WorldError>>,
 }
 
 #[derive(Component)]
 pub struct GenerateChunkMesh {
     coord: ChunkCoordinate,
-    task: Option<Task<Mesh>>,
+    /// Slices of the chunk that changed since it was last meshed.
+    slices: ChunkSlices,
+    task: Option<Task<Result<(ChunkMeshes, ChunkGeometry), WorldError>>>,
+}
+
+/// The geometry a chunk was last meshed with, reused when only some of its slices change.
+/// Taken by the mesh task while the chunk is being re-meshed.
+#[derive(Component)]
+pub struct ChunkGeometryCache(Option<ChunkGeometry>);
+
+/// A chunk fading in after first being meshed. It renders with its own copies of the chunk
+/// materials until the fade completes, then switches back to the shared materials.
+#[derive(Component)]
+pub struct ChunkFade {
+    material: Handle<ChunkMaterial>,
+    translucent_material: Handle<ChunkMaterial>,
+    started_at: f32,
+}
+
+/// A child of a chunk holding its translucent blocks, which are re-sorted back to front
+/// as the camera moves.
+#[derive(Component, Default)]
+pub struct TranslucentChunkMesh {
+    /// Where the camera was when the mesh was last sorted, or `None` before it's sorted.
+    sorted_for: Option<SortKey>,
+}
+
+/// Distance in blocks from a translucent mesh's bounds within which it is re-sorted every
+/// time the camera moves into another block.
+const NEAR_SORT_DISTANCE: f32 = CHUNK_SIZE as f32;
+
+/// Where the camera is relative to a translucent mesh, as far as sorting the mesh goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    /// The camera's block, in the mesh's space, while the camera is near the mesh and the
+    /// order of its quads changes with every step.
+    Near(IVec3),
+    /// Which side of the mesh's bounds the camera is on along each axis, as -1, 0 or 1.
+    /// The order of a far away mesh's quads hardly changes until the camera crosses one.
+    Side(IVec3),
+}
+
+impl SortKey {
+    fn new(viewer: Vec3, aabb: &Aabb) -> Self {
+        let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+        let near = viewer.cmpge(min - NEAR_SORT_DISTANCE).all()
+            && viewer.cmple(max + NEAR_SORT_DISTANCE).all();
+        if near {
+            Self::Near(viewer.round().as_ivec3())
+        } else {
+            let side = |v: f32, min: f32, max: f32| {
+                if v < min {
+                    -1
+                } else if v > max {
+                    1
+                } else {
+                    0
+                }
+            };
+            Self::Side(IVec3::new(
+                side(viewer.x, min.x, max.x),
+                side(viewer.y, min.y, max.y),
+                side(viewer.z, min.z, max.z),
+            ))
+        }
+    }
 }
 
 #[derive(Resource)]
 pub struct ChunkLoader {
-    render_distance: u32,
+    settings: RendererSettings,
     chunk_to_entity: HashMap<ChunkCoordinate, Entity>,
     chunk_iterator: ChunkIterator,
     material: Handle<ChunkMaterial>,
+    translucent_material: Handle<ChunkMaterial>,
+    plant_material: Handle<PlantMaterial>,
+    /// Plant models shared between every plant of the same block and quantised colour, so
+    /// plants are drawn as instances of a few meshes.
+    plant_meshes: HashMap<(BlockType, [u8; 4]), Handle<Mesh>>,
+    /// Slices of chunks that have been edited but not yet re-meshed.
+    dirty_slices: HashMap<ChunkCoordinate, ChunkSlices>,
+    visible_chunks: HashSet<ChunkCoordinate>,
+    visibility_camera_chunk: Option<ChunkCoordinate>,
+    visibility_dirty: bool,
+    visibility_updated_at: f32,
+    /// Chunk generation tasks that have been spawned but not yet collected.
+    generation_tasks: usize,
+    /// Chunk meshing tasks that have been spawned but not yet collected.
+    meshing_tasks: usize,
+    /// Hidden entities left behind by unloaded chunks, reused for newly loaded ones.
+    pool: Vec<Entity>,
+    /// Distance chunks are loaded to while chunk data is over its memory budget.
+    memory_distance: Option<u32>,
+    /// Smoothed camera velocity in blocks per second.
+    camera_velocity: Vec3,
+    last_camera_position: Option<Vec3>,
+}
+
+/// Threads chunks are meshed on, kept apart from the pool chunks are generated on so that
+/// a flood of generation tasks can't hold up meshing. Without it chunks are meshed on the
+/// async compute pool alongside generation.
+#[derive(Resource)]
+pub struct MeshingTaskPool(TaskPool);
+
+impl MeshingTaskPool {
+    /// A pool with `threads` threads, or `None` if there are none to set aside.
+    pub fn new(threads: usize) -> Option<Self> {
+        (threads > 0).then(|| {
+            Self(
+                TaskPoolBuilder::new()
+                    .num_threads(threads)
+                    .thread_name("Chunk Meshing Task Pool".to_string())
+                    .build(),
+            )
+        })
+    }
 }
 
-const MAX_CHUNKS_PER_FRAME: usize = 32;
+/// Extra chunks generated beyond render distance, so every chunk within render distance
+/// has the neighbours it needs to be meshed.
+const GENERATION_MARGIN: u32 = 1;
+const RELOAD_CHUNKS_KEY: KeyCode = KeyCode::F5;
+const REMESH_CHUNKS_KEY: KeyCode = KeyCode::F6;
+
+/// Seconds a newly loaded chunk takes to fade in.
+const CHUNK_FADE_DURATION: f32 = 0.5;
+pub const MAX_CHUNKS_PER_FRAME: usize = 32;
+/// Levels each channel of a plant's colour is rounded to, trading colour accuracy for
+/// fewer plant meshes.
+const PLANT_COLOR_LEVELS: u8 = 16;
+/// Most unloaded chunk entities kept around for reuse. Any more are despawned.
+const MAX_POOLED_CHUNKS: usize = 1024;
+
+/// Fraction of the chunk memory budget below which the load distance is allowed to grow
+/// back, so it doesn't flip between growing and shrinking at the budget.
+const MEMORY_RELAX_FRACTION: f32 = 0.75;
+const BYTES_PER_MEGABYTE: usize = 1024 * 1024;
+
+/// How quickly the smoothed camera velocity follows the camera, so a single long frame or
+/// a teleport doesn't count as moving fast.
+const CAMERA_VELOCITY_EASING: f32 = 4.0;
+
+/// Minimum time between occlusion updates caused by newly generated chunks.
+const VISIBILITY_UPDATE_INTERVAL: f32 = 0.25;
 
 impl ChunkLoader {
-    pub fn new(render_distance: u32, material: Handle<ChunkMaterial>) -> Self {
+    pub fn new(
+        settings: RendererSettings,
+        material: Handle<ChunkMaterial>,
+        translucent_material: Handle<ChunkMaterial>,
+        plant_material: Handle<PlantMaterial>,
+    ) -> Self {
         Self {
-            render_distance,
+            settings,
             chunk_to_entity: HashMap::new(),
-            chunk_iterator: ChunkIterator::new(),
+            chunk_iterator: ChunkIterator::with_strategy(settings.chunk_load_strategy),
             material,
+            translucent_material,
+            plant_material,
+            plant_meshes: HashMap::new(),
+            dirty_slices: HashMap::new(),
+            visible_chunks: HashSet::new(),
+            visibility_camera_chunk: None,
+            visibility_dirty: false,
+            visibility_updated_at: 0.0,
+            generation_tasks: 0,
+            meshing_tasks: 0,
+            pool: vec![],
+            memory_distance: None,
+            camera_velocity: Vec3::ZERO,
+            last_camera_position: None,
+        }
+    }
+
+    /// Distance in chunks around the camera that chunk data is generated for.
+    fn generation_distance(&self) -> u32 {
+        let render_distance = if self.moving_fast() {
+            self.settings
+                .fast_render_distance
+                .min(self.settings.render_distance)
+        } else {
+            self.settings.render_distance
+        };
+        let distance = render_distance + GENERATION_MARGIN;
+        self.memory_distance
+            .map_or(distance, |limit| limit.min(distance))
+    }
+
+    /// Distance in chunks around the camera beyond which loaded chunks are unloaded. While
+    /// over the memory budget chunks go as soon as they leave the generation distance, so
+    /// that memory can come down.
+    fn unload_distance(&self) -> u32 {
+        match self.memory_distance {
+            Some(_) => self.generation_distance(),
+            None => self.generation_distance() + self.settings.unload_margin,
+        }
+    }
+
+    /// Whether the camera is moving faster than the loader can keep up with.
+    fn moving_fast(&self) -> bool {
+        self.settings.fast_camera_speed > 0.0
+            && self.camera_velocity.length() > self.settings.fast_camera_speed
+    }
+
+    /// Tracks the camera's velocity. Returns whether it started or stopped moving fast.
+    fn update_camera_velocity(&mut self, position: Vec3, delta_seconds: f32) -> bool {
+        let was_fast = self.moving_fast();
+        if let Some(last) = self.last_camera_position.filter(|_| delta_seconds > 0.0) {
+            let velocity = (position - last) / delta_seconds;
+            let easing = (CAMERA_VELOCITY_EASING * delta_seconds).min(1.0);
+            self.camera_velocity += (velocity - self.camera_velocity) * easing;
+        }
+        self.last_camera_position = Some(position);
+        was_fast != self.moving_fast()
+    }
+
+    /// Pulls the generation distance in to just inside the farthest loaded chunk while
+    /// chunk data takes up more than its memory budget, and lets it back out a chunk at a
+    /// time once loading has caught up and memory is well under the budget.
+    fn update_memory_distance(&mut self, memory: usize, farthest: u32) {
+        let previous = self.memory_distance;
+        let budget = self.settings.max_chunk_memory as usize * BYTES_PER_MEGABYTE;
+        if budget == 0 {
+            self.memory_distance = None;
+        } else if memory > budget {
+            let limit = farthest.saturating_sub(1).min(self.generation_distance());
+            self.memory_distance = Some(limit);
+        } else if (memory as f32) < budget as f32 * MEMORY_RELAX_FRACTION {
+            let full_distance = self.settings.render_distance + GENERATION_MARGIN;
+            self.memory_distance = self
+                .memory_distance
+                .map(|limit| if farthest >= limit { limit + 1 } else { limit })
+                .filter(|limit| *limit < full_distance);
+        }
+
+        // chunks evicted to get under the budget were already handed out, and those queued
+        // at the old distance would be evicted as soon as they load, so search again
+        if self.memory_distance != previous {
+            let iterator = &mut self.chunk_iterator;
+            iterator.reset(iterator.camera_chunk, iterator.camera_forward);
+        }
+    }
+
+    /// Forgets every loaded chunk so loading starts again from scratch. The chunk
+    /// entities must be despawned separately, while pooled entities are kept for reuse.
+    pub fn reset(&mut self) {
+        self.chunk_to_entity.clear();
+        self.chunk_iterator = ChunkIterator::with_strategy(self.settings.chunk_load_strategy);
+        self.dirty_slices.clear();
+        self.visible_chunks.clear();
+        self.visibility_camera_chunk = None;
+        self.visibility_dirty = true;
+        // dropping the chunk entities cancels their tasks
+        self.generation_tasks = 0;
+        self.meshing_tasks = 0;
+        self.memory_distance = None;
+        self.camera_velocity = Vec3::ZERO;
+        self.last_camera_position = None;
+    }
+
+    /// Loads or generates a chunk and meshes it straight away rather than waiting its turn,
+    /// for gameplay that needs it present immediately, like teleporting. The mesh is added
+    /// to the chunk directly, skipping the meshing task cap and upload budget, so it is
+    /// drawn on the frame that asked for it. A chunk the loader already has keeps its place,
+    /// as its data is present for collisions from now on whatever its state.
+    pub fn force_load_chunk(
+        &mut self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        world: &mut World,
+        coord: ChunkCoordinate,
+    ) -> Result<(), WorldError> {
+        let data = world.force_load_chunk(coord)?;
+        if self.chunk_to_entity.contains_key(&coord) {
+            return Ok(());
+        }
+
+        let entity = spawn_chunk_entity(commands, self, Chunk { coord });
+        self.chunk_to_entity.insert(coord, entity);
+        self.visibility_dirty = true;
+        if data.empty() {
+            return Ok(());
+        }
+
+        let adjacent = world.adjacent_chunk_data(coord);
+        let geometry = generate_chunk_geometry(data, adjacent, None, ChunkSlices::ALL);
+        let chunk_meshes = geometry.to_meshes(self.settings);
+        // shown straight away rather than faded in, as the player is about to stand on it
+        let materials = (
+            self.material.clone_weak(),
+            self.translucent_material.clone_weak(),
+        );
+        self.insert_chunk_meshes(
+            commands,
+            meshes,
+            entity,
+            coord,
+            (chunk_meshes, geometry),
+            materials,
+        );
+        Ok(())
+    }
+
+    /// Gives a chunk entity its meshes, drawn with the given opaque and translucent
+    /// materials. Large meshes are split up, with every part after the first spawned as a
+    /// child.
+    fn insert_chunk_meshes(
+        &mut self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        entity: Entity,
+        coord: ChunkCoordinate,
+        (chunk_meshes, geometry): (ChunkMeshes, ChunkGeometry),
+        (material, translucent_material): (Handle<ChunkMaterial>, Handle<ChunkMaterial>),
+    ) {
+        let ChunkMeshes {
+            mut opaque,
+            translucent,
+            plants,
+        } = chunk_meshes;
+        let (t, _) = chunk_components(coord);
+
+        let mesh = opaque.remove(0);
+        commands.entity(entity).despawn_descendants().insert((
+            ChunkGeometryCache(Some(geometry)),
+            chunk_mesh_aabb(&mesh),
+            MaterialMeshBundle {
+                mesh: meshes.add(mesh),
+                material: material.clone(),
+                transform: t,
+                ..Default::default()
+            },
+        ));
+        for sub_mesh in opaque {
+            let child = commands
+                .spawn((
+                    chunk_mesh_aabb(&sub_mesh),
+                    MaterialMeshBundle {
+                        mesh: meshes.add(sub_mesh),
+                        material: material.clone(),
+                        ..Default::default()
+                    },
+                ))
+                .id();
+            commands.entity(entity).add_child(child);
+        }
+        for sub_mesh in translucent {
+            let child = commands
+                .spawn((
+                    chunk_mesh_aabb(&sub_mesh),
+                    MaterialMeshBundle {
+                        mesh: meshes.add(sub_mesh),
+                        material: translucent_material.clone(),
+                        ..Default::default()
+                    },
+                    TranslucentChunkMesh::default(),
+                ))
+                .id();
+            commands.entity(entity).add_child(child);
+        }
+        for plant in plants {
+            let child = commands
+                .spawn(MaterialMeshBundle {
+                    mesh: self.plant_mesh(meshes, plant.block, plant.color),
+                    material: self.plant_material.clone_weak(),
+                    transform: Transform::from_translation(plant.position),
+                    ..Default::default()
+                })
+                .id();
+            commands.entity(entity).add_child(child);
+        }
+    }
+
+    /// Queues the slices of every chunk whose mesh depends on a block to be re-meshed.
+    pub fn mark_block_dirty(&mut self, commands: &mut Commands, world: &World, block: I64Vec3) {
+        self.mark_blocks_dirty(commands, world, &[block]);
+    }
+
+    /// Queues the slices of every chunk whose mesh depends on any of the blocks to be
+    /// re-meshed, marking each chunk only once however many of the blocks it's next to.
+    pub fn mark_blocks_dirty(
+        &mut self,
+        commands: &mut Commands,
+        world: &World,
+        blocks: &[I64Vec3],
+    ) {
+        for (chunk, slices) in affected_slices(world, blocks) {
+            self.mark_slices_dirty(commands, chunk, slices);
+        }
+    }
+
+    /// Queues a whole chunk to be re-meshed.
+    pub fn mark_chunk_dirty(&mut self, commands: &mut Commands, coord: ChunkCoordinate) {
+        self.mark_slices_dirty(commands, coord, ChunkSlices::ALL);
+    }
+
+    /// Sets the render style chunks are meshed for. Loaded chunks keep their meshes until
+    /// they are re-meshed.
+    pub fn set_render_style(&mut self, style: RenderStyle) {
+        self.settings.render_style = style;
+    }
+
+    fn mark_slices_dirty(
+        &mut self,
+        commands: &mut Commands,
+        coord: ChunkCoordinate,
+        slices: ChunkSlices,
+    ) {
+        if let Some(entity) = self.chunk_to_entity.get(&coord) {
+            commands.entity(*entity).try_insert(DirtyChunk {});
+            let dirty = self.dirty_slices.entry(coord).or_insert(ChunkSlices::NONE);
+            *dirty = dirty.union(slices);
+            self.visibility_dirty = true;
         }
     }
+
+    /// The shared model for a plant, quantising its colour so that similar plants share one.
+    fn plant_mesh(
+        &mut self,
+        meshes: &mut Assets<Mesh>,
+        block: BlockType,
+        color: [f32; 4],
+    ) -> Handle<Mesh> {
+        let levels = PLANT_COLOR_LEVELS as f32;
+        let quantised = color.map(|c| (c.clamp(0.0, 1.0) * levels).round() as u8);
+        self.plant_meshes
+            .entry((block, quantised))
+            .or_insert_with(|| meshes.add(plant_mesh(block, quantised.map(|c| c as f32 / levels))))
+            .clone()
+    }
+
+    /// Drops plant models that no plant uses any more, so the cache doesn't keep every model
+    /// ever made alive. Despawning is deferred, so the models of plants unloaded this frame
+    /// are dropped on a later call.
+    fn evict_unused_plant_meshes(&mut self) {
+        self.plant_meshes.retain(|_, handle| match handle {
+            Handle::Strong(handle) => Arc::strong_count(handle) > 1,
+            Handle::Weak(_) => false,
+        });
+    }
+
+    /// Whether a chunk hasn't been culled as unreachable from the camera.
+    pub fn is_visible(&self, coord: ChunkCoordinate) -> bool {
+        !self.settings.occlusion_culling || self.visible_chunks.contains(&coord)
+    }
+
+    /// Whether a chunk is within render distance and in front of the camera, meaning the
+    /// loader will generate and mesh it without the camera turning. The camera's own chunk
+    /// and the column under it count whichever way the camera faces, as they hold the
+    /// ground the player stands on.
+    pub fn in_view(&self, coord: ChunkCoordinate, world: &World) -> bool {
+        let camera_chunk = self.chunk_iterator.camera_chunk;
+        let under_camera = coord.0.x == camera_chunk.0.x
+            && coord.0.z == camera_chunk.0.z
+            && coord.0.y <= camera_chunk.0.y;
+        chunk_distance(coord, camera_chunk) <= self.settings.render_distance
+            && (under_camera || self.chunk_iterator.dot(coord, world) >= 0.0)
+    }
 }
 
 pub fn gather_chunks(
     mut commands: Commands,
+    time: Res<Time>,
     mut chunk_loader: ResMut<ChunkLoader>,
     mut world: ResMut<World>,
-    camera_query: Query<(&Parent, &GlobalTransform), (With<Camera>, Without<PlayerLook>)>,
-    generating_chunks_query: Query<&Chunk, With<GenerateChunkData>>,
+    camera_query: Query<&GlobalTransform, With<Camera>>,
 ) {
-    if generating_chunks_query.iter().count() > 1024 {
-        return;
-    }
-
-    let (_, camera) = camera_query.get_single().expect("could not find camera");
-
+    let camera = camera_query.get_single().expect("could not find camera");
     let camera_pos = camera.translation();
+    let speed_changed = chunk_loader.update_camera_velocity(camera_pos, time.delta_seconds());
+
     let camera_chunk = world.block_to_chunk_coordinate(I64Vec3::new(
         camera_pos.x as i64,
         camera_pos.y as i64,
         camera_pos.z as i64,
     ));
 
-    let camera_forward = camera.forward();
-    chunk_loader
-        .chunk_iterator
-        .update(camera_chunk, camera_forward);
+    // when moving fast, load ahead of where the camera is going rather than where it looks
+    let camera_forward = if chunk_loader.moving_fast() {
+        chunk_loader.camera_velocity.normalize()
+    } else {
+        camera.forward()
+    };
+    if speed_changed {
+        // the search stops at the generation distance, so restart it when that changes
+        chunk_loader
+            .chunk_iterator
+            .reset(camera_chunk, camera_forward);
+    } else {
+        chunk_loader
+            .chunk_iterator
+            .update(camera_chunk, camera_forward);
+    }
 
-    let distance = chunk_loader.render_distance;
+    // hold back until enough in-flight generation tasks finish
+    let capacity = chunk_loader
+        .settings
+        .max_generation_tasks
+        .saturating_sub(chunk_loader.generation_tasks)
+        .min(MAX_CHUNKS_PER_FRAME);
+    if capacity == 0 {
+        return;
+    }
+
+    let distance = chunk_loader.generation_distance();
 
     let mut next_chunks: Vec<ChunkCoordinate> = vec![];
-    while next_chunks.len() < MAX_CHUNKS_PER_FRAME {
-        if let Some(next) =
-            chunk_loader
-                .chunk_iterator
-                .next_chunks(MAX_CHUNKS_PER_FRAME, distance, &mut world)
-        {
+    while next_chunks.len() < capacity {
+        if let Some(next) = chunk_loader.chunk_iterator.next_chunks(
+            capacity - next_chunks.len(),
+            distance,
+            &mut world,
+        ) {
             next_chunks
                 .extend(next.filter(|chunk| !chunk_loader.chunk_to_entity.contains_key(chunk)));
         } else {
@@ -128,28 +606,89 @@ fn generate_single_chunk(
     task_pool: &AsyncComputeTaskPool,
     chunk_loader: &mut ResMut<ChunkLoader>,
 ) {
+    // chunks generated ahead of the loader, like those around the spawn, only need meshing
+    if let Some(data) = world.get_chunk_data(coord) {
+        let entity = spawn_chunk_entity(commands, chunk_loader, Chunk { coord });
+        if !data.empty() {
+            commands.entity(entity).insert(DirtyChunk {});
+        }
+        chunk_loader.chunk_to_entity.insert(coord, entity);
+        chunk_loader.visibility_dirty = true;
+        return;
+    }
+
     let noise_generator = world.noise_generator.clone();
+    // saved chunks hold edits, so they are loaded rather than generated
+    let save = world
+        .save
+        .clone()
+        .filter(|_| world.chunk_exists_on_disk(coord));
     let height = world.height;
-    let entity = commands
-        .spawn((
-            Chunk { coord },
-            GenerateChunkData {
-                task: task_pool
-                    .spawn(async move { generate_chunk(noise_generator, coord, height) }),
-            },
-        ))
-        .id();
+    let terrain = world.terrain;
+    let bundle = (
+        Chunk { coord },
+        GenerateChunkData {
+            task: task_pool.spawn(async move {
+                run_chunk_task(|| {
+                    if let Some(chunk_data) = save
+                        .map(|save| save.load_chunk(coord))
+                        .transpose()
+                        .map_err(WorldError::Io)?
+                        .flatten()
+                    {
+                        return Ok(chunk_data);
+                    }
+                    generate_chunk(noise_generator, coord, height, terrain)
+                })
+            }),
+        },
+    );
+    let entity = spawn_chunk_entity(commands, chunk_loader, bundle);
     chunk_loader.chunk_to_entity.insert(coord, entity);
+    chunk_loader.generation_tasks += 1;
+}
+
+/// Spawns a chunk entity, reusing one from the pool if there are any.
+fn spawn_chunk_entity(
+    commands: &mut Commands,
+    chunk_loader: &mut ChunkLoader,
+    bundle: impl Bundle,
+) -> Entity {
+    match chunk_loader.pool.pop() {
+        Some(entity) => {
+            commands.entity(entity).insert(bundle);
+            entity
+        }
+        None => commands.spawn(bundle).id(),
+    }
 }
 
 pub fn generate_chunks(
     mut commands: Commands,
     mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
     mut chunks_query: Query<(Entity, &mut Chunk, &mut GenerateChunkData)>,
 ) {
     for (entity, chunk, mut gen_chunk) in chunks_query.iter_mut() {
-        if let Some(chunk_data) = futures::check_ready(&mut gen_chunk.task) {
-            let data = world.insert_chunk(chunk.coord, chunk_data);
+        if let Some(result) = futures::check_ready(&mut gen_chunk.task) {
+            chunk_loader.generation_tasks -= 1;
+            let chunk_data = match result {
+                Ok(chunk_data) => chunk_data,
+                Err(e) => {
+                    error!("failed to generate chunk {:?}: {}", chunk.coord.0, e);
+                    commands.entity(entity).despawn_recursive();
+                    chunk_loader.chunk_to_entity.remove(&chunk.coord);
+                    continue;
+                }
+            };
+
+            chunk_loader.visibility_dirty = true;
+            // data loaded while the task ran, e.g. around a respawn, may have been edited
+            // since, so it wins over the task's
+            let data = match world.get_chunk_data(chunk.coord) {
+                Some(data) => data,
+                None => world.insert_chunk(chunk.coord, chunk_data),
+            };
             if !data.empty() {
                 commands.entity(entity).insert(DirtyChunk {});
             }
@@ -158,9 +697,83 @@ pub fn generate_chunks(
     }
 }
 
+/// Recomputes which chunks could be visible from the camera whenever the camera changes
+/// chunk, or periodically while new chunks are being generated.
+pub fn update_chunk_visibility(
+    time: Res<Time>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    mut world: ResMut<World>,
+) {
+    if !chunk_loader.settings.occlusion_culling {
+        return;
+    }
+
+    let camera_chunk = chunk_loader.chunk_iterator.camera_chunk;
+    let camera_moved = chunk_loader.visibility_camera_chunk != Some(camera_chunk);
+    let interval_elapsed =
+        time.elapsed_seconds() - chunk_loader.visibility_updated_at >= VISIBILITY_UPDATE_INTERVAL;
+    let should_update = camera_moved || (chunk_loader.visibility_dirty && interval_elapsed);
+    if !should_update {
+        return;
+    }
+
+    let distance = chunk_loader.settings.render_distance;
+    chunk_loader.visible_chunks = reachable_chunks(camera_chunk, distance, &mut world);
+    chunk_loader.visibility_camera_chunk = Some(camera_chunk);
+    chunk_loader.visibility_dirty = false;
+    chunk_loader.visibility_updated_at = time.elapsed_seconds();
+}
+
+/// Flood fills outwards from the camera chunk, only passing between chunks through faces
+/// that are connected by non-solid blocks. Chunks that are never reached are enclosed by
+/// solid terrain and can't be seen from the camera. Ungenerated chunks are treated as
+/// reachable but are not traversed.
+fn reachable_chunks(
+    camera_chunk: ChunkCoordinate,
+    max_distance: u32,
+    world: &mut World,
+) -> HashSet<ChunkCoordinate> {
+    // faces each chunk has been entered through, so a chunk is revisited when reached
+    // through a face that may connect to different exits
+    let mut entered: HashMap<ChunkCoordinate, u8> = HashMap::new();
+    let mut queue = VecDeque::from([(camera_chunk, None)]);
+    entered.insert(camera_chunk, 0b111111);
+
+    while let Some((coord, entry_face)) = queue.pop_front() {
+        let Some(chunk_data) = world.get_chunk_data(coord) else {
+            continue;
+        };
+        let visibility = chunk_data.visibility();
+
+        for (exit_face, neighbour) in coord.adjacent().into_iter().enumerate() {
+            if let Some(entry_face) = entry_face {
+                if exit_face == entry_face || !visibility.connected(entry_face, exit_face) {
+                    continue;
+                }
+            }
+
+            if chunk_distance(neighbour, camera_chunk) > max_distance {
+                continue;
+            }
+
+            let neighbour_entry = opposite_face(exit_face);
+            let faces = entered.entry(neighbour).or_insert(0);
+            if *faces & (1 << neighbour_entry) != 0 {
+                continue;
+            }
+            *faces |= 1 << neighbour_entry;
+            queue.push_back((neighbour, Some(neighbour_entry)));
+        }
+    }
+
+    entered.into_keys().collect()
+}
+
+#[allow(clippy::type_complexity)]
 pub fn mark_chunks(
     mut commands: Commands,
     mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
     mut chunks_query: Query<
         (Entity, &mut Chunk),
         (
@@ -171,14 +784,26 @@ pub fn mark_chunks(
     >,
 ) {
     chunks_query.iter_mut().for_each(|(entity, chunk)| {
-        if chunk
-            .coord
-            .adjacent()
-            .into_iter()
-            .all(|adj| world.is_chunk_generated(adj))
+        // chunks in the generation margin only exist to provide neighbours for meshing
+        let in_render_distance =
+            chunk_distance(chunk.coord, chunk_loader.chunk_iterator.camera_chunk)
+                <= chunk_loader.settings.render_distance;
+        if in_render_distance
+            && chunk_loader.is_visible(chunk.coord)
+            && chunk
+                .coord
+                .adjacent()
+                .into_iter()
+                .all(|adj| world.is_chunk_generated(adj))
         {
+            // chunks marked dirty without an edit, e.g. when first generated, mesh in full
+            let slices = chunk_loader
+                .dirty_slices
+                .remove(&chunk.coord)
+                .unwrap_or(ChunkSlices::ALL);
             commands.entity(entity).insert(GenerateChunkMesh {
                 coord: chunk.coord,
+                slices,
                 task: None,
             });
             commands.entity(entity).remove::<DirtyChunk>();
@@ -186,30 +811,69 @@ pub fn mark_chunks(
     });
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn load_chunks(
     mut commands: Commands,
     mut world: ResMut<World>,
-    mut chunks_query: Query<(Entity, &Chunk, &mut GenerateChunkMesh)>,
+    mut chunks_query: Query<(
+        Entity,
+        &Chunk,
+        &mut GenerateChunkMesh,
+        Option<&mut ChunkGeometryCache>,
+        Has<Handle<Mesh>>,
+    )>,
     mut meshes: ResMut<Assets<Mesh>>,
-    chunk_loader: ResMut<ChunkLoader>,
+    mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    time: Res<Time>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    meshing_pool: Option<Res<MeshingTaskPool>>,
 ) {
     let mut ready = vec![];
-    let task_pool = AsyncComputeTaskPool::get();
+    let mut upload_vertices = 0;
+    let task_pool: &TaskPool = match &meshing_pool {
+        Some(pool) => &pool.0,
+        None => AsyncComputeTaskPool::get(),
+    };
 
-    for (entity, chunk, mut gen_chunk_mesh) in chunks_query.iter_mut() {
+    for (entity, chunk, mut gen_chunk_mesh, cache, meshed) in chunks_query.iter_mut() {
         match &mut gen_chunk_mesh.task {
+            // finished meshes past this frame's upload budget wait in their tasks, though the
+            // first is always taken so even a single oversized chunk gets uploaded
+            Some(_)
+                if !ready.is_empty()
+                    && upload_vertices >= chunk_loader.settings.max_upload_vertices => {}
             Some(task) => {
                 if let Some(mesh) = futures::check_ready(task) {
-                    ready.push((entity, chunk, mesh));
+                    chunk_loader.meshing_tasks -= 1;
+                    if let Ok((meshes, _)) = &mesh {
+                        upload_vertices += meshes.vertex_count();
+                    }
+                    ready.push((entity, chunk, mesh, meshed));
                 }
             }
-            None => {
-                if let Some(data) = world.get_chunk_data(gen_chunk_mesh.coord) {
+            // wait for in-flight meshing tasks to finish before spawning more
+            None if chunk_loader.meshing_tasks >= chunk_loader.settings.max_meshing_tasks => {}
+            None => match world.get_chunk_data(gen_chunk_mesh.coord) {
+                Some(data) => {
                     let adjacent = world.adjacent_chunk_data(chunk.coord);
-                    gen_chunk_mesh.task =
-                        Some(task_pool.spawn(async move { generate_chunk_mesh(data, adjacent) }));
+                    let settings = chunk_loader.settings;
+                    let previous = cache.and_then(|mut cache| cache.0.take());
+                    let slices = gen_chunk_mesh.slices;
+                    gen_chunk_mesh.task = Some(task_pool.spawn(async move {
+                        run_chunk_task(|| {
+                            let geometry =
+                                generate_chunk_geometry(data, adjacent, previous, slices);
+                            Ok((geometry.to_meshes(settings), geometry))
+                        })
+                    }));
+                    chunk_loader.meshing_tasks += 1;
                 }
-            }
+                None => {
+                    let e = WorldError::ChunkNotGenerated(gen_chunk_mesh.coord);
+                    error!("failed to mesh chunk {:?}: {}", chunk.coord.0, e);
+                    commands.entity(entity).remove::<GenerateChunkMesh>();
+                }
+            },
         }
 
         if ready.len() > MAX_CHUNKS_PER_FRAME {
@@ -217,40 +881,267 @@ pub fn load_chunks(
         }
     }
 
-    for (entity, chunk, mesh) in ready {
-        let (t, aabb) = chunk_components(chunk.coord);
+    for (entity, chunk, result, meshed) in ready {
+        let (chunk_meshes, geometry) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                error!("failed to mesh chunk {:?}: {}", chunk.coord.0, e);
+                commands.entity(entity).remove::<GenerateChunkMesh>();
+                continue;
+            }
+        };
 
-        commands.entity(entity).insert((
-            MaterialMeshBundle {
-                mesh: meshes.add(mesh),
-                material: chunk_loader.material.clone_weak(),
-                transform: t,
-                ..Default::default()
-            },
-            aabb,
-        ));
+        // only fade chunks in the first time they appear, not when they are remeshed
+        let (material, translucent_material) = if meshed {
+            (
+                chunk_loader.material.clone_weak(),
+                chunk_loader.translucent_material.clone_weak(),
+            )
+        } else {
+            let mut fade_material = |material: &Handle<ChunkMaterial>| {
+                let mut material = chunk_materials
+                    .get(material)
+                    .expect("chunk material does not exist")
+                    .clone();
+                material.fade = 0.0;
+                chunk_materials.add(material)
+            };
+            let fade = ChunkFade {
+                material: fade_material(&chunk_loader.material),
+                translucent_material: fade_material(&chunk_loader.translucent_material),
+                started_at: time.elapsed_seconds(),
+            };
+            let materials = (fade.material.clone(), fade.translucent_material.clone());
+            commands.entity(entity).insert(fade);
+            materials
+        };
+
+        chunk_loader.insert_chunk_meshes(
+            &mut commands,
+            &mut meshes,
+            entity,
+            chunk.coord,
+            (chunk_meshes, geometry),
+            (material, translucent_material),
+        );
         commands.entity(entity).remove::<GenerateChunkMesh>();
     }
 }
 
+pub fn fade_chunks(
+    mut commands: Commands,
+    time: Res<Time>,
+    chunk_loader: Res<ChunkLoader>,
+    mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    mut chunks_query: Query<(Entity, &ChunkFade, &mut Handle<ChunkMaterial>)>,
+    mut sub_mesh_query: Query<
+        (&mut Handle<ChunkMaterial>, Has<TranslucentChunkMesh>),
+        Without<ChunkFade>,
+    >,
+    children_query: Query<&Children>,
+) {
+    for (entity, fade, mut material) in chunks_query.iter_mut() {
+        let progress = (time.elapsed_seconds() - fade.started_at) / CHUNK_FADE_DURATION;
+        if progress >= 1.0 {
+            *material = chunk_loader.material.clone_weak();
+            for child in children_query.iter_descendants(entity) {
+                if let Ok((mut material, translucent)) = sub_mesh_query.get_mut(child) {
+                    *material = if translucent {
+                        chunk_loader.translucent_material.clone_weak()
+                    } else {
+                        chunk_loader.material.clone_weak()
+                    };
+                }
+            }
+            commands.entity(entity).remove::<ChunkFade>();
+        } else {
+            for handle in [&fade.material, &fade.translucent_material] {
+                if let Some(fade_material) = chunk_materials.get_mut(handle) {
+                    fade_material.fade = progress;
+                }
+            }
+        }
+    }
+}
+
+/// Sorts newly loaded translucent chunk meshes back to front, and re-sorts them as the
+/// camera moves. Meshes near the camera are re-sorted whenever it moves into another
+/// block, but far away ones only when it crosses to another side of them, so moving
+/// doesn't re-upload every translucent mesh in view.
+pub fn sort_translucent_chunks(
+    camera_query: Query<&GlobalTransform, With<Camera>>,
+    mut chunks_query: Query<(
+        &GlobalTransform,
+        &Aabb,
+        &Handle<Mesh>,
+        &mut TranslucentChunkMesh,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let camera_position = camera.translation();
+
+    for (transform, aabb, mesh, mut translucent) in chunks_query.iter_mut() {
+        let viewer = transform
+            .affine()
+            .inverse()
+            .transform_point3(camera_position);
+        let key = SortKey::new(viewer, aabb);
+        if translucent.sorted_for == Some(key) {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            sort_quads_back_to_front(mesh, viewer);
+            translucent.sorted_for = Some(key);
+        }
+    }
+}
+
+/// Unloads chunks beyond unload distance. Their entities are stripped back and kept in
+/// a pool, so moving back and forth reuses entities rather than spawning new ones.
+#[allow(clippy::type_complexity)]
 pub fn unload_chunks(
     mut commands: Commands,
     mut world: ResMut<World>,
     mut chunk_loader: ResMut<ChunkLoader>,
     chunks_query: Query<(Entity, &Chunk), (Without<GenerateChunkData>, Without<GenerateChunkMesh>)>,
 ) {
+    let camera_chunk = chunk_loader.chunk_iterator.camera_chunk;
+    let farthest = chunks_query
+        .iter()
+        .map(|(_, chunk)| chunk_distance(chunk.coord, camera_chunk))
+        .max()
+        .unwrap_or_default();
+    chunk_loader.update_memory_distance(world.chunk_memory(), farthest);
+    chunk_loader.evict_unused_plant_meshes();
+
+    // chunks loaded outside the loader, like those searched for a spawn point, are taken
+    // over once the loader reaches them and otherwise unloaded once out of range
+    let untracked: Vec<_> = world.untracked_chunks().collect();
+    for coord in untracked {
+        if chunk_loader.chunk_to_entity.contains_key(&coord) {
+            world.track_chunk(coord);
+        } else if chunk_distance(coord, camera_chunk) > chunk_loader.unload_distance() {
+            if let Err(e) = world.clear_chunk(coord) {
+                error!("failed to save chunk {:?}: {}", coord.0, e);
+            }
+        }
+    }
+
     for (entity, chunk) in chunks_query.iter() {
         if chunk_distance(chunk.coord, chunk_loader.chunk_iterator.camera_chunk)
-            > chunk_loader.render_distance
+            > chunk_loader.unload_distance()
         {
-            commands.entity(entity).despawn();
+            if chunk_loader.pool.len() < MAX_POOLED_CHUNKS {
+                // removing the mesh also means the chunk fades in again when reused
+                commands
+                    .entity(entity)
+                    .despawn_descendants()
+                    .remove::<(
+                        Chunk,
+                        DirtyChunk,
+                        ChunkGeometryCache,
+                        ChunkFade,
+                        Handle<Mesh>,
+                        Handle<ChunkMaterial>,
+                        Aabb,
+                    )>()
+                    .insert(Visibility::Hidden);
+                chunk_loader.pool.push(entity);
+            } else {
+                commands.entity(entity).despawn_recursive();
+            }
             chunk_loader.chunk_to_entity.remove(&chunk.coord);
-            world.clear_chunk(chunk.coord);
+            chunk_loader.dirty_slices.remove(&chunk.coord);
+            if let Err(e) = world.clear_chunk(chunk.coord) {
+                error!("failed to save chunk {:?}: {}", chunk.coord.0, e);
+            }
         }
     }
 }
 
-fn chunk_world_pos(chunk: ChunkCoordinate) -> Vec3 {
+/// Despawns every chunk and discards all chunk data so the world regenerates with the
+/// current generator and mesher.
+pub fn reload_chunks(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    chunks_query: Query<Entity, With<Chunk>>,
+) {
+    if !keys.just_pressed(RELOAD_CHUNKS_KEY) {
+        return;
+    }
+
+    for entity in chunks_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    chunk_loader.reset();
+    if let Err(e) = world.clear_chunks() {
+        error!("failed to save chunks: {}", e);
+    }
+    info!("reloading chunks");
+}
+
+/// Rebuilds the mesh of every loaded chunk from its existing data, leaving the terrain
+/// untouched. Quicker than a full reload when working on the mesher.
+pub fn remesh_chunks(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    chunks_query: Query<&Chunk, (With<Handle<Mesh>>, Without<GenerateChunkMesh>)>,
+) {
+    if !keys.just_pressed(REMESH_CHUNKS_KEY) {
+        return;
+    }
+
+    for chunk in chunks_query.iter() {
+        chunk_loader.mark_chunk_dirty(&mut commands, chunk.coord);
+    }
+    info!("remeshing {} chunks", chunks_query.iter().count());
+}
+
+/// Saves every edited chunk when the app exits so no edits are lost.
+pub fn save_chunks_on_exit(mut exit_events: EventReader<AppExit>, mut world: ResMut<World>) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    match world.flush_saves() {
+        Ok(()) => info!("saved world"),
+        Err(e) => error!("failed to save chunks: {}", e),
+    }
+}
+
+/// Runs chunk work on a task thread, turning a panic into an error so one bad chunk is
+/// skipped rather than taking down the game.
+fn run_chunk_task<T>(work: impl FnOnce() -> Result<T, WorldError>) -> Result<T, WorldError> {
+    panic::catch_unwind(AssertUnwindSafe(work))
+        .unwrap_or_else(|panic| Err(WorldError::TaskPanicked(panic_message(panic))))
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+/// Bounds of a chunk mesh's geometry, for culling. Most chunks only fill part of their
+/// volume, so they are culled sooner than with bounds around the whole chunk. A mesh with no
+/// geometry falls back to the whole chunk.
+fn chunk_mesh_aabb(mesh: &Mesh) -> Aabb {
+    mesh.compute_aabb()
+        .unwrap_or_else(|| chunk_components(ChunkCoordinate(I64Vec3::ZERO)).1)
+}
+
+/// World position of a chunk's origin, where its entity is placed.
+pub fn chunk_world_pos(chunk: ChunkCoordinate) -> Vec3 {
     Vec3::new(
         (chunk.0.x * 16) as f32,
         (chunk.0.y * 16) as f32,
@@ -258,38 +1149,79 @@ fn chunk_world_pos(chunk: ChunkCoordinate) -> Vec3 {
     )
 }
 
+/// The slices of each chunk whose meshes depend on any of the blocks.
+fn affected_slices(world: &World, blocks: &[I64Vec3]) -> HashMap<ChunkCoordinate, ChunkSlices> {
+    let mut affected: HashMap<ChunkCoordinate, ChunkSlices> = HashMap::new();
+    for block in blocks {
+        for chunk in world.affected_chunks(*block) {
+            let local_y = block.y - chunk.0.y * CHUNK_SIZE as i64;
+            let slices = affected.entry(chunk).or_insert(ChunkSlices::NONE);
+            *slices = slices.union(ChunkSlices::around(local_y, CHUNK_SIZE));
+        }
+    }
+    affected
+}
+
 fn chunk_distance(chunk: ChunkCoordinate, other: ChunkCoordinate) -> u32 {
     (chunk.0 - other.0).abs().max_element() as u32
 }
 
-fn chunk_components(chunk: ChunkCoordinate) -> (Transform, Aabb) {
+pub fn chunk_components(chunk: ChunkCoordinate) -> (Transform, Aabb) {
     let pos = chunk_world_pos(chunk);
     let t = Transform::from_translation(Vec3::new(pos.x, pos.y, pos.z));
     let aabb = Aabb::from_min_max(Vec3::new(0.0, 0.0, 0.0), Vec3::new(16.0, 16.0, 16.0));
     (t, aabb)
 }
 
+/// The order in which chunks around the camera are generated.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkLoadStrategy {
+    /// Rings of columns outwards from the camera, going around each ring in turn.
+    Spiral,
+    /// Chunks in front of the camera first, restarting whenever the camera turns.
+    #[default]
+    ForwardCone,
+    /// The closest chunks first, whichever way the camera faces.
+    NearestFirst,
+}
+
+/// Rings further out than this are all given the lowest spiral priority.
+const MAX_SPIRAL_RING: u32 = 1024;
+
 /// `ChunkIterator` enables iteration of nearby chunks over multiple frames
 /// by storing BFS state in memory and dynamically recalculating when the camera chunk or direction changes
 #[derive(Debug)]
-struct ChunkIterator {
+pub struct ChunkIterator {
     seen: HashSet<ChunkCoordinate>,
     camera_chunk: ChunkCoordinate,
     camera_forward: Vec3,
     queue: PriorityQueue<ChunkCoordinate, u32>,
+    strategy: ChunkLoadStrategy,
+}
+
+impl Default for ChunkIterator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ChunkIterator {
-    fn new() -> Self {
+    pub fn new() -> Self {
+        Self::with_strategy(ChunkLoadStrategy::default())
+    }
+
+    pub fn with_strategy(strategy: ChunkLoadStrategy) -> Self {
         Self {
             seen: HashSet::new(),
             camera_chunk: ChunkCoordinate(I64Vec3::ZERO),
             camera_forward: Vec3::ZERO,
             queue: PriorityQueue::new(),
+            strategy,
         }
     }
 
-    fn next_chunks(
+    pub fn next_chunks(
         &mut self,
         count: usize,
         max_distance: u32,
@@ -318,12 +1250,12 @@ impl ChunkIterator {
     }
 
     fn queue_chunk(&mut self, chunk: ChunkCoordinate, world: &mut World) {
-        if self.seen.contains(&chunk) {
+        if self.seen.contains(&chunk) || !world.is_chunk_within_border(chunk) {
             return;
         }
 
-        let dot = self.dot(chunk, world);
-        if dot < 0.0 {
+        // chunks behind the camera wait until it turns round
+        if self.strategy == ChunkLoadStrategy::ForwardCone && self.dot(chunk, world) < 0.0 {
             return;
         }
 
@@ -339,35 +1271,467 @@ impl ChunkIterator {
     }
 
     fn calculate_priority(&self, chunk: ChunkCoordinate, world: &mut World) -> u32 {
-        let mut score = self.dot(chunk, world) / chunk_distance(chunk, self.camera_chunk) as f32;
-
-        if let Some(true) = world.get_chunk_data(chunk).map(|data| data.empty()) {
-            score = 0.0;
-        }
+        let offset = chunk.0 - self.camera_chunk.0;
+        match self.strategy {
+            ChunkLoadStrategy::Spiral => {
+                // outer rings last, then chunks further above or below, then by angle
+                let ring = offset
+                    .x
+                    .abs()
+                    .max(offset.z.abs())
+                    .min(MAX_SPIRAL_RING as i64) as u32;
+                let height = offset.y.unsigned_abs().min(63) as u32;
+                let angle = (offset.z as f32).atan2(offset.x as f32).to_degrees() + 180.0;
+                ((MAX_SPIRAL_RING - ring) * 64 + (63 - height)) * 361 + (360 - angle as u32)
+            }
+            ChunkLoadStrategy::ForwardCone => {
+                let mut score =
+                    self.dot(chunk, world) / chunk_distance(chunk, self.camera_chunk) as f32;
 
-        (score * 100.0).round() as u32
-    }
+                if let Some(true) = world.get_chunk_data(chunk).map(|data| data.empty()) {
+                    score = 0.0;
+                }
 
-    fn update(&mut self, camera_chunk: ChunkCoordinate, camera_forward: Vec3) {
-        // reset if camera turns too far from original direction
-        if camera_forward.dot(self.camera_forward) < 0.75 {
-            self.reset(camera_chunk, camera_forward);
-            return;
+                (score * 100.0).round() as u32
+            }
+            ChunkLoadStrategy::NearestFirst => {
+                u32::MAX - offset.length_squared().min(u32::MAX as i64) as u32
+            }
         }
+    }
 
-        // reset if chunk changes
-        if camera_chunk != self.camera_chunk {
+    /// Moves the search to a new camera position and direction. Returns whether the search
+    /// had to restart from the camera chunk.
+    pub fn update(&mut self, camera_chunk: ChunkCoordinate, camera_forward: Vec3) -> bool {
+        // reset if camera turns too far from original direction, when the order depends on
+        // it, or if chunk changes
+        let turned = self.strategy == ChunkLoadStrategy::ForwardCone
+            && camera_forward.dot(self.camera_forward) < 0.75;
+        let started = !self.seen.is_empty() || !self.queue.is_empty();
+        if turned || camera_chunk != self.camera_chunk || !started {
             self.reset(camera_chunk, camera_forward);
-            return;
+            return true;
         }
+        false
     }
 
     fn reset(&mut self, camera_chunk: ChunkCoordinate, camera_forward: Vec3) {
         self.seen.clear();
+        self.queue.clear();
 
         self.camera_chunk = camera_chunk;
         self.camera_forward = camera_forward;
 
-        self.queue.push(camera_chunk, 99999);
+        self.queue.push(camera_chunk, u32::MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bevy::{
+        asset::{Assets, Handle},
+        ecs::system::{Commands, ResMut, RunSystemOnce},
+        math::{I64Vec3, IVec3, U16Vec3, Vec3},
+        render::mesh::Mesh,
+        tasks::{AsyncComputeTaskPool, TaskPool},
+        time::Time,
+    };
+
+    use crate::{
+        block::BlockType,
+        chunks::{
+            chunk::{solid_chunk, ChunkCoordinate, ChunkData, ChunkSlices},
+            generate::generator::generate_chunk_geometry,
+            material::ChunkMaterial,
+        },
+        settings::RendererSettings,
+        world::{World, WorldError},
+    };
+
+    use super::{
+        affected_slices, chunk_components, chunk_distance, chunk_mesh_aabb, load_chunks,
+        reachable_chunks, run_chunk_task, unload_chunks, Chunk, ChunkIterator, ChunkLoadStrategy,
+        ChunkLoader, GenerateChunkMesh, MeshingTaskPool, SortKey, BYTES_PER_MEGABYTE,
+        GENERATION_MARGIN, NEAR_SORT_DISTANCE,
+    };
+
+    /// A loader with placeholder materials, for tests that never draw anything.
+    fn test_loader(settings: RendererSettings) -> ChunkLoader {
+        ChunkLoader::new(
+            settings,
+            Handle::default(),
+            Handle::default(),
+            Handle::default(),
+        )
+    }
+
+    /// Every chunk within `distance` of the origin, in the order the strategy loads them.
+    fn load_order(strategy: ChunkLoadStrategy, distance: u32) -> Vec<ChunkCoordinate> {
+        let mut world = World::new();
+        let mut iterator = ChunkIterator::with_strategy(strategy);
+        iterator.update(ChunkCoordinate(I64Vec3::ZERO), Vec3::Z);
+
+        let mut order = vec![];
+        while let Some(chunks) = iterator.next_chunks(1, distance, &mut world) {
+            order.extend(chunks);
+        }
+        order
+    }
+
+    #[test]
+    fn test_reachable_chunks_blocked_by_solid_chunk() {
+        let mut world = World::new();
+        let chunk = |x| ChunkCoordinate(I64Vec3::new(x, 0, 0));
+        world.insert_chunk(chunk(0), ChunkData::default());
+        world.insert_chunk(chunk(1), solid_chunk());
+        world.insert_chunk(chunk(2), ChunkData::default());
+
+        let reachable = reachable_chunks(chunk(0), 8, &mut world);
+
+        assert!(reachable.contains(&chunk(0)));
+        assert!(reachable.contains(&chunk(1)));
+        assert!(!reachable.contains(&chunk(2)));
+    }
+
+    #[test]
+    fn test_reachable_chunks_through_open_chunk() {
+        let mut world = World::new();
+        let chunk = |x| ChunkCoordinate(I64Vec3::new(x, 0, 0));
+        world.insert_chunk(chunk(0), ChunkData::default());
+        world.insert_chunk(chunk(1), ChunkData::default());
+        world.insert_chunk(chunk(2), ChunkData::default());
+
+        let reachable = reachable_chunks(chunk(0), 8, &mut world);
+
+        assert!(reachable.contains(&chunk(2)));
+        assert!(reachable.contains(&chunk(3)));
+        assert!(!reachable.contains(&chunk(4)));
+    }
+
+    #[test]
+    fn test_memory_budget_limits_generation_distance() {
+        let settings = RendererSettings {
+            render_distance: 8,
+            max_chunk_memory: 1,
+            ..Default::default()
+        };
+        let mut loader = test_loader(settings);
+        let full_distance = 8 + GENERATION_MARGIN;
+
+        loader.update_memory_distance(2 * BYTES_PER_MEGABYTE, full_distance);
+        assert_eq!(full_distance - 1, loader.generation_distance());
+
+        // stays put until loading reaches the limit and memory is well under budget
+        loader.update_memory_distance(BYTES_PER_MEGABYTE / 2, full_distance - 2);
+        assert_eq!(full_distance - 1, loader.generation_distance());
+        loader.update_memory_distance(BYTES_PER_MEGABYTE, full_distance - 1);
+        assert_eq!(full_distance - 1, loader.generation_distance());
+
+        loader.update_memory_distance(BYTES_PER_MEGABYTE / 2, full_distance - 1);
+        assert_eq!(full_distance, loader.generation_distance());
+        assert_eq!(None, loader.memory_distance);
+    }
+
+    #[test]
+    fn test_chunks_evicted_over_memory_budget_load_again() {
+        let settings = RendererSettings {
+            render_distance: 2,
+            max_chunk_memory: 1,
+            ..Default::default()
+        };
+        let mut loader = test_loader(settings);
+        let mut world = World::new();
+        let full_distance = loader.generation_distance();
+        let camera_chunk = ChunkCoordinate(I64Vec3::ZERO);
+        loader.chunk_iterator.update(camera_chunk, Vec3::Z);
+        let mut drain = |loader: &mut ChunkLoader| {
+            let distance = loader.generation_distance();
+            let mut chunks = vec![];
+            while let Some(next) = loader.chunk_iterator.next_chunks(64, distance, &mut world) {
+                chunks.extend(next);
+            }
+            chunks
+        };
+        let outermost = |chunks: &[ChunkCoordinate]| {
+            chunks
+                .iter()
+                .filter(|chunk| chunk_distance(**chunk, camera_chunk) == full_distance)
+                .count()
+        };
+        assert!(outermost(&drain(&mut loader)) > 0);
+
+        // the outermost chunks are evicted to get under budget, then the budget relaxes
+        loader.update_memory_distance(2 * BYTES_PER_MEGABYTE, full_distance);
+        assert_eq!(0, outermost(&drain(&mut loader)));
+        loader.update_memory_distance(BYTES_PER_MEGABYTE / 2, full_distance - 1);
+        assert_eq!(full_distance, loader.generation_distance());
+        assert!(outermost(&drain(&mut loader)) > 0);
+    }
+
+    #[test]
+    fn test_chunks_unload_past_margin_unless_over_memory_budget() {
+        let settings = RendererSettings {
+            render_distance: 8,
+            unload_margin: 2,
+            max_chunk_memory: 1,
+            ..Default::default()
+        };
+        let mut loader = test_loader(settings);
+        let full_distance = 8 + GENERATION_MARGIN;
+        assert_eq!(full_distance + 2, loader.unload_distance());
+
+        loader.update_memory_distance(2 * BYTES_PER_MEGABYTE, full_distance + 2);
+        assert_eq!(loader.generation_distance(), loader.unload_distance());
+    }
+
+    #[test]
+    fn test_chunks_loaded_outside_loader_unload_out_of_range() {
+        let mut world = World::with_seed(0);
+        let baseline = world.loaded_chunks().count();
+        let spawn = world.spawn_point();
+        assert!(world.loaded_chunks().count() > baseline);
+
+        let mut loader = test_loader(RendererSettings::default());
+        let far = world.block_to_chunk_coordinate(spawn.as_i64vec3() + I64Vec3::X * 4096);
+        loader.chunk_iterator.reset(far, Vec3::Z);
+
+        let mut app_world = bevy::ecs::world::World::new();
+        app_world.insert_resource(world);
+        app_world.insert_resource(loader);
+        app_world.run_system_once(unload_chunks);
+
+        let world = app_world.resource::<World>();
+        assert_eq!(baseline, world.loaded_chunks().count());
+        assert_eq!(0, world.untracked_chunks().count());
+    }
+
+    #[test]
+    fn test_spawn_chunk_and_ground_are_in_view() {
+        let world = World::new();
+        let mut loader = test_loader(RendererSettings::default());
+        let camera_chunk = ChunkCoordinate(I64Vec3::new(0, 2, 0));
+        loader.chunk_iterator.update(camera_chunk, Vec3::Z);
+
+        assert!(loader.in_view(camera_chunk, &world));
+        assert!(loader.in_view(ChunkCoordinate(I64Vec3::new(0, 1, 0)), &world));
+        assert!(loader.in_view(ChunkCoordinate(I64Vec3::new(0, 2, 1)), &world));
+        assert!(!loader.in_view(ChunkCoordinate(I64Vec3::new(0, 2, -1)), &world));
+    }
+
+    #[test]
+    fn test_fast_camera_caps_generation_distance() {
+        let settings = RendererSettings {
+            render_distance: 8,
+            fast_camera_speed: 24.0,
+            fast_render_distance: 3,
+            ..Default::default()
+        };
+        let mut loader = test_loader(settings);
+        let move_camera = |loader: &mut ChunkLoader, speed: f32, frames: usize| {
+            let start = loader.last_camera_position.unwrap_or_default();
+            (1..=frames)
+                .map(|frame| {
+                    let position = start + Vec3::X * speed * frame as f32 / 60.0;
+                    loader.update_camera_velocity(position, 1.0 / 60.0)
+                })
+                .filter(|changed| *changed)
+                .count()
+        };
+
+        // a walking camera loads the full distance
+        assert_eq!(0, move_camera(&mut loader, 5.0, 120));
+        assert_eq!(8 + GENERATION_MARGIN, loader.generation_distance());
+
+        assert_eq!(1, move_camera(&mut loader, 100.0, 120));
+        assert_eq!(3 + GENERATION_MARGIN, loader.generation_distance());
+        assert!(loader.camera_velocity.normalize().dot(Vec3::X) > 0.99);
+
+        // and expands back out once the camera stops
+        assert_eq!(1, move_camera(&mut loader, 0.0, 120));
+        assert_eq!(8 + GENERATION_MARGIN, loader.generation_distance());
+    }
+
+    #[test]
+    fn test_affected_slices_lists_each_chunk_once() {
+        let world = World::new();
+        // a pillar on the +x border of the origin chunk, spanning its bottom two slices
+        let blocks: Vec<_> = (0..8).map(|y| I64Vec3::new(15, y, 4)).collect();
+
+        let affected = affected_slices(&world, &blocks);
+
+        let mut chunks: Vec<_> = affected.keys().map(|chunk| chunk.0.to_array()).collect();
+        chunks.sort();
+        assert_eq!(vec![[0, -1, 0], [0, 0, 0], [1, 0, 0]], chunks);
+        let origin = affected[&ChunkCoordinate(I64Vec3::ZERO)];
+        assert!(origin.contains(0) && origin.contains(7) && origin.contains(8));
+    }
+
+    #[test]
+    fn test_generate_chunk_mesh_none_for_ungenerated_chunk() {
+        let mut app_world = bevy::ecs::world::World::new();
+        app_world.insert_resource(World::new());
+        app_world.insert_resource(test_loader(RendererSettings::default()));
+        app_world.init_resource::<Assets<Mesh>>();
+        app_world.init_resource::<Assets<ChunkMaterial>>();
+        app_world.init_resource::<Time>();
+        AsyncComputeTaskPool::get_or_init(TaskPool::default);
+
+        let coord = ChunkCoordinate(I64Vec3::ZERO);
+        let entity = app_world
+            .spawn((
+                Chunk { coord },
+                GenerateChunkMesh {
+                    coord,
+                    slices: ChunkSlices::ALL,
+                    task: None,
+                },
+            ))
+            .id();
+        app_world.run_system_once(load_chunks);
+
+        // the chunk gives up on meshing rather than waiting for data that isn't coming
+        let chunk = app_world.entity(entity);
+        assert!(!chunk.contains::<GenerateChunkMesh>());
+        assert!(!chunk.contains::<Handle<Mesh>>());
+        assert_eq!(0, app_world.resource::<ChunkLoader>().meshing_tasks);
+    }
+
+    #[test]
+    fn test_forced_chunk_is_meshed_immediately() {
+        let mut app_world = bevy::ecs::world::World::new();
+        app_world.insert_resource(World::new());
+        app_world.insert_resource(test_loader(RendererSettings {
+            max_upload_vertices: 1,
+            ..RendererSettings::default()
+        }));
+        app_world.init_resource::<Assets<Mesh>>();
+
+        // below sea level, so the chunk is never empty
+        let coord = ChunkCoordinate(I64Vec3::ZERO);
+        app_world.run_system_once(
+            move |mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  mut world: ResMut<World>,
+                  mut loader: ResMut<ChunkLoader>| {
+                loader
+                    .force_load_chunk(&mut commands, &mut meshes, &mut world, coord)
+                    .unwrap();
+            },
+        );
+
+        // drawn straight away, without waiting on a meshing task or the upload budget
+        let loader = app_world.resource::<ChunkLoader>();
+        assert_eq!(0, loader.meshing_tasks);
+        let chunk = app_world.entity(loader.chunk_to_entity[&coord]);
+        assert!(chunk.contains::<Handle<Mesh>>());
+        assert!(!chunk.contains::<GenerateChunkMesh>());
+    }
+
+    #[test]
+    fn test_far_translucent_meshes_resort_only_across_sides() {
+        let aabb = chunk_components(ChunkCoordinate(I64Vec3::ZERO)).1;
+        let far = 4.0 * NEAR_SORT_DISTANCE;
+
+        // moving about far from a mesh doesn't re-sort it until the camera crosses a side
+        let behind = SortKey::new(Vec3::new(8.0, 8.0, -far), &aabb);
+        assert_eq!(behind, SortKey::new(Vec3::new(9.0, 8.0, -far - 1.0), &aabb));
+        assert_eq!(SortKey::Side(IVec3::new(0, 0, -1)), behind);
+        assert_ne!(behind, SortKey::new(Vec3::new(far, 8.0, -far), &aabb));
+
+        // but near it every block counts
+        let near = SortKey::new(Vec3::new(8.0, 8.0, -2.0), &aabb);
+        assert_ne!(near, SortKey::new(Vec3::new(8.0, 8.0, -3.0), &aabb));
+    }
+
+    #[test]
+    fn test_mesh_aabb_fits_geometry() {
+        let mut chunk_data = ChunkData::default();
+        for x in 0..4 {
+            for z in 0..4 {
+                chunk_data.set_block_at(U16Vec3::new(x, 0, z), BlockType::Stone);
+            }
+        }
+        let geometry =
+            generate_chunk_geometry(Arc::new(chunk_data), vec![None; 6], None, ChunkSlices::ALL);
+        let meshes = geometry.to_meshes(RendererSettings::default());
+
+        let aabb = chunk_mesh_aabb(&meshes.opaque[0]);
+        assert_eq!(Vec3::new(-0.5, -0.5, -0.5), Vec3::from(aabb.min()));
+        assert_eq!(Vec3::new(3.5, 0.5, 3.5), Vec3::from(aabb.max()));
+
+        // nothing to draw keeps the whole chunk's bounds
+        let empty = generate_chunk_geometry(
+            Arc::new(ChunkData::default()),
+            vec![None; 6],
+            None,
+            ChunkSlices::ALL,
+        );
+        let aabb = chunk_mesh_aabb(&empty.to_meshes(RendererSettings::default()).opaque[0]);
+        assert_eq!(Vec3::splat(16.0), Vec3::from(aabb.max()));
+    }
+
+    #[test]
+    fn test_meshing_pool_needs_threads() {
+        assert!(MeshingTaskPool::new(0).is_none());
+        let pool = MeshingTaskPool::new(2).unwrap();
+        assert_eq!(2, pool.0.thread_num());
+    }
+
+    #[test]
+    fn test_run_chunk_task_catches_panic() {
+        let result: Result<(), WorldError> = run_chunk_task(|| panic!("bad chunk"));
+        match result {
+            Err(WorldError::TaskPanicked(message)) => assert_eq!("bad chunk", message),
+            _ => panic!("expected the panic to be caught"),
+        }
+    }
+
+    #[test]
+    fn test_forward_cone_skips_chunks_behind() {
+        let order = load_order(ChunkLoadStrategy::ForwardCone, 3);
+
+        assert!(order.iter().all(|chunk| chunk.0.z >= 0));
+    }
+
+    #[test]
+    fn test_nearest_first_loads_closest_chunks_first() {
+        let order = load_order(ChunkLoadStrategy::NearestFirst, 3);
+
+        // chunks behind the camera are loaded too
+        assert!(order.contains(&ChunkCoordinate(I64Vec3::new(0, 0, -3))));
+        assert!(order
+            .windows(2)
+            .all(|pair| pair[0].0.length_squared() <= pair[1].0.length_squared()));
+    }
+
+    #[test]
+    fn test_spiral_loads_ring_by_ring() {
+        let order = load_order(ChunkLoadStrategy::Spiral, 3);
+        let ring = |chunk: &ChunkCoordinate| chunk.0.x.abs().max(chunk.0.z.abs());
+
+        // chunks behind the camera are loaded too
+        assert!(order.contains(&ChunkCoordinate(I64Vec3::new(0, 0, -3))));
+        assert!(order
+            .windows(2)
+            .all(|pair| ring(&pair[0]) <= ring(&pair[1])));
+    }
+
+    #[test]
+    fn test_unused_plant_meshes_are_evicted() {
+        let mut loader = test_loader(RendererSettings::default());
+        let mut meshes = Assets::<Mesh>::default();
+        let grass = loader.plant_mesh(&mut meshes, BlockType::TallGrass, [1.0; 4]);
+        assert_eq!(
+            grass,
+            loader.plant_mesh(&mut meshes, BlockType::TallGrass, [1.0; 4])
+        );
+
+        loader.evict_unused_plant_meshes();
+        assert_eq!(1, loader.plant_meshes.len());
+        drop(grass);
+        loader.evict_unused_plant_meshes();
+        assert!(loader.plant_meshes.is_empty());
     }
 }
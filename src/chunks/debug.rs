@@ -0,0 +1,167 @@
+use bevy::{
+    ecs::{
+        query::Has,
+        system::{Query, Res, ResMut, Resource},
+    },
+    gizmos::gizmos::Gizmos,
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec3,
+    render::color::Color,
+    transform::components::Transform,
+};
+
+use super::{
+    chunk::ChunkCoordinate,
+    chunk_loader::{
+        chunk_components, chunk_world_pos, Chunk, DirtyChunk, GenerateChunkData, GenerateChunkMesh,
+    },
+};
+use crate::screenshot::TakingScreenshot;
+
+/// Toggles chunk outlines, or chunk origins while shift is held.
+const CHUNK_GIZMOS_KEY: KeyCode = KeyCode::F3;
+/// Length in blocks of each axis drawn at a chunk's origin.
+const ORIGIN_AXIS_LENGTH: f32 = 2.0;
+
+/// Whether every loaded chunk is outlined with a box coloured by its loading state.
+#[derive(Resource, Default)]
+pub struct ChunkGizmos(pub bool);
+
+/// Whether every loaded chunk has its x, y and z axes drawn in red, green and blue at its
+/// origin, to check chunks are placed where the coordinate maths expects.
+#[derive(Resource, Default)]
+pub struct ChunkOriginGizmos(pub bool);
+
+/// Where a chunk is in its lifecycle, from generating its data to being drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkState {
+    Generating,
+    Meshing,
+    /// Edited and waiting to be re-meshed.
+    Dirty,
+    Loaded,
+}
+
+impl ChunkState {
+    pub fn new(generating: bool, meshing: bool, dirty: bool) -> Self {
+        if generating {
+            Self::Generating
+        } else if meshing {
+            Self::Meshing
+        } else if dirty {
+            Self::Dirty
+        } else {
+            Self::Loaded
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Generating => Color::RED,
+            Self::Meshing => Color::YELLOW,
+            Self::Dirty => Color::ORANGE,
+            Self::Loaded => Color::GREEN,
+        }
+    }
+}
+
+pub fn toggle_chunk_gizmos(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut gizmos: ResMut<ChunkGizmos>,
+    mut origins: ResMut<ChunkOriginGizmos>,
+) {
+    if !keys.just_pressed(CHUNK_GIZMOS_KEY) {
+        return;
+    }
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        origins.0 = !origins.0;
+    } else {
+        gizmos.0 = !gizmos.0;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn draw_chunk_gizmos(
+    enabled: Res<ChunkGizmos>,
+    screenshot: Res<TakingScreenshot>,
+    mut gizmos: Gizmos,
+    chunks_query: Query<(
+        &Chunk,
+        Has<GenerateChunkData>,
+        Has<GenerateChunkMesh>,
+        Has<DirtyChunk>,
+    )>,
+) {
+    if !enabled.0 || screenshot.0 {
+        return;
+    }
+
+    for (chunk, generating, meshing, dirty) in chunks_query.iter() {
+        let (transform, aabb) = chunk_components(chunk.coord());
+        let centre = transform.translation + Vec3::from(aabb.center);
+        let size = Vec3::from(aabb.half_extents) * 2.0;
+        gizmos.cuboid(
+            Transform::from_translation(centre).with_scale(size),
+            ChunkState::new(generating, meshing, dirty).color(),
+        );
+    }
+}
+
+/// The axes drawn at a chunk's origin, as start, end and colour.
+fn origin_axes(coord: ChunkCoordinate) -> [(Vec3, Vec3, Color); 3] {
+    let origin = chunk_world_pos(coord);
+    [
+        (Vec3::X, Color::RED),
+        (Vec3::Y, Color::GREEN),
+        (Vec3::Z, Color::BLUE),
+    ]
+    .map(|(axis, color)| (origin, origin + axis * ORIGIN_AXIS_LENGTH, color))
+}
+
+pub fn draw_chunk_origin_gizmos(
+    enabled: Res<ChunkOriginGizmos>,
+    screenshot: Res<TakingScreenshot>,
+    mut gizmos: Gizmos,
+    chunks_query: Query<&Chunk>,
+) {
+    if !enabled.0 || screenshot.0 {
+        return;
+    }
+
+    for chunk in chunks_query.iter() {
+        for (start, end, color) in origin_axes(chunk.coord()) {
+            gizmos.line(start, end, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        math::{I64Vec3, Vec3},
+        render::color::Color,
+    };
+
+    use crate::chunks::chunk::ChunkCoordinate;
+
+    use super::{origin_axes, ChunkState};
+
+    #[test]
+    fn test_chunk_state_prefers_earliest_stage() {
+        assert_eq!(ChunkState::Generating, ChunkState::new(true, true, true));
+        assert_eq!(ChunkState::Meshing, ChunkState::new(false, true, true));
+        assert_eq!(ChunkState::Dirty, ChunkState::new(false, false, true));
+        assert_eq!(ChunkState::Loaded, ChunkState::new(false, false, false));
+    }
+
+    #[test]
+    fn test_origin_axes_start_at_chunk_origin() {
+        let axes = origin_axes(ChunkCoordinate(I64Vec3::new(1, -2, 3)));
+
+        let origin = Vec3::new(16.0, -32.0, 48.0);
+        assert!(axes.iter().all(|(start, _, _)| *start == origin));
+        let (_, x_end, x_color) = axes[0];
+        assert_eq!(Color::RED, x_color);
+        assert!((x_end - origin).normalize() == Vec3::X);
+    }
+}
@@ -0,0 +1,59 @@
+/// Biomes are picked per column from a temperature noise value in `0.0..=1.0`, with
+/// columns at or above `SAVANNA_TEMPERATURE` being savanna.
+#[derive(Debug, Copy, Clone)]
+pub enum Biome {
+    Plains,
+    Savanna,
+}
+
+const SAVANNA_TEMPERATURE: f32 = 0.6;
+
+/// Width of the temperature band over which grass colour blends between biomes.
+const BLEND_WIDTH: f32 = 0.1;
+
+impl Biome {
+    /// Colour multiplied into the greyscale grass texture.
+    pub fn grass_color(&self) -> [f32; 4] {
+        match self {
+            Self::Plains => [0.235, 0.531, 0.018, 1.0],
+            Self::Savanna => [0.89, 0.84, 0.37, 1.0],
+        }
+    }
+}
+
+/// Grass colour for a column, blended between neighbouring biomes near their boundary
+/// so colour shifts smoothly rather than changing abruptly.
+pub fn grass_tint(temperature: f32) -> [f32; 4] {
+    let plains = Biome::Plains.grass_color();
+    let savanna = Biome::Savanna.grass_color();
+
+    let t = ((temperature - SAVANNA_TEMPERATURE) / BLEND_WIDTH + 0.5).clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+
+    let mut tint = [0.0; 4];
+    for i in 0..4 {
+        tint[i] = plains[i] + (savanna[i] - plains[i]) * t;
+    }
+    tint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{grass_tint, Biome};
+
+    #[test]
+    fn test_grass_tint_matches_biome_away_from_boundary() {
+        assert_eq!(Biome::Plains.grass_color(), grass_tint(0.0));
+        assert_eq!(Biome::Savanna.grass_color(), grass_tint(1.0));
+    }
+
+    #[test]
+    fn test_grass_tint_blends_at_boundary() {
+        let tint = grass_tint(0.6);
+        let (plains, savanna) = (Biome::Plains.grass_color(), Biome::Savanna.grass_color());
+        for i in 0..3 {
+            assert!(tint[i] > plains[i].min(savanna[i]));
+            assert!(tint[i] < plains[i].max(savanna[i]));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use serde::Deserialize;
+
+/// Most control points a height curve can have.
+pub const MAX_CURVE_POINTS: usize = 8;
+
+/// Maps terrain noise to a fraction of the world height, as straight lines between control
+/// points of `[noise, height]`. Shallow segments flatten the noise they cover into plains and
+/// steep ones stretch it into cliffs and peaks. Past either end the curve levels off at the
+/// height of its end point.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "Vec<[f64; 2]>")]
+pub struct HeightCurve {
+    points: [[f64; 2]; MAX_CURVE_POINTS],
+    len: usize,
+}
+
+impl HeightCurve {
+    /// A curve through `points`, which need at least two points in order of increasing noise.
+    pub fn new(points: &[[f64; 2]]) -> Result<Self, String> {
+        if points.len() < 2 || points.len() > MAX_CURVE_POINTS {
+            return Err(format!(
+                "height curve needs 2 to {} points, got {}",
+                MAX_CURVE_POINTS,
+                points.len()
+            ));
+        }
+        if points.windows(2).any(|pair| pair[0][0] >= pair[1][0]) {
+            return Err("height curve points must be in order of increasing noise".to_string());
+        }
+
+        let mut curve = Self {
+            points: [[0.0; 2]; MAX_CURVE_POINTS],
+            len: points.len(),
+        };
+        curve.points[..points.len()].copy_from_slice(points);
+        Ok(curve)
+    }
+
+    pub fn points(&self) -> &[[f64; 2]] {
+        &self.points[..self.len]
+    }
+
+    /// Fraction of the world height the terrain reaches for a noise value.
+    pub fn sample(&self, noise: f64) -> f64 {
+        let points = self.points();
+        let noise = noise.clamp(points[0][0], points[points.len() - 1][0]);
+        let segment = points
+            .windows(2)
+            .position(|pair| noise < pair[1][0])
+            .unwrap_or(points.len() - 2);
+        let ([x0, y0], [x1, y1]) = (points[segment], points[segment + 1]);
+        y0 + (noise - x0) / (x1 - x0) * (y1 - y0)
+    }
+
+    /// Highest fraction of the world height the curve reaches.
+    pub fn max_height(&self) -> f64 {
+        self.points()
+            .iter()
+            .map(|point| point[1])
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+impl Default for HeightCurve {
+    /// Height proportional to the noise.
+    fn default() -> Self {
+        Self::new(&[[0.0, 0.0], [1.0, 1.0]]).unwrap()
+    }
+}
+
+impl TryFrom<Vec<[f64; 2]>> for HeightCurve {
+    type Error = String;
+
+    fn try_from(points: Vec<[f64; 2]>) -> Result<Self, Self::Error> {
+        Self::new(&points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::I64Vec2;
+
+    use crate::{
+        chunks::generate::{generator::column_height, noise::NoiseGenerator},
+        settings::TerrainSettings,
+    };
+
+    use super::HeightCurve;
+
+    #[test]
+    fn test_curve_interpolates_between_points() {
+        let curve = HeightCurve::new(&[[0.0, 0.0], [0.5, 0.1], [1.0, 1.0]]).unwrap();
+        assert!((curve.sample(0.25) - 0.05).abs() < 1e-9);
+        assert!((curve.sample(0.75) - 0.55).abs() < 1e-9);
+        // levels off past either end
+        assert_eq!(1.0, curve.sample(1.5));
+        assert_eq!(0.0, curve.sample(-0.5));
+
+        let peaks = HeightCurve::new(&[[0.0, 0.0], [0.8, 1.4], [1.0, 1.2]]).unwrap();
+        assert_eq!(1.4, peaks.max_height());
+        assert!(peaks.sample(2.0) <= peaks.max_height());
+
+        assert!(HeightCurve::new(&[[0.0, 0.0]]).is_err());
+        assert!(HeightCurve::new(&[[0.5, 0.0], [0.2, 1.0]]).is_err());
+    }
+
+    #[test]
+    fn test_flattened_curve_lowers_hills() {
+        let heights = |height_curve| {
+            let terrain = TerrainSettings {
+                height_curve,
+                ..TerrainSettings::default()
+            };
+            let mut noise = NoiseGenerator::new(7);
+            let mut heights: Vec<_> = (0..64 * 64)
+                .map(|i| I64Vec2::new(i % 64, i / 64) * 256)
+                .map(|column| column_height(&mut noise, column, 256, terrain))
+                .collect();
+            heights.sort();
+            heights
+        };
+
+        let linear = heights(HeightCurve::default());
+        let plains = heights(HeightCurve::new(&[[0.0, 0.0], [0.6, 0.2], [1.0, 1.0]]).unwrap());
+        // most columns are ocean, so compare the hills near the top of the distribution
+        let hills = linear.len() * 9 / 10;
+        assert!(plains[hills] < linear[hills]);
+    }
+}
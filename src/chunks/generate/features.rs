@@ -0,0 +1,38 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Random number generator for placing features like plants, trees and ores in the column
+/// of chunks at `chunk_x`, `chunk_z`. It depends only on the world seed and the column, so
+/// features land in the same spots however and whenever the chunk is generated.
+pub fn feature_rng(seed: u32, chunk_x: i64, chunk_z: i64) -> StdRng {
+    let hash = mix(mix(mix(seed as u64) ^ chunk_x as u64) ^ chunk_z as u64);
+    StdRng::seed_from_u64(hash)
+}
+
+/// The splitmix64 finaliser, spreading every input bit across the output so neighbouring
+/// chunks get unrelated seeds.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::feature_rng;
+
+    #[test]
+    fn test_feature_rng_is_deterministic() {
+        let rolls = |seed, x, z| {
+            let mut rng = feature_rng(seed, x, z);
+            (0..8).map(|_| rng.gen::<u32>()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(rolls(1, 3, -4), rolls(1, 3, -4));
+        assert_ne!(rolls(1, 3, -4), rolls(2, 3, -4));
+        assert_ne!(rolls(1, 3, -4), rolls(1, -4, 3));
+        assert_ne!(rolls(1, 3, -4), rolls(1, 3, -5));
+    }
+}
@@ -1,36 +1,155 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use bevy::{
-    math::{I64Vec2, U16Vec3, Vec3},
+    log::warn,
+    math::{I64Vec2, I64Vec3, IVec3, U16Vec3, Vec2, Vec3},
     render::{
         mesh::{Indices, Mesh, VertexAttributeValues},
         render_asset::RenderAssetUsages,
     },
 };
 
-use super::noise::NoiseGenerator;
-use crate::block::{BlockType, BLOCK_COUNT};
-use crate::chunks::chunk::{ChunkCoordinate, ChunkData};
+use rand::Rng;
+use serde::Deserialize;
+
+use super::{biome::grass_tint, features::feature_rng, noise::NoiseGenerator};
+use crate::block::{BlockShape, BlockType, ATLAS_TILES, GRASS_SIDE_TILE, MAX_LIGHT};
+use crate::chunks::chunk::{
+    ChunkCoordinate, ChunkData, ChunkSlices, ADJACENT_OFFSETS, SLICE_HEIGHT,
+};
+use crate::chunks::material::RenderStyle;
+use crate::settings::{RendererSettings, TerrainSettings};
 use crate::util::primitives::Vertex;
+use crate::world::WorldError;
+
+/// Fraction of grass columns that get a plant on top.
+const PLANT_DENSITY: f64 = 0.1;
+/// Caves are only carved below this height.
+const CAVE_DEPTH: i64 = -32;
+/// Cave noise above this value is carved out.
+const CAVE_THRESHOLD: f64 = 0.45;
+/// Caves fill with lava below this height.
+const LAVA_LEVEL: i64 = -48;
+/// Open columns are filled with water below this height.
+pub const SEA_LEVEL: i64 = 32;
+/// Height of the bottom of the bedrock floor, below which there is nothing.
+pub const WORLD_BOTTOM: i64 = -64;
+/// Thickest a noisy bedrock floor gets.
+const MAX_BEDROCK_THICKNESS: f64 = 4.0;
+/// Offset on the world seed for the bedrock rolls, which have their own generator so that
+/// rolling them doesn't move every plant.
+const BEDROCK_SEED_OFFSET: u32 = 5;
+
+/// How the bottom of the world is sealed off.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BedrockFloor {
+    /// No floor, with the ground carrying on below the world bottom.
+    None,
+    /// A single flat layer of bedrock.
+    Flat,
+    /// Between one and four layers of bedrock, varying from column to column.
+    #[default]
+    Noisy,
+}
+
+impl BedrockFloor {
+    /// Layers of bedrock in a column given the column's roll in `0.0..1.0`, or `None` if the
+    /// world has no floor.
+    pub fn thickness(&self, roll: f64) -> Option<i64> {
+        match self {
+            Self::None => None,
+            Self::Flat => Some(1),
+            Self::Noisy => Some(1 + (roll * MAX_BEDROCK_THICKNESS) as i64),
+        }
+    }
+}
+
+/// Number of blocks in the terrain column at `column`, counting up from y = 0, so the
+/// surface block sits at one less than this.
+pub fn column_height(
+    noise: &mut NoiseGenerator,
+    column: I64Vec2,
+    world_height: u64,
+    terrain: TerrainSettings,
+) -> u64 {
+    let height = terrain.height_curve.sample(noise.get(column));
+    (height * world_height as f64 * terrain.height_scale).round() as u64
+}
+
+/// Whether a column lies on a shoreline: its surface is near sea level and there is water
+/// within the beach width of it.
+fn is_shore(
+    noise: &mut NoiseGenerator,
+    column: I64Vec2,
+    world_height: u64,
+    terrain: TerrainSettings,
+) -> bool {
+    let mut surface = |column| column_height(noise, column, world_height, terrain) as i64 - 1;
+    let height = surface(column);
+    if terrain.beach_width <= 0 || height > SEA_LEVEL + terrain.beach_height {
+        return false;
+    }
+    if height < SEA_LEVEL {
+        return true;
+    }
+
+    // sampling a ring of columns rather than every column in range keeps this cheap,
+    // and terrain is smooth enough that it rarely misses water
+    (-1..=1)
+        .flat_map(|x| (-1..=1).map(move |z| I64Vec2::new(x, z)))
+        .filter(|offset| *offset != I64Vec2::ZERO)
+        .any(|offset| surface(column + offset * terrain.beach_width) < SEA_LEVEL)
+}
 
 pub fn generate_chunk(
     noise_generator: Arc<RwLock<NoiseGenerator>>,
     chunk_pos: ChunkCoordinate,
     world_height: u64,
-) -> ChunkData {
+    terrain: TerrainSettings,
+) -> Result<ChunkData, WorldError> {
     let mut chunk_data = ChunkData::default();
-    let mut noise = noise_generator.write().unwrap();
+    let mut noise = noise_generator
+        .write()
+        .map_err(|_| WorldError::NoiseGeneratorPoisoned)?;
+    let mut rng = feature_rng(noise.seed(), chunk_pos.0.x, chunk_pos.0.z);
+    let mut bedrock_rng = feature_rng(
+        noise.seed().wrapping_add(BEDROCK_SEED_OFFSET),
+        chunk_pos.0.x,
+        chunk_pos.0.z,
+    );
 
     for x in 0..chunk_data.size {
         for z in 0..chunk_data.size {
+            // rolled for every column, so each column's roll is the same in every chunk above it
+            let plant_roll: f64 = rng.gen();
+            let bedrock_roll: f64 = bedrock_rng.gen();
             let (world_x, world_y, world_z) = (
                 chunk_pos.0.x * chunk_data.size as i64 + x as i64,
                 chunk_pos.0.y * chunk_data.size as i64,
                 chunk_pos.0.z * chunk_data.size as i64 + z as i64,
             );
-            let noise_val = noise.get(I64Vec2::new(world_x, world_z));
+            chunk_data.set_temperature_at(
+                x,
+                z,
+                noise.get_temperature(I64Vec2::new(world_x, world_z)) as f32,
+            );
 
-            let world_height = (noise_val * world_height as f64).round() as u64;
+            let beach = is_shore(
+                &mut noise,
+                I64Vec2::new(world_x, world_z),
+                world_height,
+                terrain,
+            );
+            let world_height = column_height(
+                &mut noise,
+                I64Vec2::new(world_x, world_z),
+                world_height,
+                terrain,
+            );
             let chunk_height = if world_y > 0 {
                 let positive_y = world_y as u64;
                 (world_height - positive_y.min(world_height)).min(chunk_data.size as u64)
@@ -52,13 +171,15 @@ pub fn generate_chunk(
             for y in 0..chunk_height {
                 let world_y = world_y + y as i64;
 
-                let block = if world_y >= 90 && combined_gradient <= 2.0 {
+                let block = if world_y >= terrain.snow_height && combined_gradient <= 2.0 {
                     BlockType::Snow
-                } else if world_y >= 70 && combined_gradient >= 2.0
-                    || (world_y >= 36 && combined_gradient >= 3.5)
+                } else if world_y >= terrain.stone_height && combined_gradient >= 2.0
+                    || (world_y >= terrain.grass_height && combined_gradient >= 3.5)
                 {
                     BlockType::Stone
-                } else if world_y >= 36 {
+                } else if beach && world_y <= SEA_LEVEL + terrain.beach_height {
+                    BlockType::Sand
+                } else if world_y >= terrain.grass_height {
                     BlockType::Grass
                 } else {
                     BlockType::Sand
@@ -66,7 +187,52 @@ pub fn generate_chunk(
                 chunk_data.set_block_at(U16Vec3::new(x, y as u16, z), block);
             }
 
-            if world_y <= 16 {
+            // carve caves deep underground, with lava pooled in their lower parts
+            for y in 0..chunk_height {
+                let world_y = world_y + y as i64;
+                if world_y >= CAVE_DEPTH
+                    || noise.get_cave(I64Vec3::new(world_x, world_y, world_z)) < CAVE_THRESHOLD
+                {
+                    continue;
+                }
+
+                let block = if world_y < LAVA_LEVEL {
+                    BlockType::Lava
+                } else {
+                    BlockType::Air
+                };
+                chunk_data.set_block_at(U16Vec3::new(x, y as u16, z), block);
+            }
+
+            // lay the floor after carving caves so they can't break through it
+            if let Some(thickness) = terrain.bedrock.thickness(bedrock_roll) {
+                for y in 0..chunk_height {
+                    let world_y = world_y + y as i64;
+                    let block = if world_y < WORLD_BOTTOM {
+                        BlockType::Air
+                    } else if world_y < WORLD_BOTTOM + thickness {
+                        BlockType::Bedrock
+                    } else {
+                        break;
+                    };
+                    chunk_data.set_block_at(U16Vec3::new(x, y as u16, z), block);
+                }
+            }
+
+            // scatter plants on grass whose surface lies inside this chunk
+            if chunk_height > 0
+                && chunk_height < chunk_data.size as u64
+                && chunk_data.get_block_at(U16Vec3::new(x, chunk_height as u16 - 1, z))
+                    == Some(BlockType::Grass)
+                && plant_roll < PLANT_DENSITY
+            {
+                chunk_data.set_block_at(
+                    U16Vec3::new(x, chunk_height as u16, z),
+                    BlockType::TallGrass,
+                );
+            }
+
+            if world_y < SEA_LEVEL {
                 for y in chunk_height..chunk_data.size as u64 {
                     chunk_data.set_block_at(U16Vec3::new(x, y as u16, z), BlockType::Water);
                 }
@@ -74,125 +240,466 @@ pub fn generate_chunk(
         }
     }
 
-    chunk_data
+    chunk_data.update_visibility();
+    Ok(chunk_data)
 }
 
-pub fn generate_chunk_mesh(
+/// Meshes the given slices of a chunk, reusing the geometry of every other slice from
+/// `previous`. The whole chunk is meshed if there is no previous geometry, or if block
+/// light is involved since light spreads between slices.
+pub fn generate_chunk_geometry(
     chunk: Arc<ChunkData>,
     adjacent_chunks: Vec<Option<Arc<ChunkData>>>,
-) -> Mesh {
-    let mut vertices: Vec<Vertex> = vec![];
-    let mut indices: Vec<u32> = vec![];
-
-    let mut add_vertices = |vs: &[Vertex], position: Vec3, block_type: BlockType| {
-        let uv_scale = 1.0 / (BLOCK_COUNT - 1) as f32;
-
-        let triangle_start: u32 = vertices.len() as u32;
-        vertices.extend(&mut vs.iter().map(|v| Vertex {
-            position: (Vec3::from(v.position) + position).into(),
-            normal: v.normal,
-            uv: [
-                uv_scale * (v.uv[0] + (block_type as usize - 1) as f32),
-                v.uv[1],
-            ],
-        }));
-        indices.extend(vec![
-            triangle_start,
-            triangle_start + 1,
-            triangle_start + 2,
-            triangle_start + 2,
-            triangle_start + 1,
-            triangle_start + 3,
-        ]);
+    previous: Option<ChunkGeometry>,
+    slices: ChunkSlices,
+) -> ChunkGeometry {
+    // light is spread across chunk borders so it stays continuous over the seams
+    let size = chunk.size as i64;
+    let adjacent_light: Vec<_> = adjacent_chunks
+        .iter()
+        .map(|adjacent| adjacent.as_ref().map(|adjacent| adjacent.block_light()))
+        .collect();
+    let incoming = ADJACENT_OFFSETS
+        .iter()
+        .zip(adjacent_light.iter())
+        .filter_map(|(offset, light)| light.as_ref().map(|light| (offset, light)))
+        .flat_map(|(offset, light)| {
+            light.iter().filter_map(move |(coord, level)| {
+                // the block just inside this chunk next to a lit block on the neighbour's border
+                let inside = coord.as_i64vec3() + *offset * (size - 1);
+                (inside.min_element() >= 0 && inside.max_element() < size)
+                    .then(|| (inside.as_u16vec3(), level.saturating_sub(1)))
+            })
+        })
+        .collect::<Vec<_>>();
+    let lit = !incoming.is_empty();
+    let block_light = chunk.block_light_with(incoming);
+    let lit = lit || !block_light.is_empty();
+    let light_at = |coord: I64Vec3| {
+        let inside = coord.clamp(I64Vec3::ZERO, I64Vec3::splat(size - 1));
+        let light = block_light
+            .get(&inside.as_u16vec3())
+            .copied()
+            .unwrap_or_default();
+        if inside == coord {
+            return light;
+        }
+
+        // outside the chunk, use the neighbour's own light or this chunk's spilling into it
+        let offset = coord - inside;
+        let adjacent = ADJACENT_OFFSETS
+            .iter()
+            .position(|o| *o == offset)
+            .and_then(|i| adjacent_light[i].as_ref())
+            .and_then(|light| light.get(&(coord - offset * size).as_u16vec3()))
+            .copied()
+            .unwrap_or_default();
+        adjacent.max(light.saturating_sub(1))
     };
 
+    let slice_count = chunk.size.div_ceil(SLICE_HEIGHT) as usize;
+    let (mut geometry, slices) = match previous {
+        Some(previous) if !previous.lit && !lit && previous.slices.len() == slice_count => {
+            (previous.slices, slices)
+        }
+        _ => (vec![], ChunkSlices::ALL),
+    };
+    geometry.resize_with(slice_count, SliceGeometry::default);
+    for (i, slice) in geometry.iter_mut().enumerate() {
+        if slices.contains(i as u16 * SLICE_HEIGHT) {
+            *slice = SliceGeometry::default();
+        }
+    }
+
+    let mut add_vertices =
+        |vs: &[Vertex], position: Vec3, block_type: BlockType, color: [f32; 4]| {
+            let slice = &mut geometry[position.y as usize / SLICE_HEIGHT as usize];
+            let buffers = if block_type.is_translucent() {
+                &mut slice.translucent
+            } else {
+                &mut slice.opaque
+            };
+            buffers.push_block(vs, position, block_type, color);
+        };
+    let mut plants = vec![];
+
     let cube_vertices = crate::util::primitives::cube();
-    let face_vertices = [
-        &cube_vertices[0..4],   // front
-        &cube_vertices[4..8],   // right
-        &cube_vertices[8..12],  // left
-        &cube_vertices[12..16], // back
-        &cube_vertices[16..20], // top
-        &cube_vertices[20..24], // bottom
+    let slab_vertices = crate::util::primitives::slab();
+    let face_directions = [
+        I64Vec3::NEG_Z, // front
+        I64Vec3::X,     // right
+        I64Vec3::NEG_X, // left
+        I64Vec3::Z,     // back
+        I64Vec3::Y,     // top
+        I64Vec3::NEG_Y, // bottom
     ];
 
     for (coord, block) in chunk.blocks().iter() {
+        if !slices.contains(coord.y) {
+            continue;
+        }
+
         let (x, y, z) = (coord.x, coord.y, coord.z);
         let world_position = Vec3::new(x as f32, y as f32, z as f32);
+        let [r, g, b] = block.tint();
+        let block_tint = [r, g, b, 1.0];
+        let tint = match block {
+            BlockType::Grass | BlockType::TallGrass => grass_tint(chunk.temperature_at(x, z)),
+            _ => block_tint,
+        };
+        let light_color = |tint: [f32; 4], light: u8| {
+            let light = light.max(block.light_emission()) as f32 / MAX_LIGHT as f32;
+            [tint[0], tint[1], tint[2], light]
+        };
+
+        // plants are never culled and never hide their neighbours
+        if block.shape() == BlockShape::Cross {
+            plants.push(PlantInstance {
+                position: world_position,
+                block: *block,
+                color: light_color(tint, light_at(coord.as_i64vec3())),
+            });
+            continue;
+        }
 
         let front = if z > 0 {
-            chunk.get_block_at(U16Vec3::new(x, y, z - 1))
+            mesh_block_at(&chunk, U16Vec3::new(x, y, z - 1))
         } else {
             let adjacent = &adjacent_chunks[1].as_ref();
             adjacent
-                .map(|adjacent| adjacent.get_block_at(U16Vec3::new(x, y, adjacent.size - 1)))
+                .map(|adjacent| mesh_block_at(adjacent, U16Vec3::new(x, y, adjacent.size - 1)))
                 .unwrap_or_default()
         };
 
         let back = if z < chunk.size - 1 {
-            chunk.get_block_at(U16Vec3::new(x, y, z + 1))
+            mesh_block_at(&chunk, U16Vec3::new(x, y, z + 1))
         } else {
             let adjacent = &adjacent_chunks[0].as_ref();
             adjacent
-                .map(|adjacent| adjacent.get_block_at(U16Vec3::new(x, y, 0)))
+                .map(|adjacent| mesh_block_at(adjacent, U16Vec3::new(x, y, 0)))
                 .unwrap_or_default()
         };
 
         let left = if x > 0 {
-            chunk.get_block_at(U16Vec3::new(x - 1, y, z))
+            mesh_block_at(&chunk, U16Vec3::new(x - 1, y, z))
         } else {
             let adjacent = &adjacent_chunks[3].as_ref();
             adjacent
-                .map(|adjacent| adjacent.get_block_at(U16Vec3::new(adjacent.size - 1, y, z)))
+                .map(|adjacent| mesh_block_at(adjacent, U16Vec3::new(adjacent.size - 1, y, z)))
                 .unwrap_or_default()
         };
 
         let right = if x < chunk.size - 1 {
-            chunk.get_block_at(U16Vec3::new(x + 1, y, z))
+            mesh_block_at(&chunk, U16Vec3::new(x + 1, y, z))
         } else {
             let adjacent = &adjacent_chunks[2].as_ref();
             adjacent
-                .map(|adjacent| adjacent.get_block_at(U16Vec3::new(0, y, z)))
+                .map(|adjacent| mesh_block_at(adjacent, U16Vec3::new(0, y, z)))
                 .unwrap_or_default()
         };
 
         let top = if y < chunk.size - 1 {
-            chunk.get_block_at(U16Vec3::new(x, y + 1, z))
+            mesh_block_at(&chunk, U16Vec3::new(x, y + 1, z))
         } else {
             let adjacent = &adjacent_chunks[4].as_ref();
             adjacent
-                .map(|adjacent| adjacent.get_block_at(U16Vec3::new(x, 0, z)))
+                .map(|adjacent| mesh_block_at(adjacent, U16Vec3::new(x, 0, z)))
                 .unwrap_or_default()
         };
 
         let bottom = if y > 0 {
-            chunk.get_block_at(U16Vec3::new(x, y - 1, z))
+            mesh_block_at(&chunk, U16Vec3::new(x, y - 1, z))
         } else {
             let adjacent = &adjacent_chunks[5].as_ref();
             adjacent
-                .map(|adjacent| adjacent.get_block_at(U16Vec3::new(x, adjacent.size - 1, z)))
+                .map(|adjacent| mesh_block_at(adjacent, U16Vec3::new(x, adjacent.size - 1, z)))
                 .unwrap_or_default()
         };
 
+        let shape_vertices = match block.shape() {
+            BlockShape::Cube => &cube_vertices,
+            BlockShape::Slab => &slab_vertices,
+            BlockShape::Cross => unreachable!("plants are meshed separately"),
+        };
+
         let sides = [front, right, left, back, top, bottom];
         for (i, side) in sides.iter().enumerate() {
-            match side {
-                BlockType::Water => {
-                    if *block != BlockType::Water {
-                        add_vertices(&face_vertices[i], world_position, *block)
-                    }
-                }
-                BlockType::Air => add_vertices(&face_vertices[i], world_position, *block),
-                _ => (),
+            let direction = face_directions[i];
+            // faces set back from the cell boundary can't be hidden by a neighbour
+            let hidden = block.shape().touches_face(direction)
+                && if side.is_transparent() {
+                    side == block
+                } else {
+                    side.shape().covers_face(-direction)
+                };
+
+            if !hidden {
+                let face = &shape_vertices[i * 4..i * 4 + 4];
+                // grass is only green on top and on the overlay hanging down its sides, which
+                // the shader limits the tint to, so the dirt underneath is left untinted
+                let face_tint = if *block == BlockType::Grass && direction == I64Vec3::NEG_Y {
+                    block_tint
+                } else {
+                    tint
+                };
+                let color = light_color(face_tint, light_at(coord.as_i64vec3() + direction));
+                add_vertices(face, world_position, *block, color);
+            }
+        }
+    }
+
+    for plant in plants {
+        geometry[plant.position.y as usize / SLICE_HEIGHT as usize]
+            .plants
+            .push(plant);
+    }
+
+    ChunkGeometry {
+        slices: geometry,
+        lit,
+    }
+}
+
+/// A chunk's mesh geometry, kept per slice so that edits only need to re-mesh the slices
+/// they affect.
+#[derive(Clone, Default)]
+pub struct ChunkGeometry {
+    slices: Vec<SliceGeometry>,
+    /// Whether any block light reached the chunk when it was meshed.
+    lit: bool,
+}
+
+impl ChunkGeometry {
+    pub fn to_meshes(&self, settings: RendererSettings) -> ChunkMeshes {
+        let mut opaque = MeshBuffers::default();
+        let mut translucent = MeshBuffers::default();
+        let mut plants = vec![];
+        for slice in self.slices.iter() {
+            opaque.extend(&slice.opaque);
+            translucent.extend(&slice.translucent);
+            plants.extend_from_slice(&slice.plants);
+        }
+
+        // without instancing, plants are baked into the chunk mesh and can't sway
+        if !settings.instanced_plants {
+            let cross_vertices = crate::util::primitives::cross();
+            for plant in plants.drain(..) {
+                opaque.push_block(&cross_vertices, plant.position, plant.block, plant.color);
+            }
+        }
+        for plant in plants.iter_mut() {
+            plant.color = styled_color(plant.color, plant.block, settings);
+        }
+
+        // smoothing runs over the whole chunk so normals are shared across slices
+        if settings.smooth_normals {
+            average_normals(&mut opaque.vertices);
+            average_normals(&mut translucent.vertices);
+        }
+
+        let mut opaque = opaque.into_meshes(settings);
+        if opaque.is_empty() {
+            opaque.push(build_mesh(&[], vec![], vec![], settings.tangents));
+        }
+        ChunkMeshes {
+            opaque,
+            translucent: translucent.into_meshes(settings),
+            plants,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct SliceGeometry {
+    opaque: MeshBuffers,
+    translucent: MeshBuffers,
+    plants: Vec<PlantInstance>,
+}
+
+/// The meshes making up a chunk. Translucent blocks are kept apart from opaque ones so
+/// they can be blended and drawn back to front.
+pub struct ChunkMeshes {
+    /// Always holds at least one mesh, which may be empty.
+    pub opaque: Vec<Mesh>,
+    pub translucent: Vec<Mesh>,
+    /// Plants to draw as instances of a shared model, empty if plants are in `opaque`.
+    pub plants: Vec<PlantInstance>,
+}
+
+impl ChunkMeshes {
+    /// Number of vertices uploaded to the gpu when these meshes are added. Plants share
+    /// their models, so they aren't counted.
+    pub fn vertex_count(&self) -> usize {
+        self.opaque
+            .iter()
+            .chain(&self.translucent)
+            .map(Mesh::count_vertices)
+            .sum()
+    }
+}
+
+/// A plant drawn as an instance of a shared model rather than baked into the chunk mesh,
+/// so it can sway without the chunk being re-meshed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlantInstance {
+    /// Centre of the plant's block, relative to the chunk.
+    pub position: Vec3,
+    pub block: BlockType,
+    /// Tint in rgb and block light in alpha, as for chunk mesh vertices.
+    pub color: [f32; 4],
+}
+
+/// The shared model for plants of one block type and colour, centred on the origin.
+pub fn plant_mesh(block: BlockType, color: [f32; 4]) -> Mesh {
+    let mut buffers = MeshBuffers::default();
+    buffers.push_block(&crate::util::primitives::cross(), Vec3::ZERO, block, color);
+    build_mesh(&buffers.vertices, buffers.colors, buffers.surfaces, false)
+}
+
+/// The colour a vertex is drawn with, tinted by the colour palette. The colored style has
+/// no atlas, so each block's colour is baked into its tint.
+fn styled_color(mut color: [f32; 4], block: BlockType, settings: RendererSettings) -> [f32; 4] {
+    let tint = settings.palette.tint(block);
+    let base = match settings.render_style {
+        RenderStyle::Colored => block.base_color(),
+        _ => [1.0; 3],
+    };
+    for i in 0..3 {
+        color[i] *= base[i] * tint[i];
+    }
+    color
+}
+
+#[derive(Clone, Default)]
+struct MeshBuffers {
+    vertices: Vec<Vertex>,
+    colors: Vec<[f32; 4]>,
+    surfaces: Vec<[f32; 2]>,
+    /// The block each vertex belongs to, for baking block colours.
+    blocks: Vec<BlockType>,
+}
+
+impl MeshBuffers {
+    /// Adds a block's vertices at `position`, mapping each face's uvs onto its atlas tile.
+    /// The colour's rgb tints the block and its alpha carries the block light level.
+    fn push_block(
+        &mut self,
+        vs: &[Vertex],
+        position: Vec3,
+        block_type: BlockType,
+        color: [f32; 4],
+    ) {
+        // air has no tile, and is never meshed anyway
+        let Some(block_tile) = block_type.texture_index() else {
+            return;
+        };
+        let uv_scale = 1.0 / ATLAS_TILES as f32;
+        self.vertices.extend(vs.iter().map(|v| {
+            let tile = block_type
+                .face_texture_index(v.normal)
+                .unwrap_or(block_tile);
+            // the overlay hangs from the top edge, so the tile is kept upright on every side.
+            // the bottom lands on the overlay's empty bottom row, leaving plain dirt
+            let v_coord = if tile == GRASS_SIDE_TILE {
+                0.5 - v.position[1]
+            } else {
+                v.uv[1]
             };
+            Vertex {
+                position: (Vec3::from(v.position) + position).into(),
+                normal: v.normal,
+                uv: [uv_scale * (v.uv[0] + tile as f32), v_coord],
+            }
+        }));
+        self.colors.extend(vs.iter().map(|_| color));
+        self.surfaces
+            .extend(vs.iter().map(|_| block_type.surface()));
+        self.blocks.extend(vs.iter().map(|_| block_type));
+    }
+
+    fn extend(&mut self, other: &MeshBuffers) {
+        self.vertices.extend_from_slice(&other.vertices);
+        self.colors.extend_from_slice(&other.colors);
+        self.surfaces.extend_from_slice(&other.surfaces);
+        self.blocks.extend_from_slice(&other.blocks);
+    }
+
+    /// Builds meshes of at most `max_mesh_vertices` vertices each.
+    fn into_meshes(mut self, settings: RendererSettings) -> Vec<Mesh> {
+        for (color, block) in self.colors.iter_mut().zip(self.blocks.iter()) {
+            *color = styled_color(*color, *block, settings);
         }
+
+        // split on whole quads so no face is torn between two meshes
+        let max_vertices = (settings.max_mesh_vertices / 4).max(1) * 4;
+        self.vertices
+            .chunks(max_vertices)
+            .zip(self.colors.chunks(max_vertices))
+            .zip(self.surfaces.chunks(max_vertices))
+            .map(|((vertices, colors), surfaces)| {
+                build_mesh(
+                    vertices,
+                    colors.to_vec(),
+                    surfaces.to_vec(),
+                    settings.tangents,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Reorders a mesh's quads so those furthest from `viewer`, in the mesh's local space,
+/// are drawn first. Blending is only correct when translucent faces are drawn back to
+/// front.
+pub fn sort_quads_back_to_front(mesh: &mut Mesh, viewer: Vec3) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+
+    let mut quads: Vec<(u32, f32)> = positions
+        .chunks(4)
+        .enumerate()
+        .map(|(quad, vertices)| {
+            let centre = vertices.iter().map(|v| Vec3::from(*v)).sum::<Vec3>() / 4.0;
+            (quad as u32, centre.distance_squared(viewer))
+        })
+        .collect();
+    quads.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let vertex_count = positions.len();
+    mesh.insert_indices(quad_indices(quads.iter().map(|q| q.0), vertex_count));
+}
+
+/// Indices for two triangles per quad. They are 16 bit when every vertex can be
+/// addressed with 16 bits, halving the size of the index buffer.
+fn quad_indices(quads: impl Iterator<Item = u32>, vertex_count: usize) -> Indices {
+    let indices = quads.flat_map(|quad| {
+        let start = quad * 4;
+        [start, start + 1, start + 2, start + 2, start + 1, start + 3]
+    });
+    if vertex_count <= u16::MAX as usize + 1 {
+        Indices::U16(indices.map(|index| index as u16).collect())
+    } else {
+        Indices::U32(indices.collect())
     }
+}
+
+/// Builds a mesh from quads of four vertices each, with tangents if `tangents` is set.
+fn build_mesh(
+    vertices: &[Vertex],
+    colors: Vec<[f32; 4]>,
+    surfaces: Vec<[f32; 2]>,
+    tangents: bool,
+) -> Mesh {
+    let indices = quad_indices(0..vertices.len() as u32 / 4, vertices.len());
 
     let mut mesh = Mesh::new(
         bevy::render::mesh::PrimitiveTopology::TriangleList,
         RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
     );
-    mesh.insert_indices(Indices::U32(indices));
+    mesh.insert_indices(indices);
     mesh.insert_attribute(
         Mesh::ATTRIBUTE_POSITION,
         VertexAttributeValues::Float32x3(vertices.iter().map(|v| v.position).collect()),
@@ -205,5 +712,627 @@ pub fn generate_chunk_mesh(
         Mesh::ATTRIBUTE_UV_0,
         VertexAttributeValues::Float32x2(vertices.iter().map(|v| v.uv).collect()),
     );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_1,
+        VertexAttributeValues::Float32x2(surfaces),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_COLOR,
+        VertexAttributeValues::Float32x4(colors),
+    );
+    if tangents {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_TANGENT,
+            VertexAttributeValues::Float32x4(quad_tangents(vertices)),
+        );
+    }
     mesh
 }
+
+/// Per-vertex tangents for quads of four vertices each, pointing along increasing u with
+/// the handedness of the uv layout in w. Every vertex of a quad lies in one plane with a
+/// linear uv mapping, so the tangent of its first triangle holds for the whole quad.
+fn quad_tangents(vertices: &[Vertex]) -> Vec<[f32; 4]> {
+    vertices
+        .chunks(4)
+        .flat_map(|quad| {
+            let position = |i: usize| Vec3::from(quad[i].position);
+            let uv = |i: usize| Vec2::from(quad[i].uv);
+            let (edge1, edge2) = (position(1) - position(0), position(2) - position(0));
+            let (duv1, duv2) = (uv(1) - uv(0), uv(2) - uv(0));
+
+            let determinant = duv1.x * duv2.y - duv2.x * duv1.y;
+            let r = if determinant == 0.0 {
+                0.0
+            } else {
+                1.0 / determinant
+            };
+            let tangent = (edge1 * duv2.y - edge2 * duv1.y) * r;
+            let bitangent = (edge2 * duv1.x - edge1 * duv2.x) * r;
+
+            quad.iter().map(move |vertex| {
+                let normal = Vec3::from(vertex.normal);
+                // keep the tangent perpendicular to the normal, which may have been smoothed
+                let orthogonal = (tangent - normal * normal.dot(tangent)).normalize_or_zero();
+                let handedness = if normal.cross(orthogonal).dot(bitangent) < 0.0 {
+                    -1.0
+                } else {
+                    1.0
+                };
+                orthogonal.extend(handedness).to_array()
+            })
+        })
+        .collect()
+}
+
+/// Looks up a neighbouring block while meshing. Positions outside the chunk are logged
+/// and treated as air rather than crashing the mesh task.
+fn mesh_block_at(chunk: &ChunkData, block_coord: U16Vec3) -> BlockType {
+    chunk.get_block_at(block_coord).unwrap_or_else(|| {
+        warn!(
+            "mesh query for block {:?} is outside its chunk",
+            block_coord
+        );
+        BlockType::Air
+    })
+}
+
+/// Replaces each vertex normal with the average of the normals of every vertex sharing
+/// its position.
+fn average_normals(vertices: &mut [Vertex]) {
+    // vertices lie on a half block grid, so doubling positions gives exact keys
+    let key = |v: &Vertex| (Vec3::from(v.position) * 2.0).round().as_ivec3();
+
+    let mut normals: HashMap<IVec3, Vec3> = HashMap::new();
+    for v in vertices.iter() {
+        *normals.entry(key(v)).or_default() += Vec3::from(v.normal);
+    }
+
+    for v in vertices.iter_mut() {
+        let normal = normals[&key(v)].normalize_or_zero();
+        if normal != Vec3::ZERO {
+            v.normal = normal.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::{Arc, RwLock},
+    };
+
+    use bevy::{
+        math::{I64Vec2, I64Vec3, U16Vec3, Vec3},
+        render::mesh::{Indices, Mesh, VertexAttributeValues},
+    };
+
+    use crate::{
+        block::{BlockType, ATLAS_TILES, GRASS_SIDE_TILE},
+        chunks::{
+            chunk::{ChunkCoordinate, ChunkData, ChunkSlices},
+            generate::{biome::grass_tint, noise::NoiseGenerator},
+            material::RenderStyle,
+        },
+        settings::{RendererSettings, TerrainSettings},
+    };
+
+    use super::{
+        column_height, generate_chunk, generate_chunk_geometry, is_shore, quad_indices,
+        sort_quads_back_to_front, ChunkMeshes, SEA_LEVEL, WORLD_BOTTOM,
+    };
+
+    fn generate_chunk_mesh(
+        chunk: Arc<ChunkData>,
+        adjacent_chunks: Vec<Option<Arc<ChunkData>>>,
+        settings: RendererSettings,
+    ) -> ChunkMeshes {
+        generate_chunk_geometry(chunk, adjacent_chunks, None, ChunkSlices::ALL).to_meshes(settings)
+    }
+
+    fn mesh_chunk(chunk_data: ChunkData, settings: RendererSettings) -> Vec<Mesh> {
+        generate_chunk_mesh(Arc::new(chunk_data), vec![None; 6], settings).opaque
+    }
+
+    fn positions(mesh: &Mesh) -> Vec<[f32; 3]> {
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+            _ => panic!("mesh has no positions"),
+        }
+    }
+
+    #[test]
+    fn test_slab_does_not_hide_neighbouring_face() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(2, 1, 1), BlockType::StoneSlab);
+
+        let mesh = &mesh_chunk(chunk_data, RendererSettings::default())[0];
+        let positions = positions(mesh);
+
+        // the cube keeps all six faces, the slab loses the one against the cube
+        assert_eq!(6 * 4 + 5 * 4, positions.len());
+        let slab_top = positions
+            .iter()
+            .filter(|p| p[0] > 1.5)
+            .map(|p| p[1])
+            .fold(f32::MIN, f32::max);
+        assert_eq!(1.0, slab_top);
+    }
+
+    #[test]
+    fn test_plant_does_not_hide_neighbouring_face() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 1), BlockType::TallGrass);
+        let settings = RendererSettings {
+            instanced_plants: false,
+            ..RendererSettings::default()
+        };
+
+        let mesh = &mesh_chunk(chunk_data, settings)[0];
+
+        // six cube faces plus four plant quads
+        assert_eq!(6 * 4 + 4 * 4, positions(mesh).len());
+    }
+
+    #[test]
+    fn test_instanced_plants_are_left_out_of_mesh() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 1), BlockType::TallGrass);
+
+        let meshes = generate_chunk_mesh(
+            Arc::new(chunk_data),
+            vec![None; 6],
+            RendererSettings::default(),
+        );
+
+        assert_eq!(6 * 4, positions(&meshes.opaque[0]).len());
+        assert_eq!(1, meshes.plants.len());
+        assert_eq!(Vec3::new(1.0, 2.0, 1.0), meshes.plants[0].position);
+        assert_eq!(BlockType::TallGrass, meshes.plants[0].block);
+    }
+
+    #[test]
+    fn test_smooth_normals_average_shared_vertices() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+
+        let settings = RendererSettings {
+            smooth_normals: true,
+            ..Default::default()
+        };
+        let mesh = &mesh_chunk(chunk_data, settings)[0];
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("mesh has no normals");
+        };
+
+        // every corner of a lone cube is shared by three faces
+        for normal in normals {
+            for component in normal {
+                assert!((component.abs() - 1.0 / 3.0f32.sqrt()).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mesh_is_split_at_vertex_cap() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(5, 5, 5), BlockType::Stone);
+
+        let settings = RendererSettings {
+            max_mesh_vertices: 10,
+            ..Default::default()
+        };
+        let meshes = mesh_chunk(chunk_data, settings);
+
+        // twelve faces at two whole quads per mesh
+        assert_eq!(6, meshes.len());
+        for mesh in meshes.iter() {
+            assert_eq!(8, positions(mesh).len());
+            assert_eq!(12, mesh.indices().unwrap().len());
+            assert!(mesh.indices().unwrap().iter().all(|i| i < 8));
+        }
+    }
+
+    #[test]
+    fn test_height_scale_changes_terrain_height() {
+        let mut noise = NoiseGenerator::new(7);
+        // much of the world is flat ocean floor, so find a chunk with some land in it
+        let column = (0..64 * 64)
+            .map(|i| I64Vec2::new(i % 64, i / 64) * 256)
+            .find(|&column| noise.get(column) > 0.1)
+            .expect("no land near the origin");
+        let noise_generator = Arc::new(RwLock::new(noise));
+        let solid_blocks = |height_scale| {
+            let terrain = TerrainSettings {
+                height_scale,
+                ..Default::default()
+            };
+            (1..24)
+                .map(|y| {
+                    let coord = ChunkCoordinate(I64Vec3::new(column.x / 16, y, column.y / 16));
+                    generate_chunk(noise_generator.clone(), coord, 256, terrain)
+                        .unwrap()
+                        .blocks()
+                        .values()
+                        .filter(|b| b.is_solid())
+                        .count()
+                })
+                .sum::<usize>()
+        };
+
+        let flat = solid_blocks(0.5);
+        let normal = solid_blocks(1.0);
+        let mountainous = solid_blocks(2.0);
+        assert!(flat < normal);
+        assert!(normal < mountainous);
+    }
+
+    #[test]
+    fn test_noisy_bedrock_floor_is_uneven() {
+        let noise_generator = Arc::new(RwLock::new(NoiseGenerator::new(7)));
+        let chunk = |y| {
+            let coord = ChunkCoordinate(I64Vec3::new(0, y, 0));
+            generate_chunk(
+                noise_generator.clone(),
+                coord,
+                256,
+                TerrainSettings::default(),
+            )
+            .unwrap()
+        };
+        let floor = chunk(WORLD_BOTTOM / 16);
+        let below = chunk(WORLD_BOTTOM / 16 - 1);
+
+        let thicknesses: HashSet<_> = (0..16)
+            .flat_map(|x| (0..16).map(move |z| (x, z)))
+            .map(|(x, z)| {
+                (0..16)
+                    .take_while(|y| {
+                        floor.get_block_at(U16Vec3::new(x, *y, z)) == Some(BlockType::Bedrock)
+                    })
+                    .count()
+            })
+            .collect();
+        assert_eq!(HashSet::from([1, 2, 3, 4]), thicknesses);
+        assert!(below
+            .blocks()
+            .values()
+            .all(|block| *block == BlockType::Air));
+    }
+
+    #[test]
+    fn test_shorelines_are_sand() {
+        let mut noise = NoiseGenerator::new(7);
+        let terrain = TerrainSettings::default();
+        let surface_block = |noise: &mut NoiseGenerator, column: I64Vec2| {
+            let height = column_height(noise, column, 256, terrain) as i64 - 1;
+            let coord = ChunkCoordinate(
+                I64Vec3::new(column.x, height, column.y).div_euclid(I64Vec3::splat(16)),
+            );
+            let noise_generator = Arc::new(RwLock::new(NoiseGenerator::new(7)));
+            let local = I64Vec3::new(column.x, height, column.y).rem_euclid(I64Vec3::splat(16));
+            generate_chunk(noise_generator, coord, 256, terrain)
+                .unwrap()
+                .get_block_at(local.as_u16vec3())
+        };
+
+        // land above the grass line but low enough for beaches, both on the coast and
+        // away from it
+        let lowland: Vec<_> = (0..64 * 64)
+            .map(|i| I64Vec2::new(i % 64, i / 64) * 128)
+            .filter(|&column| {
+                let surface = column_height(&mut noise, column, 256, terrain) as i64 - 1;
+                (terrain.grass_height..=SEA_LEVEL + terrain.beach_height).contains(&surface)
+            })
+            .collect();
+        let shore = *lowland
+            .iter()
+            .find(|&&column| is_shore(&mut noise, column, 256, terrain))
+            .expect("no shoreline near the origin");
+        let inland = *lowland
+            .iter()
+            .find(|&&column| !is_shore(&mut noise, column, 256, terrain))
+            .expect("no inland lowland near the origin");
+
+        assert_eq!(Some(BlockType::Sand), surface_block(&mut noise, shore));
+        assert_eq!(Some(BlockType::Grass), surface_block(&mut noise, inland));
+    }
+
+    #[test]
+    fn test_light_crosses_chunk_border() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        let mut adjacent = ChunkData::default();
+        adjacent.set_block_at(U16Vec3::new(15, 1, 1), BlockType::Lava);
+
+        // the -x neighbour
+        let mut adjacent_chunks = vec![None; 6];
+        adjacent_chunks[3] = Some(Arc::new(adjacent));
+        let mesh = &generate_chunk_mesh(
+            Arc::new(chunk_data),
+            adjacent_chunks,
+            RendererSettings::default(),
+        )
+        .opaque[0];
+
+        let (
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x4(colors)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR),
+        )
+        else {
+            panic!("mesh has no normals or colours");
+        };
+
+        // the face towards the lava is one block further from it than the border
+        let light = normals
+            .iter()
+            .zip(colors.iter())
+            .find(|(normal, _)| normal[0] < -0.5)
+            .map(|(_, color)| color[3])
+            .unwrap();
+        assert_eq!(14.0 / 15.0, light);
+    }
+
+    #[test]
+    fn test_water_is_meshed_separately_and_sorted() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 5), BlockType::Water);
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 9), BlockType::Water);
+
+        let meshes = generate_chunk_mesh(
+            Arc::new(chunk_data),
+            vec![None; 6],
+            RendererSettings::default(),
+        );
+        assert_eq!(6 * 4, positions(&meshes.opaque[0]).len());
+        let mut water = meshes.translucent[0].clone();
+        assert_eq!(12 * 4, positions(&water).len());
+
+        // viewed from far along +z, the first quad drawn belongs to the farther block at z = 5
+        sort_quads_back_to_front(&mut water, Vec3::new(1.0, 1.0, 100.0));
+        let positions = positions(&water);
+        let first = water.indices().unwrap().iter().next().unwrap();
+        assert!(positions[first][2] < 6.0);
+    }
+
+    #[test]
+    fn test_glass_culls_shared_faces_only() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Glass);
+        chunk_data.set_block_at(U16Vec3::new(2, 1, 1), BlockType::Glass);
+        chunk_data.set_block_at(U16Vec3::new(3, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 5), BlockType::RedGlass);
+
+        let meshes = generate_chunk_mesh(
+            Arc::new(chunk_data),
+            vec![None; 6],
+            RendererSettings::default(),
+        );
+        // the stone is seen through the glass, so none of its faces are hidden
+        assert_eq!(6 * 4, positions(&meshes.opaque[0]).len());
+        // the glass pair loses the face between them and the face against the stone
+        let glass = &meshes.translucent[0];
+        assert_eq!((5 + 4 + 6) * 4, positions(glass).len());
+
+        let Some(VertexAttributeValues::Float32x4(colors)) = glass.attribute(Mesh::ATTRIBUTE_COLOR)
+        else {
+            panic!("mesh has no colours");
+        };
+        let red = BlockType::RedGlass.tint();
+        assert_eq!(6 * 4, colors.iter().filter(|c| c[..3] == red).count());
+    }
+
+    #[test]
+    fn test_vertex_count_covers_opaque_and_translucent_meshes() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 5), BlockType::Water);
+
+        let meshes = generate_chunk_mesh(
+            Arc::new(chunk_data),
+            vec![None; 6],
+            RendererSettings::default(),
+        );
+        assert_eq!(2 * 6 * 4, meshes.vertex_count());
+    }
+
+    #[test]
+    fn test_only_dirty_slices_are_remeshed() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        let previous = generate_chunk_geometry(
+            Arc::new(chunk_data.clone()),
+            vec![None; 6],
+            None,
+            ChunkSlices::ALL,
+        );
+
+        // edit two slices but only mark the bottom one as dirty
+        chunk_data.set_block_at(U16Vec3::new(5, 1, 5), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(5, 9, 5), BlockType::Stone);
+        let chunk_data = Arc::new(chunk_data);
+        let partial = generate_chunk_geometry(
+            chunk_data.clone(),
+            vec![None; 6],
+            Some(previous),
+            ChunkSlices::around(1, 16),
+        )
+        .to_meshes(RendererSettings::default());
+        let full = generate_chunk_mesh(chunk_data, vec![None; 6], RendererSettings::default());
+
+        assert_eq!(2 * 6 * 4, positions(&partial.opaque[0]).len());
+        assert_eq!(3 * 6 * 4, positions(&full.opaque[0]).len());
+    }
+
+    #[test]
+    fn test_index_type_fits_vertex_count() {
+        let small = quad_indices(0..2, 8);
+        assert!(matches!(small, Indices::U16(_)));
+        assert_eq!(
+            vec![0, 1, 2, 2, 1, 3, 4, 5, 6, 6, 5, 7],
+            small.iter().collect::<Vec<_>>()
+        );
+
+        // the largest mesh whose vertices can all be addressed with 16 bits
+        assert!(matches!(quad_indices(0..16384, 65536), Indices::U16(_)));
+
+        let large = quad_indices(0..16385, 65540);
+        assert!(matches!(large, Indices::U32(_)));
+        assert_eq!(Some(65539), large.iter().max());
+    }
+
+    #[test]
+    fn test_chunk_mesh_uses_16_bit_indices() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+
+        let mesh = mesh_chunk(chunk_data, RendererSettings::default());
+
+        assert!(matches!(mesh[0].indices(), Some(Indices::U16(_))));
+    }
+
+    #[test]
+    fn test_tangents_follow_uvs() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        let settings = RendererSettings {
+            tangents: true,
+            ..RendererSettings::default()
+        };
+
+        let mesh = &mesh_chunk(chunk_data, settings)[0];
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x2(uvs)),
+            Some(VertexAttributeValues::Float32x4(tangents)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+            mesh.attribute(Mesh::ATTRIBUTE_TANGENT),
+        )
+        else {
+            panic!("mesh is missing attributes");
+        };
+
+        assert_eq!(positions.len(), tangents.len());
+        for quad in 0..positions.len() / 4 {
+            let i = quad * 4;
+            let tangent = Vec3::from_slice(&tangents[i]);
+            assert!((tangent.length() - 1.0).abs() < 1e-4);
+            assert!(tangent.dot(Vec3::from(normals[i])).abs() < 1e-4);
+            assert_eq!(1.0, tangents[i][3].abs());
+
+            // moving along the tangent increases u across every edge of the quad
+            for j in i + 1..i + 4 {
+                let along = (Vec3::from(positions[j]) - Vec3::from(positions[i])).dot(tangent);
+                let du = uvs[j][0] - uvs[i][0];
+                assert!(along.abs() < 1e-4 || along.signum() == du.signum());
+            }
+        }
+    }
+
+    #[test]
+    fn test_tangents_are_optional() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+
+        let mesh = &mesh_chunk(chunk_data, RendererSettings::default())[0];
+
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_TANGENT).is_none());
+    }
+
+    #[test]
+    fn test_grass_sides_use_upright_side_tile() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Grass);
+
+        let mesh = &mesh_chunk(chunk_data, RendererSettings::default())[0];
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x2(uvs)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+        )
+        else {
+            panic!("mesh is missing attributes");
+        };
+
+        let tile_width = 1.0 / ATLAS_TILES as f32;
+        for ((position, normal), uv) in positions.iter().zip(normals).zip(uvs) {
+            let tile = if normal[1] <= 0.0 {
+                // the top edge of every side is the top of the tile, where the overlay is
+                assert_eq!(position[1] > 1.0, uv[1] == 0.0);
+                GRASS_SIDE_TILE
+            } else {
+                BlockType::Grass.texture_index().unwrap()
+            };
+            let start = tile as f32 * tile_width;
+            assert!(uv[0] >= start - 1e-6 && uv[0] <= start + tile_width + 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_grass_tint_skips_the_dirt_underneath() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Grass);
+
+        let mesh = &mesh_chunk(chunk_data, RendererSettings::default())[0];
+        let (
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x4(colors)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR),
+        )
+        else {
+            panic!("mesh is missing attributes");
+        };
+
+        let tint = grass_tint(0.0);
+        for (normal, color) in normals.iter().zip(colors) {
+            if normal[1] < 0.0 {
+                assert_eq!([1.0, 1.0, 1.0], color[..3]);
+            } else {
+                assert_eq!(tint[..3], color[..3]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_colored_style_bakes_block_colors() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Sand);
+        let settings = RendererSettings {
+            render_style: RenderStyle::Colored,
+            ..RendererSettings::default()
+        };
+
+        let color = |mesh: &Mesh| match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => colors[0],
+            _ => panic!("mesh has no colours"),
+        };
+        let textured = color(&mesh_chunk(chunk_data.clone(), RendererSettings::default())[0]);
+        let colored = color(&mesh_chunk(chunk_data, settings)[0]);
+
+        assert_eq!([1.0, 1.0, 1.0], textured[..3]);
+        assert_eq!(BlockType::Sand.base_color(), colored[..3]);
+        // alpha still carries the block light
+        assert_eq!(textured[3], colored[3]);
+    }
+}
@@ -1,2 +1,6 @@
+pub mod biome;
+pub mod curve;
+pub mod features;
 pub mod generator;
 pub mod noise;
+pub mod preset;
@@ -1,14 +1,40 @@
 use std::cell::RefCell;
 
-use bevy::{math::I64Vec2, utils::HashMap};
+use bevy::{
+    math::{I64Vec2, I64Vec3},
+    utils::HashMap,
+};
 use noise::{
-    Cache, Clamp, Fbm, MultiFractal, NoiseFn, Perlin, ScalePoint, Seedable, Select, Turbulence,
+    Cache, Clamp, Fbm, MultiFractal, NoiseFn, Perlin, ScaleBias, ScalePoint, Seedable, Select,
+    Turbulence,
 };
 
-pub fn world_noise(seed: u32) -> impl NoiseFn<f64, 2> {
-    let scale: f64 = 1.0 / 1024.0;
+/// Shape of the terrain height noise, which worldgen presets vary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseParams {
+    /// Noise units per block. Smaller values stretch terrain features out.
+    pub scale: f64,
+    /// Base frequency of the continents, with mountains at a multiple of it.
+    pub frequency: f64,
+    /// Multiplier on the noise, where 0 gives perfectly flat terrain.
+    pub amplitude: f64,
+    /// Added to the noise after the amplitude, raising or sinking all terrain.
+    pub bias: f64,
+}
+
+impl Default for NoiseParams {
+    fn default() -> Self {
+        Self {
+            scale: 1.0 / 1024.0,
+            frequency: 0.2,
+            amplitude: 1.0,
+            bias: 0.0,
+        }
+    }
+}
 
-    let freq = 0.2;
+pub fn world_noise(seed: u32, params: NoiseParams) -> impl NoiseFn<f64, 2> {
+    let freq = params.frequency;
     let lacunarity = 2.2089;
     let base_continents = Fbm::<Perlin>::new(seed)
         .set_frequency(freq)
@@ -31,16 +57,52 @@ pub fn world_noise(seed: u32) -> impl NoiseFn<f64, 2> {
         .set_bounds(0.2, 1.0)
         .set_falloff(0.1);
 
-    let generator = Clamp::new(ScalePoint::new(combined).set_scale(scale))
+    let shaped = ScaleBias::new(ScalePoint::new(combined).set_scale(params.scale))
+        .set_scale(params.amplitude)
+        .set_bias(params.bias);
+    let generator = Clamp::new(shaped)
         .set_lower_bound(0.0)
         .set_upper_bound(10.0);
 
     Cache::new(generator)
 }
 
+/// Low frequency noise in `0.0..=1.0` used to pick the biome of each column.
+pub fn temperature_noise(seed: u32) -> impl NoiseFn<f64, 2> {
+    let scale: f64 = 1.0 / 768.0;
+
+    let temperature = Fbm::<Perlin>::new(seed.wrapping_add(1))
+        .set_frequency(1.0)
+        .set_octaves(3)
+        .set_persistence(0.5);
+
+    Clamp::new(
+        ScaleBias::new(ScalePoint::new(temperature).set_scale(scale))
+            .set_scale(0.5)
+            .set_bias(0.5),
+    )
+    .set_lower_bound(0.0)
+    .set_upper_bound(1.0)
+}
+
+/// 3D noise in roughly `-1.0..=1.0` whose peaks are carved out as caves.
+pub fn cave_noise(seed: u32) -> impl NoiseFn<f64, 3> {
+    let scale: f64 = 1.0 / 32.0;
+
+    let caves = Fbm::<Perlin>::new(seed.wrapping_add(3))
+        .set_frequency(1.0)
+        .set_octaves(2)
+        .set_persistence(0.5);
+
+    ScalePoint::new(caves).set_scale(scale)
+}
+
 pub struct NoiseGenerator {
+    seed: u32,
     cache: RefCell<HashMap<I64Vec2, f64>>,
     source: Box<dyn NoiseFn<f64, 2>>,
+    temperature: Box<dyn NoiseFn<f64, 2>>,
+    caves: Box<dyn NoiseFn<f64, 3>>,
 }
 
 unsafe impl Send for NoiseGenerator {}
@@ -48,14 +110,25 @@ unsafe impl Sync for NoiseGenerator {}
 
 impl NoiseGenerator {
     pub fn new(seed: u32) -> Self {
+        Self::with_params(seed, NoiseParams::default())
+    }
+
+    pub fn with_params(seed: u32, params: NoiseParams) -> Self {
         Self {
+            seed,
             cache: RefCell::new(HashMap::new()),
-            source: Box::new(world_noise(seed)),
+            source: Box::new(world_noise(seed, params)),
+            temperature: Box::new(temperature_noise(seed)),
+            caves: Box::new(cave_noise(seed)),
         }
     }
 }
 
 impl NoiseGenerator {
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
     pub fn get(&mut self, pos: I64Vec2) -> f64 {
         if self.cache.borrow().contains_key(&pos) {
             return *self.cache.borrow().get(&pos).unwrap();
@@ -66,4 +139,12 @@ impl NoiseGenerator {
 
         value
     }
+
+    pub fn get_temperature(&self, pos: I64Vec2) -> f64 {
+        self.temperature.get([pos.x as f64, pos.y as f64])
+    }
+
+    pub fn get_cave(&self, pos: I64Vec3) -> f64 {
+        self.caves.get([pos.x as f64, pos.y as f64, pos.z as f64])
+    }
 }
@@ -0,0 +1,103 @@
+use serde::Deserialize;
+
+use super::noise::NoiseParams;
+use crate::settings::TerrainSettings;
+
+/// A named style of world, bundling the shape of the terrain noise with adjustments to the
+/// block thresholds in the terrain settings.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorldPreset {
+    /// Rolling continents with the occasional mountain range.
+    #[default]
+    Default,
+    /// Much taller, more rugged mountains, with snow and stone pushed up to match.
+    Amplified,
+    /// Level grassland a few blocks above sea level, everywhere.
+    Flat,
+    /// Small islands scattered across open ocean, ringed by wide beaches.
+    Islands,
+}
+
+impl WorldPreset {
+    pub fn noise_params(&self) -> NoiseParams {
+        let default = NoiseParams::default();
+        match self {
+            Self::Default => default,
+            Self::Amplified => NoiseParams {
+                frequency: default.frequency * 1.5,
+                ..default
+            },
+            Self::Flat => NoiseParams {
+                amplitude: 0.0,
+                bias: 0.15,
+                ..default
+            },
+            Self::Islands => NoiseParams {
+                scale: default.scale * 4.0,
+                bias: -0.05,
+                ..default
+            },
+        }
+    }
+
+    /// The terrain settings adjusted for this preset. Thresholds are scaled rather than
+    /// replaced, so tweaks made in the settings still carry over.
+    pub fn terrain(&self, terrain: TerrainSettings) -> TerrainSettings {
+        match self {
+            Self::Default | Self::Flat => terrain,
+            Self::Amplified => TerrainSettings {
+                height_scale: terrain.height_scale * 3.0,
+                snow_height: terrain.snow_height * 2,
+                stone_height: terrain.stone_height * 2,
+                ..terrain
+            },
+            Self::Islands => TerrainSettings {
+                beach_width: terrain.beach_width * 2,
+                ..terrain
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::I64Vec2;
+
+    use crate::{
+        chunks::generate::{
+            generator::{column_height, SEA_LEVEL},
+            noise::NoiseGenerator,
+        },
+        settings::TerrainSettings,
+    };
+
+    use super::WorldPreset;
+
+    fn tallest_column(preset: WorldPreset) -> u64 {
+        let terrain = preset.terrain(TerrainSettings::default());
+        let mut noise = NoiseGenerator::with_params(7, preset.noise_params());
+        (0..64 * 64)
+            .map(|i| I64Vec2::new(i % 64, i / 64) * 256)
+            .map(|column| column_height(&mut noise, column, 256, terrain))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_amplified_mountains_are_taller() {
+        assert!(tallest_column(WorldPreset::Amplified) > 2 * tallest_column(WorldPreset::Default));
+    }
+
+    #[test]
+    fn test_flat_terrain_is_level_above_sea() {
+        let terrain = WorldPreset::Flat.terrain(TerrainSettings::default());
+        let mut noise = NoiseGenerator::with_params(7, WorldPreset::Flat.noise_params());
+        let heights: Vec<_> = (0..16)
+            .map(|i| column_height(&mut noise, I64Vec2::new(i * 100, -i * 37), 256, terrain))
+            .collect();
+
+        assert!(heights.iter().all(|height| *height == heights[0]));
+        assert!(heights[0] as i64 > SEA_LEVEL);
+    }
+}
@@ -1,23 +1,199 @@
 use bevy::{
-    asset::Asset,
-    pbr::{MaterialPipeline, MaterialPipelineKey},
+    asset::{Asset, LoadState},
+    pbr::{ExtendedMaterial, MaterialExtension, MaterialPipeline, MaterialPipelineKey},
     prelude::*,
     reflect::TypePath,
     render::{
         mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssetUsages,
         render_resource::{
-            AsBindGroup, Face, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+            AsBindGroup, Extent3d, Face, PolygonMode, RenderPipelineDescriptor, ShaderDefVal,
+            ShaderRef, SpecializedMeshPipelineError, TextureDimension, TextureFormat,
         },
+        renderer::RenderDevice,
+        settings::WgpuFeatures,
+        texture::{ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
     },
 };
 
+use serde::Deserialize;
+
+use super::chunk_loader::{Chunk, ChunkLoader};
+use crate::{
+    block::{BlockType, ATLAS_TILES, GRASS_SIDE_TILE, WATER_TILE},
+    settings::Settings,
+};
+
+const RENDER_STYLE_KEY: KeyCode = KeyCode::F7;
+const CULL_MODE_KEY: KeyCode = KeyCode::F8;
+
+/// How chunk meshes are drawn.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderStyle {
+    /// Blocks are drawn with their atlas textures.
+    #[default]
+    Textured,
+    /// Each block face is a single solid colour, for low-end machines.
+    Flat,
+    /// Blocks are drawn in colours baked into the mesh by block type and shaded by height,
+    /// so the world is recognisable without the block atlas.
+    Colored,
+    /// Only the edges of each triangle are drawn, for debugging meshes.
+    Wireframe,
+}
+
+impl RenderStyle {
+    /// Whether a device with `features` can draw this style. Wireframes need line polygons,
+    /// which not every adapter supports.
+    pub fn is_supported(self, features: WgpuFeatures) -> bool {
+        self != Self::Wireframe || features.contains(WgpuFeatures::POLYGON_MODE_LINE)
+    }
+
+    /// The style after this one that a device with `features` can draw.
+    fn next(self, features: WgpuFeatures) -> Self {
+        let next = match self {
+            Self::Textured => Self::Flat,
+            Self::Flat => Self::Colored,
+            Self::Colored => Self::Wireframe,
+            Self::Wireframe => Self::Textured,
+        };
+        if next.is_supported(features) {
+            next
+        } else {
+            next.next(features)
+        }
+    }
+}
+
+/// Tints applied per block type so that terrain stays distinguishable with colour vision
+/// deficiencies, pushing blocks apart along the colours each deficiency can still tell apart.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorPalette {
+    #[default]
+    Default,
+    /// Red-green, from missing green cones. Grass is pushed towards teal and sand and
+    /// lava towards yellow.
+    Deuteranopia,
+    /// Red-green, from missing red cones. Like deuteranopia, with reds brightened too as
+    /// they appear dark.
+    Protanopia,
+    /// Blue-yellow. Water is pushed towards cyan and sand towards pink.
+    Tritanopia,
+}
+
+impl ColorPalette {
+    /// Colour multiplied onto a block's tint.
+    pub fn tint(&self, block: BlockType) -> [f32; 3] {
+        match (self, block) {
+            (Self::Default, _) => [1.0; 3],
+            (Self::Deuteranopia | Self::Protanopia, BlockType::Grass | BlockType::TallGrass) => {
+                [0.6, 1.0, 1.8]
+            }
+            (Self::Deuteranopia | Self::Protanopia, BlockType::Sand) => [1.2, 1.1, 0.4],
+            (Self::Deuteranopia | Self::Protanopia, BlockType::Water) => [0.7, 0.9, 1.3],
+            (Self::Deuteranopia, BlockType::Lava) => [1.0, 1.2, 0.4],
+            (Self::Protanopia, BlockType::Lava) => [1.4, 1.4, 0.4],
+            (Self::Tritanopia, BlockType::Grass | BlockType::TallGrass) => [1.1, 1.0, 0.6],
+            (Self::Tritanopia, BlockType::Sand) => [1.2, 0.8, 0.85],
+            (Self::Tritanopia, BlockType::Water) => [0.5, 1.0, 1.1],
+            (Self::Tritanopia, BlockType::Lava) => [1.2, 0.6, 0.6],
+            (_, BlockType::Stone | BlockType::StoneSlab | BlockType::Cobblestone) => {
+                [0.85, 0.85, 0.9]
+            }
+            _ => [1.0; 3],
+        }
+    }
+}
+
+/// Which faces of chunk meshes are culled. Chunk faces are wound so that culling front
+/// faces hides the ones facing away from the camera, so switching modes shows at a glance
+/// whether a meshing change inverted the winding.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    #[default]
+    Front,
+    Back,
+    None,
+}
+
+impl CullMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Front => Self::Back,
+            Self::Back => Self::None,
+            Self::None => Self::Front,
+        }
+    }
+
+    fn face(self) -> Option<Face> {
+        match self {
+            Self::Front => Some(Face::Front),
+            Self::Back => Some(Face::Back),
+            Self::None => None,
+        }
+    }
+}
+
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[bind_group_data(ChunkMaterialKey)]
 pub struct ChunkMaterial {
+    /// Tint and opacity of water. Other blocks are drawn in their texture's colours.
     #[uniform(0)]
     pub color: Color,
     #[texture(1)]
     #[sampler(2)]
     pub texture: Option<Handle<Image>>,
+    /// How far through fading in the chunk is, from 0 (invisible) to 1 (opaque).
+    #[uniform(3)]
+    pub fade: f32,
+    /// Distances in blocks over which terrain blends towards flat colours, hiding the
+    /// detail lost in distant meshes.
+    #[uniform(4)]
+    pub lod_fade: Vec2,
+    /// Distance in blocks beyond which terrain is not drawn.
+    #[uniform(5)]
+    pub view_distance: f32,
+    /// Average colour of each atlas tile, which the flat style and distant terrain are
+    /// drawn in. Filled in from the atlas once it loads.
+    #[uniform(6)]
+    pub tile_colors: [Vec4; ATLAS_TILES],
+    /// Opaque terrain is alpha masked, while translucent blocks like water are blended.
+    pub alpha_mode: AlphaMode,
+    pub style: RenderStyle,
+    pub cull_mode: CullMode,
+}
+
+/// The chunk material with a vertex shader that sways plants in the wind.
+pub type PlantMaterial = ExtendedMaterial<ChunkMaterial, SwayExtension>;
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct SwayExtension {
+    /// Distance in blocks the tops of plants sway by.
+    #[uniform(100)]
+    pub strength: f32,
+}
+
+impl MaterialExtension for SwayExtension {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/plant.wgsl".into()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ChunkMaterialKey {
+    style: RenderStyle,
+    cull_mode: CullMode,
+}
+
+impl From<&ChunkMaterial> for ChunkMaterialKey {
+    fn from(material: &ChunkMaterial) -> Self {
+        Self {
+            style: material.style,
+            cull_mode: material.cull_mode,
+        }
+    }
 }
 
 impl Material for ChunkMaterial {
@@ -26,16 +202,466 @@ impl Material for ChunkMaterial {
     }
 
     fn alpha_mode(&self) -> AlphaMode {
-        AlphaMode::Mask(0.5)
+        self.alpha_mode
     }
 
     fn specialize(
         _pipeline: &MaterialPipeline<Self>,
         descriptor: &mut RenderPipelineDescriptor,
         _layout: &MeshVertexBufferLayout,
-        _key: MaterialPipelineKey<Self>,
+        key: MaterialPipelineKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
-        descriptor.primitive.cull_mode = Some(Face::Front);
+        descriptor.primitive.cull_mode = key.bind_group_data.cull_mode.face();
+        // the shader picks out grass sides and water by where their tiles are in the atlas
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.extend([
+                ShaderDefVal::UInt("ATLAS_TILES".into(), ATLAS_TILES as u32),
+                ShaderDefVal::UInt("GRASS_SIDE_TILE".into(), GRASS_SIDE_TILE as u32),
+                ShaderDefVal::UInt("WATER_TILE".into(), WATER_TILE as u32),
+            ]);
+        }
+        match key.bind_group_data.style {
+            RenderStyle::Textured => {}
+            RenderStyle::Flat => {
+                if let Some(fragment) = descriptor.fragment.as_mut() {
+                    fragment.shader_defs.push("FLAT_SHADED".into());
+                }
+            }
+            RenderStyle::Colored => {
+                if let Some(fragment) = descriptor.fragment.as_mut() {
+                    fragment.shader_defs.push("BLOCK_COLORS".into());
+                }
+            }
+            // only offered when the device has the POLYGON_MODE_LINE feature
+            RenderStyle::Wireframe => descriptor.primitive.polygon_mode = PolygonMode::Line,
+        }
         Ok(())
     }
 }
+
+/// Cycles the render style of every chunk. Materials are changed in place, so loaded
+/// chunks only need remeshing when switching to or from the colored style, whose colours
+/// are baked into the mesh.
+#[allow(clippy::too_many_arguments)]
+pub fn cycle_render_style(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    mut plant_materials: ResMut<Assets<PlantMaterial>>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    chunks_query: Query<&Chunk, With<Handle<Mesh>>>,
+    mut style: Local<Option<RenderStyle>>,
+    settings_query: Query<&Settings>,
+    render_device: Res<RenderDevice>,
+) {
+    if !keys.just_pressed(RENDER_STYLE_KEY) {
+        return;
+    }
+
+    let current = style.unwrap_or_else(|| {
+        settings_query
+            .get_single()
+            .map(|settings| settings.renderer.render_style)
+            .unwrap_or_default()
+    });
+    let next = current.next(render_device.features());
+    *style = Some(next);
+
+    chunk_loader.set_render_style(next);
+    if (current == RenderStyle::Colored) != (next == RenderStyle::Colored) {
+        for chunk in chunks_query.iter() {
+            chunk_loader.mark_chunk_dirty(&mut commands, chunk.coord());
+        }
+    }
+
+    for (_, material) in chunk_materials.iter_mut() {
+        material.style = next;
+    }
+    for (_, material) in plant_materials.iter_mut() {
+        material.base.style = next;
+    }
+    info!("render style is now {:?}", next);
+}
+
+/// Cycles which faces of chunk meshes are culled, for debugging face winding.
+pub fn cycle_cull_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    mut plant_materials: ResMut<Assets<PlantMaterial>>,
+    mut cull_mode: Local<CullMode>,
+) {
+    if !keys.just_pressed(CULL_MODE_KEY) {
+        return;
+    }
+
+    *cull_mode = cull_mode.next();
+    for (_, material) in chunk_materials.iter_mut() {
+        material.cull_mode = *cull_mode;
+    }
+    for (_, material) in plant_materials.iter_mut() {
+        material.base.cull_mode = *cull_mode;
+    }
+    info!("culling {:?} faces", *cull_mode);
+}
+
+/// Swaps in a generated "missing texture" if the block atlas fails to load, so a missing
+/// file is obvious in game rather than rendering untextured.
+pub fn check_block_atlas(
+    asset_server: Res<AssetServer>,
+    mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    mut plant_materials: ResMut<Assets<PlantMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    use_missing_texture_if_failed(&asset_server, &mut chunk_materials, &mut images);
+    // plants share the atlas, so they need the missing texture too
+    use_missing_texture_if_failed(&asset_server, &mut plant_materials, &mut images);
+}
+
+/// A material drawing blocks from the block atlas.
+trait AtlasMaterial: Asset {
+    fn chunk_material(&self) -> &ChunkMaterial;
+    fn chunk_material_mut(&mut self) -> &mut ChunkMaterial;
+}
+
+impl AtlasMaterial for ChunkMaterial {
+    fn chunk_material(&self) -> &ChunkMaterial {
+        self
+    }
+
+    fn chunk_material_mut(&mut self) -> &mut ChunkMaterial {
+        self
+    }
+}
+
+impl AtlasMaterial for PlantMaterial {
+    fn chunk_material(&self) -> &ChunkMaterial {
+        &self.base
+    }
+
+    fn chunk_material_mut(&mut self) -> &mut ChunkMaterial {
+        &mut self.base
+    }
+}
+
+/// Swaps the atlas of every material whose atlas failed to load for the missing texture.
+fn use_missing_texture_if_failed<M: AtlasMaterial>(
+    asset_server: &AssetServer,
+    materials: &mut Assets<M>,
+    images: &mut Assets<Image>,
+) {
+    let failed: Vec<_> = materials
+        .iter()
+        .filter(|(_, material)| {
+            material
+                .chunk_material()
+                .texture
+                .as_ref()
+                .is_some_and(|texture| {
+                    asset_server.get_load_state(texture) == Some(LoadState::Failed)
+                })
+        })
+        .map(|(id, _)| id)
+        .collect();
+
+    for id in failed {
+        let Some(material) = materials.get_mut(id).map(M::chunk_material_mut) else {
+            continue;
+        };
+
+        let path = material
+            .texture
+            .as_ref()
+            .and_then(|texture| texture.path())
+            .map(|path| path.to_string())
+            .unwrap_or_default();
+        warn!(
+            "failed to load block atlas '{}', using missing texture",
+            path
+        );
+        let texture = missing_texture();
+        material.tile_colors = tile_colors(&texture);
+        material.texture = Some(images.add(texture));
+    }
+}
+
+/// A magenta and black checkerboard laid out like the block atlas, giving every
+/// block tile its own 2x2 checker.
+fn missing_texture() -> Image {
+    const TILE_SIZE: u32 = 16;
+    const CHECKER_SIZE: u32 = TILE_SIZE / 2;
+
+    let (width, height) = (TILE_SIZE * ATLAS_TILES as u32, TILE_SIZE);
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let magenta = (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2);
+            data.extend_from_slice(if magenta {
+                &[255, 0, 255, 255]
+            } else {
+                &[0, 0, 0, 255]
+            });
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Configures the sampler of the block atlas once it has loaded. Magnification stays
+/// nearest so close blocks keep their pixelated look, while minification uses mipmaps
+/// (and optionally anisotropic filtering) so distant terrain doesn't shimmer. Also hands
+/// the materials the average colour of each tile.
+pub fn configure_block_atlas(
+    mut events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    mut plant_materials: ResMut<Assets<PlantMaterial>>,
+    settings_query: Query<&Settings>,
+) {
+    let renderer = settings_query
+        .get_single()
+        .map(|settings| settings.renderer)
+        .unwrap_or_default();
+
+    for event in events.read() {
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+
+        let is_atlas = chunk_materials
+            .iter()
+            .any(|(_, material)| material.texture.as_ref().map(|t| t.id()) == Some(*id));
+        if !is_atlas {
+            continue;
+        }
+
+        let Some(image) = images.get_mut(*id) else {
+            continue;
+        };
+
+        let colors = tile_colors(image);
+        for (_, material) in chunk_materials.iter_mut() {
+            if material.texture.as_ref().map(|t| t.id()) == Some(*id) {
+                material.tile_colors = colors;
+            }
+        }
+        for (_, material) in plant_materials.iter_mut() {
+            if material.base.texture.as_ref().map(|t| t.id()) == Some(*id) {
+                material.base.tile_colors = colors;
+            }
+        }
+
+        if renderer.mipmaps {
+            generate_mipmaps(image);
+        }
+
+        // wgpu only allows anisotropic filtering when every filter is linear, in which case
+        // the shader snaps magnified samples to texel centres to keep them looking nearest
+        let anisotropic = renderer.anisotropy > 1;
+        let filter = if anisotropic {
+            ImageFilterMode::Linear
+        } else {
+            ImageFilterMode::Nearest
+        };
+
+        image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: ImageFilterMode::Linear,
+            anisotropy_clamp: renderer.anisotropy.max(1),
+            ..default()
+        });
+    }
+}
+
+/// Average linear colour of each tile of an RGBA8 block atlas. Worked out here rather than
+/// read from the mip chain so it doesn't depend on mipmaps being enabled.
+fn tile_colors(image: &Image) -> [Vec4; ATLAS_TILES] {
+    let mut colors = [Vec4::ONE; ATLAS_TILES];
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let tile_width = width / ATLAS_TILES;
+    let srgb = match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb => true,
+        TextureFormat::Rgba8Unorm => false,
+        _ => return colors,
+    };
+    if tile_width == 0 || image.data.len() < width * height * 4 {
+        return colors;
+    }
+
+    for (tile, color) in colors.iter_mut().enumerate() {
+        let mut sum = [0u32; 4];
+        for y in 0..height {
+            for x in tile * tile_width..(tile + 1) * tile_width {
+                let texel = &image.data[(y * width + x) * 4..][..4];
+                for (total, value) in sum.iter_mut().zip(texel) {
+                    *total += *value as u32;
+                }
+            }
+        }
+        let [r, g, b, a] = sum.map(|total| total as f32 / (tile_width * height * 255) as f32);
+        *color = if srgb {
+            Color::rgba(r, g, b, a).as_linear_rgba_f32().into()
+        } else {
+            Vec4::new(r, g, b, a)
+        };
+    }
+    colors
+}
+
+/// Appends a box-filtered mip chain to an RGBA8 image. Each level halves both dimensions,
+/// and the chain stops once a dimension becomes odd so that atlas tiles, which are
+/// power-of-two sized, never bleed into each other.
+fn generate_mipmaps(image: &mut Image) {
+    if !matches!(
+        image.texture_descriptor.format,
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+    ) || image.texture_descriptor.mip_level_count > 1
+    {
+        return;
+    }
+
+    let (mut width, mut height) = (image.width() as usize, image.height() as usize);
+    let mut level = image.data.clone();
+    let mut mip_level_count = 1;
+
+    while width % 2 == 0 && height % 2 == 0 {
+        let (next_width, next_height) = (width / 2, height / 2);
+        let mut next = vec![0; next_width * next_height * 4];
+        for y in 0..next_height {
+            for x in 0..next_width {
+                for c in 0..4 {
+                    let sample = |sx: usize, sy: usize| level[(sy * width + sx) * 4 + c] as u32;
+                    let sum = sample(x * 2, y * 2)
+                        + sample(x * 2 + 1, y * 2)
+                        + sample(x * 2, y * 2 + 1)
+                        + sample(x * 2 + 1, y * 2 + 1);
+                    next[(y * next_width + x) * 4 + c] = (sum / 4) as u8;
+                }
+            }
+        }
+
+        image.data.extend_from_slice(&next);
+        level = next;
+        width = next_width;
+        height = next_height;
+        mip_level_count += 1;
+    }
+
+    image.texture_descriptor.mip_level_count = mip_level_count;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        settings::WgpuFeatures,
+        texture::Image,
+    };
+
+    use crate::block::BlockType;
+
+    use super::{generate_mipmaps, missing_texture, tile_colors, ColorPalette, RenderStyle};
+
+    #[test]
+    fn test_missing_texture_is_checkerboard() {
+        let image = missing_texture();
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * image.width() + x) * 4) as usize;
+            &image.data[i..i + 4]
+        };
+
+        assert_eq!(&[255, 0, 255, 255], pixel(0, 0));
+        assert_eq!(&[0, 0, 0, 255], pixel(8, 0));
+        assert_eq!(&[0, 0, 0, 255], pixel(0, 8));
+        assert_eq!(&[255, 0, 255, 255], pixel(8, 8));
+    }
+
+    #[test]
+    fn test_tile_colors_average_each_tile() {
+        // half magenta and half black, so half intensity red and blue before linearising
+        let colors = tile_colors(&missing_texture());
+        for color in colors {
+            assert!((color.x - 0.214).abs() < 0.01, "{:?}", color);
+            assert_eq!(color.x, color.z);
+            assert_eq!(0.0, color.y);
+            assert_eq!(1.0, color.w);
+        }
+    }
+
+    #[test]
+    fn test_wireframe_skipped_without_line_polygons() {
+        let lines = WgpuFeatures::POLYGON_MODE_LINE;
+        assert_eq!(RenderStyle::Wireframe, RenderStyle::Colored.next(lines));
+        assert_eq!(
+            RenderStyle::Textured,
+            RenderStyle::Colored.next(WgpuFeatures::empty())
+        );
+        assert!(!RenderStyle::Wireframe.is_supported(WgpuFeatures::empty()));
+    }
+
+    #[test]
+    fn test_generate_mipmaps_stops_at_tile_size() {
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: 80,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+
+        generate_mipmaps(&mut image);
+
+        // 80x16, 40x8, 20x4, 10x2, 5x1
+        assert_eq!(5, image.texture_descriptor.mip_level_count);
+        assert_eq!(
+            (80 * 16 + 40 * 8 + 20 * 4 + 10 * 2 + 5) * 4,
+            image.data.len()
+        );
+        assert_eq!(&[255, 0, 0, 255], &image.data[image.data.len() - 4..]);
+    }
+
+    #[test]
+    fn test_deuteranopia_palette_separates_grass_and_sand() {
+        // how a colour looks with deuteranopia (Vienot et al. 1999)
+        let simulate = |c: [f32; 3]| {
+            let red_green = 0.29275 * c[0] + 0.70725 * c[1];
+            [
+                red_green,
+                red_green,
+                -0.02234 * c[0] + 0.02234 * c[1] + c[2],
+            ]
+        };
+        let seen_distance = |palette: ColorPalette| {
+            let tinted = |color: [f32; 3], block| {
+                let tint = palette.tint(block);
+                simulate([color[0] * tint[0], color[1] * tint[1], color[2] * tint[2]])
+            };
+            let grass = tinted([0.4, 0.7, 0.25], BlockType::Grass);
+            let sand = tinted([0.72, 0.64, 0.46], BlockType::Sand);
+            (0..3)
+                .map(|i| (grass[i] - sand[i]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        };
+
+        assert_eq!([1.0; 3], ColorPalette::Default.tint(BlockType::Grass));
+        assert!(
+            seen_distance(ColorPalette::Deuteranopia) > 1.5 * seen_distance(ColorPalette::Default)
+        );
+    }
+}
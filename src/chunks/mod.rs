@@ -1,4 +1,5 @@
 pub mod chunk;
 pub mod chunk_loader;
+pub mod debug;
 pub mod generate;
 pub mod material;
@@ -0,0 +1,113 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        query::With,
+        system::{Commands, Query, Res},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec3,
+    render::{color::Color, view::Visibility},
+    text::{Text, TextStyle},
+    transform::components::Transform,
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+    utils::default,
+};
+
+use crate::player::Player;
+
+const COMPASS_KEY: KeyCode = KeyCode::F10;
+
+/// A small readout of the player's position and the direction they are facing.
+#[derive(Component)]
+pub struct Compass;
+
+pub fn setup_compass(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        }),
+        Compass,
+    ));
+}
+
+pub fn toggle_compass(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut compass_query: Query<&mut Visibility, With<Compass>>,
+) {
+    if !keys.just_pressed(COMPASS_KEY) {
+        return;
+    }
+
+    for mut visibility in compass_query.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+pub fn update_compass(
+    player_query: Query<&Transform, With<Player>>,
+    mut compass_query: Query<(&mut Text, &Visibility), With<Compass>>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut text, visibility) in compass_query.iter_mut() {
+        if visibility == Visibility::Hidden {
+            continue;
+        }
+
+        let position = player.translation;
+        text.sections[0].value = format!(
+            "{:.1} {:.1} {:.1}\nfacing {}",
+            position.x,
+            position.y,
+            position.z,
+            cardinal_direction(*player.forward())
+        );
+    }
+}
+
+/// The compass direction closest to `forward` along the ground, with north towards -z and
+/// east towards +x.
+pub fn cardinal_direction(forward: Vec3) -> &'static str {
+    if forward.x.abs() > forward.z.abs() {
+        if forward.x > 0.0 {
+            "E"
+        } else {
+            "W"
+        }
+    } else if forward.z > 0.0 {
+        "S"
+    } else {
+        "N"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec3;
+
+    use super::cardinal_direction;
+
+    #[test]
+    fn test_cardinal_direction() {
+        assert_eq!("N", cardinal_direction(Vec3::NEG_Z));
+        assert_eq!("E", cardinal_direction(Vec3::new(0.9, 0.2, -0.3)));
+        assert_eq!("S", cardinal_direction(Vec3::new(0.3, -0.5, 0.8)));
+        assert_eq!("W", cardinal_direction(Vec3::NEG_X));
+    }
+}
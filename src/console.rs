@@ -0,0 +1,316 @@
+use std::str::FromStr;
+
+use bevy::{
+    asset::Assets,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::{EventReader, EventWriter},
+        query::With,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::BuildChildren,
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    math::{I64Vec3, Vec3},
+    render::{color::Color, mesh::Mesh, view::Visibility},
+    text::{Text, TextStyle},
+    transform::components::Transform,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        BackgroundColor, PositionType, Style, Val,
+    },
+    utils::default,
+    window::ReceivedCharacter,
+};
+
+use crate::{
+    block::BlockType,
+    chunks::chunk_loader::ChunkLoader,
+    new_world::NewWorld,
+    player::{Player, PlayerMovement},
+    world::World,
+};
+
+const CONSOLE_KEY: KeyCode = KeyCode::Backquote;
+const CONSOLE_HISTORY: usize = 8;
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+}
+
+impl Console {
+    fn log(&mut self, line: String) {
+        info!("{}", line);
+        self.history.push(line);
+        if self.history.len() > CONSOLE_HISTORY {
+            self.history.remove(0);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleUi;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+#[derive(Debug, PartialEq)]
+pub enum ConsoleCommand {
+    Teleport(Vec3),
+    SetBlock(I64Vec3, BlockType),
+    Seed,
+    NewWorld(Option<u32>),
+}
+
+impl FromStr for ConsoleCommand {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parts = line.split_whitespace();
+        let name = parts.next().ok_or("empty command")?;
+        let args: Vec<&str> = parts.collect();
+
+        match (name, args.as_slice()) {
+            ("tp", [x, y, z]) => {
+                let pos = Vec3::new(parse_arg(x)?, parse_arg(y)?, parse_arg(z)?);
+                if !pos.is_finite() {
+                    return Err("coordinates must be finite".to_string());
+                }
+                Ok(Self::Teleport(pos))
+            }
+            ("setblock", [x, y, z, block]) => Ok(Self::SetBlock(
+                I64Vec3::new(parse_arg(x)?, parse_arg(y)?, parse_arg(z)?),
+                block.parse()?,
+            )),
+            ("seed", []) => Ok(Self::Seed),
+            ("newworld", []) => Ok(Self::NewWorld(None)),
+            ("newworld", [seed]) => Ok(Self::NewWorld(Some(parse_arg(seed)?))),
+            ("tp", _) => Err("usage: tp <x> <y> <z>".to_string()),
+            ("setblock", _) => Err("usage: setblock <x> <y> <z> <blockid>".to_string()),
+            ("seed", _) => Err("usage: seed".to_string()),
+            ("newworld", _) => Err("usage: newworld [seed]".to_string()),
+            _ => Err(format!("unknown command '{}'", name)),
+        }
+    }
+}
+
+fn parse_arg<T: FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse()
+        .map_err(|_| format!("invalid argument '{}'", arg))
+}
+
+pub fn console_closed(console: Res<Console>) -> bool {
+    !console.open
+}
+
+pub fn setup_console(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    padding: bevy::ui::UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ConsoleUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+pub fn toggle_console(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    mut ui_query: Query<&mut Visibility, With<ConsoleUi>>,
+) {
+    if !keys.just_pressed(CONSOLE_KEY) {
+        return;
+    }
+
+    console.open = !console.open;
+    console.input.clear();
+    for mut visibility in ui_query.iter_mut() {
+        *visibility = if console.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn console_input(
+    mut commands: Commands,
+    mut console: ResMut<Console>,
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut player_query: Query<(&mut Transform, &mut PlayerMovement), With<Player>>,
+    mut new_world_events: EventWriter<NewWorld>,
+) {
+    if !console.open {
+        chars.clear();
+        return;
+    }
+
+    for ev in chars.read() {
+        console
+            .input
+            .extend(ev.char.chars().filter(|c| !c.is_control() && *c != '`'));
+    }
+
+    if keys.just_pressed(KeyCode::Backspace) {
+        console.input.pop();
+    }
+
+    if !keys.just_pressed(KeyCode::Enter) {
+        return;
+    }
+
+    let line = std::mem::take(&mut console.input);
+    console.log(format!("> {}", line));
+
+    match line.parse::<ConsoleCommand>() {
+        Ok(ConsoleCommand::Teleport(pos)) => {
+            let pos = world.clamp_to_border(pos);
+            // load the ground at the destination now, rather than leave the player hanging
+            // in the air until the loader gets to it
+            for block in [pos, pos - Vec3::Y] {
+                let coord = world.block_to_chunk_coordinate(block.round().as_i64vec3());
+                if let Err(e) =
+                    chunk_loader.force_load_chunk(&mut commands, &mut meshes, &mut world, coord)
+                {
+                    console.log(format!("failed to load chunk {:?}: {}", coord.0, e));
+                }
+            }
+            for (mut transform, mut movement) in player_query.iter_mut() {
+                transform.translation = pos;
+                movement.stop();
+            }
+            console.log(format!("teleported to {} {} {}", pos.x, pos.y, pos.z));
+        }
+        Ok(ConsoleCommand::SetBlock(pos, block)) => match world.get_block(pos) {
+            Some(previous) => {
+                world.set_block(pos, block);
+                chunk_loader.mark_block_dirty(&mut commands, &world, pos);
+                console.log(format!(
+                    "replaced {:?} with {:?} at {} {} {}",
+                    previous, block, pos.x, pos.y, pos.z
+                ));
+            }
+            None => console.log("chunk is not loaded".to_string()),
+        },
+        Ok(ConsoleCommand::Seed) => {
+            let seed = world.seed();
+            console.log(format!("seed is {}", seed));
+        }
+        Ok(ConsoleCommand::NewWorld(seed)) => {
+            new_world_events.send(NewWorld { seed });
+            console.log("creating new world".to_string());
+        }
+        Err(e) => console.log(e),
+    }
+}
+
+pub fn update_console_text(
+    console: Res<Console>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    let mut lines = console.history.clone();
+    lines.push(format!("> {}_", console.input));
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{I64Vec3, Vec3};
+
+    use crate::block::BlockType;
+
+    use super::ConsoleCommand;
+
+    #[test]
+    fn test_parse_teleport() {
+        assert_eq!(
+            Ok(ConsoleCommand::Teleport(Vec3::new(0.0, 100.0, -2.5))),
+            "tp 0 100 -2.5".parse()
+        );
+    }
+
+    #[test]
+    fn test_parse_setblock_by_name_and_id() {
+        assert_eq!(
+            Ok(ConsoleCommand::SetBlock(
+                I64Vec3::new(1, -2, 3),
+                BlockType::Stone
+            )),
+            "setblock 1 -2 3 stone".parse()
+        );
+        assert_eq!(
+            Ok(ConsoleCommand::SetBlock(
+                I64Vec3::new(1, 2, 3),
+                BlockType::Sand
+            )),
+            "setblock 1 2 3 3".parse()
+        );
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(Ok(ConsoleCommand::Seed), "  seed ".parse());
+    }
+
+    #[test]
+    fn test_parse_newworld() {
+        assert_eq!(Ok(ConsoleCommand::NewWorld(None)), "newworld".parse());
+        assert_eq!(
+            Ok(ConsoleCommand::NewWorld(Some(42))),
+            "newworld 42".parse()
+        );
+        assert!("newworld -1".parse::<ConsoleCommand>().is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        assert!("".parse::<ConsoleCommand>().is_err());
+        assert!("tp 1 2".parse::<ConsoleCommand>().is_err());
+        assert!("tp a b c".parse::<ConsoleCommand>().is_err());
+        assert_eq!(
+            Err("coordinates must be finite".to_string()),
+            "tp NaN 0 0".parse::<ConsoleCommand>()
+        );
+        assert!("tp 0 inf 0".parse::<ConsoleCommand>().is_err());
+        assert!("setblock 1 2 3 diamond".parse::<ConsoleCommand>().is_err());
+        assert!("fly".parse::<ConsoleCommand>().is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use bevy::{
+    ecs::{
+        event::EventReader,
+        query::With,
+        system::{Query, Res},
+    },
+    input::{mouse::MouseButton, ButtonInput},
+    window::{CursorGrabMode, PrimaryWindow, Window, WindowFocused},
+};
+
+fn set_cursor_grab(window: &mut Window, grabbed: bool) {
+    if grabbed {
+        window.cursor.grab_mode = CursorGrabMode::Locked;
+        window.cursor.visible = false;
+    } else {
+        window.cursor.grab_mode = CursorGrabMode::None;
+        window.cursor.visible = true;
+    }
+}
+
+pub fn grab_cursor(mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window_query.get_single_mut() {
+        set_cursor_grab(&mut window, true);
+    }
+}
+
+pub fn release_cursor(mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window_query.get_single_mut() {
+        set_cursor_grab(&mut window, false);
+    }
+}
+
+/// Frees the cursor when the window loses focus, e.g. when alt-tabbing away.
+pub fn release_cursor_on_focus_loss(
+    mut focus_events: EventReader<WindowFocused>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    for event in focus_events.read() {
+        if event.focused {
+            continue;
+        }
+        if let Ok(mut window) = window_query.get_mut(event.window) {
+            set_cursor_grab(&mut window, false);
+        }
+    }
+}
+
+/// Captures the cursor again when the window is clicked after it was released.
+pub fn grab_cursor_on_click(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !mouse.get_just_pressed().any(|_| true) {
+        return;
+    }
+    if let Ok(mut window) = window_query.get_single_mut() {
+        if window.cursor.grab_mode == CursorGrabMode::None {
+            set_cursor_grab(&mut window, true);
+        }
+    }
+}
+
+/// Whether the cursor is captured, so mouse movement should turn the camera.
+pub fn cursor_grabbed(window_query: Query<&Window, With<PrimaryWindow>>) -> bool {
+    window_query
+        .get_single()
+        .is_ok_and(|window| window.cursor.grab_mode != CursorGrabMode::None)
+}
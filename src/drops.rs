@@ -0,0 +1,172 @@
+use bevy::{
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{With, Without},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::{Quat, Vec3},
+    pbr::{AlphaMode, PbrBundle, StandardMaterial},
+    render::{mesh::Mesh, texture::Image},
+    time::Time,
+    transform::components::Transform,
+    utils::{default, HashMap},
+};
+
+use crate::{
+    block::BlockType,
+    inventory::Inventory,
+    item::Item,
+    particles::{block_cube_mesh, BlockBroken},
+    physics::move_and_collide,
+    player::Player,
+    settings::Settings,
+    util::frustum::OnScreen,
+    world::World,
+};
+
+/// Width of a dropped item in blocks.
+const DROP_SIZE: f32 = 0.3;
+/// Distance from the player's body within which drops are picked up.
+const PICKUP_RADIUS: f32 = 1.5;
+/// Seconds before a new drop can be picked up, so it is seen to drop first.
+const PICKUP_DELAY: f32 = 0.5;
+/// Seconds before an uncollected drop disappears.
+const DROP_LIFETIME: f32 = 300.0;
+/// Radians per second that drops spin at.
+const SPIN_SPEED: f32 = 1.5;
+
+/// A block dropped into the world, waiting for the player to walk over it.
+#[derive(Component)]
+pub struct ItemDrop {
+    block: BlockType,
+    vertical_velocity: f32,
+    /// Seconds since the drop was spawned.
+    age: f32,
+}
+
+/// The material shared by every drop, and a mesh per block type.
+#[derive(Resource)]
+pub struct DropAssets {
+    material: Handle<StandardMaterial>,
+    meshes: HashMap<BlockType, Handle<Mesh>>,
+}
+
+pub fn setup_drops(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(asset_server.load::<Image>("textures/blocks.png")),
+        alpha_mode: AlphaMode::Mask(0.5),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    commands.insert_resource(DropAssets {
+        material,
+        meshes: HashMap::new(),
+    });
+}
+
+pub fn spawn_block_drops(
+    mut commands: Commands,
+    mut events: EventReader<BlockBroken>,
+    mut drop_assets: ResMut<DropAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for event in events.read() {
+        if event.block_type == BlockType::Air || !event.harvested {
+            continue;
+        }
+
+        let block = event.block_type.drop();
+        let mesh = drop_assets
+            .meshes
+            .entry(block)
+            .or_insert_with(|| meshes.add(block_cube_mesh(block, 1.0)))
+            .clone();
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material: drop_assets.material.clone(),
+                transform: Transform::from_translation(event.block.as_vec3())
+                    .with_scale(Vec3::splat(DROP_SIZE)),
+                ..default()
+            },
+            ItemDrop {
+                block,
+                vertical_velocity: 0.0,
+                age: 0.0,
+            },
+            OnScreen::default(),
+        ));
+    }
+}
+
+/// Drops fall until they land, spinning while on screen, and disappear after a while. Only
+/// runs while playing, so drops neither move nor age while paused.
+pub fn update_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut world: ResMut<World>,
+    mut drops_query: Query<(Entity, &mut ItemDrop, &mut Transform, &OnScreen)>,
+    settings_query: Query<&Settings>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    let delta = time.delta_seconds();
+
+    for (entity, mut drop, mut transform, on_screen) in drops_query.iter_mut() {
+        drop.age += delta;
+        if drop.age > DROP_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if on_screen.0 {
+            transform.rotate(Quat::from_rotation_y(SPIN_SPEED * delta));
+        }
+
+        // physics works with the bottom of the drop, while the mesh is centred on it
+        let half_height = Vec3::Y * DROP_SIZE / 2.0;
+        drop.vertical_velocity = (drop.vertical_velocity + settings.player.gravity * delta)
+            .max(-settings.player.terminal_velocity);
+        let (position, blocked) = move_and_collide(
+            &mut world,
+            transform.translation - half_height,
+            Vec3::splat(DROP_SIZE),
+            Vec3::Y * drop.vertical_velocity * delta,
+        );
+        transform.translation = position + half_height;
+        if blocked.y {
+            drop.vertical_velocity = 0.0;
+        }
+    }
+}
+
+/// Adds drops near the player to the inventory, leaving them if it is full.
+pub fn pick_up_drops(
+    mut commands: Commands,
+    mut inventory: ResMut<Inventory>,
+    drops_query: Query<(Entity, &ItemDrop, &Transform), Without<Player>>,
+    player_query: Query<&Transform, With<Player>>,
+    settings_query: Query<&Settings>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    // the middle of the player's body
+    let body = player.translation + Vec3::Y * settings.player.collision_height / 2.0;
+
+    for (entity, drop, transform) in drops_query.iter() {
+        if drop.age >= PICKUP_DELAY
+            && transform.translation.distance(body) <= PICKUP_RADIUS
+            && inventory.add(Item::Block(drop.block))
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        query::With,
+        system::{Local, Query},
+    },
+    utils::Instant,
+    window::{PrimaryWindow, Window},
+};
+
+use crate::settings::Settings;
+
+/// Applies the vsync setting to the window, which is created before settings are read.
+pub fn configure_present_mode(
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    settings_query: Query<&Settings>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.present_mode = settings.renderer.present_mode();
+    }
+}
+
+/// Sleeps at the end of each frame so frames take at least as long as the frame rate cap
+/// allows, rather than spinning the CPU and GPU when vsync is off.
+pub fn limit_frame_rate(mut frame_start: Local<Option<Instant>>, settings_query: Query<&Settings>) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    if settings.renderer.max_fps > 0 {
+        let frame_time = Duration::from_secs_f64(1.0 / settings.renderer.max_fps as f64);
+        if let Some(elapsed) = frame_start.map(|start| start.elapsed()) {
+            if elapsed < frame_time {
+                std::thread::sleep(frame_time - elapsed);
+            }
+        }
+    }
+    *frame_start = Some(Instant::now());
+}
@@ -0,0 +1,117 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        system::{Commands, Query, Res},
+    },
+    hierarchy::{BuildChildren, Parent},
+    input::{keyboard::KeyCode, mouse::MouseMotion, ButtonInput},
+    math::{Vec2, Vec3},
+    render::camera::Camera,
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    player::{fly_vertical_input, look_angles, movement_input, Player},
+    settings::Settings,
+    world::World,
+};
+
+const FREE_CAMERA_KEY: KeyCode = KeyCode::F4;
+
+/// Marks the camera as detached from the player. Stores the camera's transform relative
+/// to the player so it can be restored when reattached.
+#[derive(Component)]
+pub struct FreeCamera {
+    player_offset: Transform,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_free_camera(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut camera_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &GlobalTransform,
+            Option<&Parent>,
+            Option<&FreeCamera>,
+        ),
+        With<Camera>,
+    >,
+    player_query: Query<Entity, With<Player>>,
+) {
+    if !keys.just_pressed(FREE_CAMERA_KEY) {
+        return;
+    }
+
+    let Ok((camera, mut transform, global_transform, parent, free_camera)) =
+        camera_query.get_single_mut()
+    else {
+        return;
+    };
+
+    match (parent, free_camera) {
+        (Some(_), None) => {
+            let player_offset = *transform;
+            *transform = global_transform.compute_transform();
+            commands
+                .entity(camera)
+                .remove_parent()
+                .insert(FreeCamera { player_offset });
+        }
+        (None, Some(free_camera)) => {
+            let Ok(player) = player_query.get_single() else {
+                return;
+            };
+            *transform = free_camera.player_offset;
+            commands
+                .entity(camera)
+                .remove::<FreeCamera>()
+                .set_parent(player);
+        }
+        _ => (),
+    }
+}
+
+/// Flies the free camera where it faces, at the player's flying speed.
+pub fn free_camera_move(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    world: Res<World>,
+    mut camera_query: Query<&mut Transform, (With<Camera>, With<FreeCamera>)>,
+    settings_query: Query<&Settings>,
+) {
+    let Ok(mut transform) = camera_query.get_single_mut() else {
+        return;
+    };
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    let move_speed = settings.player.fly_speed * settings.player.fly_speed_multiplier;
+
+    let movement = transform.rotation * movement_input(&keys) * move_speed
+        + Vec3::Y * fly_vertical_input(&keys) * move_speed;
+    transform.translation =
+        world.clamp_to_border(transform.translation + movement * time.delta_seconds());
+}
+
+pub fn free_camera_look(
+    time: Res<Time>,
+    mut camera_query: Query<&mut Transform, (With<Camera>, With<FreeCamera>)>,
+    mut motion_evr: EventReader<MouseMotion>,
+    settings_query: Query<&Settings>,
+) {
+    let Ok(mut transform) = camera_query.get_single_mut() else {
+        motion_evr.clear();
+        return;
+    };
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+
+    let delta: Vec2 = motion_evr.read().map(|ev| ev.delta).sum();
+    let (yaw, pitch) = look_angles(delta, &settings.player, time.delta_seconds());
+    transform.rotate_axis(Vec3::new(0.0, 1.0, 0.0), yaw);
+    transform.rotate_local_x(pitch);
+}
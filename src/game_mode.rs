@@ -0,0 +1,124 @@
+use bevy::{
+    ecs::system::{Res, ResMut, Resource},
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+};
+use serde::Deserialize;
+
+use crate::{block::BlockType, item::ToolTier};
+
+const GAME_MODE_KEY: KeyCode = KeyCode::F1;
+const CREATIVE_BLOCK_KEY: KeyCode = KeyCode::KeyB;
+
+/// The rules the player plays by, switched between with F1.
+#[derive(Resource, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    /// Blocks take time to break and placing them uses them up. No flying.
+    #[default]
+    Survival,
+    /// For building: flying, blocks break instantly and any block can be placed, picked
+    /// with B rather than taken from the inventory. Broken blocks don't drop, as there's no
+    /// need to collect them.
+    Creative,
+}
+
+impl GameMode {
+    pub fn toggled(&self) -> Self {
+        match self {
+            Self::Survival => Self::Creative,
+            Self::Creative => Self::Survival,
+        }
+    }
+
+    pub fn can_fly(&self) -> bool {
+        *self == Self::Creative
+    }
+
+    /// Whether placing a block takes it from the inventory and breaking one drops it.
+    pub fn uses_items(&self) -> bool {
+        *self == Self::Survival
+    }
+
+    /// Seconds `block` takes to break with `tool`.
+    pub fn break_time(&self, block: BlockType, tool: ToolTier) -> f32 {
+        match self {
+            Self::Survival => block.break_time(tool),
+            Self::Creative => 0.0,
+        }
+    }
+}
+
+/// The block placed in creative mode.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreativeBlock(pub BlockType);
+
+impl Default for CreativeBlock {
+    fn default() -> Self {
+        Self(BlockType::Stone)
+    }
+}
+
+impl CreativeBlock {
+    /// The next block type by id, skipping air.
+    fn next(self) -> Self {
+        let next = BlockType::from_id(self.0.id() + 1)
+            .or_else(|| BlockType::from_id(BlockType::Air.id() + 1))
+            .unwrap_or_default();
+        Self(next)
+    }
+}
+
+pub fn cycle_creative_block(
+    keys: Res<ButtonInput<KeyCode>>,
+    game_mode: Res<GameMode>,
+    mut block: ResMut<CreativeBlock>,
+) {
+    if *game_mode == GameMode::Creative && keys.just_pressed(CREATIVE_BLOCK_KEY) {
+        *block = block.next();
+        info!("placing {:?}", block.0);
+    }
+}
+
+pub fn toggle_game_mode(keys: Res<ButtonInput<KeyCode>>, mut game_mode: ResMut<GameMode>) {
+    if keys.just_pressed(GAME_MODE_KEY) {
+        *game_mode = game_mode.toggled();
+        info!("switched to {:?} mode", *game_mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{block::BlockType, item::ToolTier};
+
+    use super::{CreativeBlock, GameMode};
+
+    #[test]
+    fn test_creative_breaks_instantly_without_using_items() {
+        let creative = GameMode::Survival.toggled();
+        assert_eq!(GameMode::Creative, creative);
+        assert!(creative.can_fly() && !creative.uses_items());
+        assert_eq!(0.0, creative.break_time(BlockType::Stone, ToolTier::Hand));
+
+        let survival = GameMode::Survival;
+        assert!(!survival.can_fly() && survival.uses_items());
+        assert!(survival.break_time(BlockType::Stone, ToolTier::Hand) > 0.0);
+    }
+
+    #[test]
+    fn test_creative_block_cycles_every_block_but_air() {
+        let mut block = CreativeBlock::default();
+        let mut seen = Vec::new();
+        for _ in 0..64 {
+            block = block.next();
+            assert_ne!(BlockType::Air, block.0);
+            if !seen.contains(&block.0) {
+                seen.push(block.0);
+            }
+        }
+        let block_count = (0..=u8::MAX)
+            .take_while(|id| BlockType::from_id(*id).is_some())
+            .count();
+        assert_eq!(block_count - 1, seen.len());
+    }
+}
@@ -0,0 +1,246 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::BuildChildren,
+    input::{mouse::MouseButton, ButtonInput},
+    math::{primitives::Cuboid, Quat, Vec3},
+    pbr::{AlphaMode, PbrBundle, StandardMaterial},
+    render::{
+        camera::Camera,
+        color::Color,
+        mesh::{Mesh, Meshable},
+        texture::Image,
+        view::Visibility,
+    },
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+    utils::{default, HashMap},
+};
+
+use crate::{
+    block::BlockType, interaction::TargetBlock, inventory::Inventory, item::Item,
+    particles::block_cube_mesh, player::Player,
+};
+
+/// Where the held item rests relative to the camera, down and to the right of centre.
+const REST_POSITION: Vec3 = Vec3::new(0.35, -0.3, -0.6);
+/// Width of a held block.
+const HELD_BLOCK_SIZE: f32 = 0.2;
+/// Size of a held tool's handle.
+const HELD_TOOL_SIZE: Vec3 = Vec3::new(0.04, 0.3, 0.04);
+const TOOL_COLOR: Color = Color::rgb(0.45, 0.3, 0.15);
+/// Horizontal speed in blocks per second at which bobbing is strongest.
+const BOB_FULL_SPEED: f32 = 5.0;
+/// Distance in blocks the held item bobs down by at full speed.
+const BOB_HEIGHT: f32 = 0.03;
+/// Bob cycles per block walked.
+const BOB_FREQUENCY: f32 = 0.3;
+/// How quickly bobbing eases in and out as the player starts and stops moving.
+const BOB_EASING: f32 = 8.0;
+/// Swings per second while mining.
+const SWING_RATE: f32 = 3.0;
+/// Radians the held item tips forward by at the bottom of a swing.
+const SWING_ANGLE: f32 = 0.9;
+
+/// The item in the player's selected hotbar slot, drawn in front of the camera.
+#[derive(Component, Default)]
+pub struct HeldItem {
+    item: Option<Item>,
+    /// Progress through the walking bob, in radians.
+    bob_phase: f32,
+    /// How strongly the item bobs, from 0 standing still to 1 at full speed.
+    bob_amount: f32,
+    /// Progress through the current swing, in radians, where 0 is at rest.
+    swing_phase: f32,
+    last_position: Option<Vec3>,
+}
+
+/// The materials shared by held items, and a mesh per block type.
+#[derive(Resource)]
+pub struct HeldItemAssets {
+    block_material: Handle<StandardMaterial>,
+    tool_material: Handle<StandardMaterial>,
+    tool_mesh: Handle<Mesh>,
+    block_meshes: HashMap<BlockType, Handle<Mesh>>,
+}
+
+pub fn setup_held_item(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    camera_query: Query<Entity, With<Camera>>,
+) {
+    let block_material = materials.add(StandardMaterial {
+        base_color_texture: Some(asset_server.load::<Image>("textures/blocks.png")),
+        alpha_mode: AlphaMode::Mask(0.5),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    let tool_material = materials.add(StandardMaterial {
+        base_color: TOOL_COLOR,
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    commands.insert_resource(HeldItemAssets {
+        block_material,
+        tool_material,
+        tool_mesh: meshes.add(Cuboid::from_size(HELD_TOOL_SIZE).mesh()),
+        block_meshes: HashMap::new(),
+    });
+
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let held_item = commands
+        .spawn((
+            PbrBundle {
+                transform: Transform::from_translation(REST_POSITION),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HeldItem::default(),
+        ))
+        .id();
+    commands.entity(camera).add_child(held_item);
+}
+
+/// Swaps the held item's model whenever the selected item changes.
+pub fn update_held_item(
+    inventory: Res<Inventory>,
+    mut assets: ResMut<HeldItemAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut held_query: Query<(
+        &mut HeldItem,
+        &mut Handle<Mesh>,
+        &mut Handle<StandardMaterial>,
+        &mut Visibility,
+    )>,
+) {
+    let selected = inventory.selected_item();
+    for (mut held, mut mesh, mut material, mut visibility) in held_query.iter_mut() {
+        if held.item == selected {
+            continue;
+        }
+        held.item = selected;
+
+        match selected {
+            Some(Item::Block(block)) => {
+                *mesh = assets
+                    .block_meshes
+                    .entry(block)
+                    .or_insert_with(|| meshes.add(block_cube_mesh(block, 1.0)))
+                    .clone();
+                *material = assets.block_material.clone();
+                *visibility = Visibility::Inherited;
+            }
+            Some(Item::Tool(_)) => {
+                *mesh = assets.tool_mesh.clone();
+                *material = assets.tool_material.clone();
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// Bobs the held item as the player walks and swings it while they mine.
+pub fn animate_held_item(
+    time: Res<Time>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    target: Res<TargetBlock>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut held_query: Query<(&mut HeldItem, &mut Transform), Without<Player>>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    let delta = time.delta_seconds();
+    let position = player.translation();
+    let mining = mouse.pressed(MouseButton::Left) && target.0.is_some();
+
+    for (mut held, mut transform) in held_query.iter_mut() {
+        let moved = held
+            .last_position
+            .map_or(Vec3::ZERO, |last| position - last);
+        held.last_position = Some(position);
+
+        let distance = Vec3::new(moved.x, 0.0, moved.z).length();
+        let speed = if delta > 0.0 { distance / delta } else { 0.0 };
+        let target_amount = (speed / BOB_FULL_SPEED).min(1.0);
+        held.bob_amount += (target_amount - held.bob_amount) * (BOB_EASING * delta).min(1.0);
+        held.bob_phase = (held.bob_phase + distance * BOB_FREQUENCY * TAU) % TAU;
+
+        // a swing always finishes, so letting go never snaps the item back to rest
+        if mining || held.swing_phase > 0.0 {
+            held.swing_phase += SWING_RATE * TAU * delta;
+            if held.swing_phase >= TAU {
+                held.swing_phase = if mining { held.swing_phase % TAU } else { 0.0 };
+            }
+        }
+
+        *transform =
+            held_item_transform(held.item, held.bob_phase, held.bob_amount, held.swing_phase);
+    }
+}
+
+/// Local transform of the held item at a point in its bob and swing.
+fn held_item_transform(
+    item: Option<Item>,
+    bob_phase: f32,
+    bob_amount: f32,
+    swing_phase: f32,
+) -> Transform {
+    let bob = Vec3::new(
+        bob_phase.sin() * BOB_HEIGHT * 0.5,
+        -bob_phase.cos().abs() * BOB_HEIGHT,
+        0.0,
+    ) * bob_amount;
+    // dips forward and back once per swing
+    let swing = (1.0 - swing_phase.cos()) * 0.5;
+    let rotation = Quat::from_rotation_x(-swing * SWING_ANGLE) * Quat::from_rotation_y(0.4);
+    let scale = match item {
+        Some(Item::Tool(_)) => Vec3::ONE,
+        _ => Vec3::splat(HELD_BLOCK_SIZE),
+    };
+
+    Transform::from_translation(REST_POSITION + bob + Vec3::new(0.0, -0.1, -0.1) * swing)
+        .with_rotation(rotation)
+        .with_scale(scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use bevy::{math::Vec3, transform::components::Transform};
+
+    use crate::{block::BlockType, item::Item};
+
+    use super::{held_item_transform, REST_POSITION};
+
+    #[test]
+    fn test_held_item_rests_without_movement() {
+        let item = Some(Item::Block(BlockType::Stone));
+        let rest = held_item_transform(item, 1.0, 0.0, 0.0);
+        assert_eq!(REST_POSITION, rest.translation);
+    }
+
+    #[test]
+    fn test_swing_tips_item_forward() {
+        let item = Some(Item::Block(BlockType::Stone));
+        let rest = held_item_transform(item, 0.0, 0.0, 0.0);
+        let swung = held_item_transform(item, 0.0, 0.0, PI);
+
+        let forward = |transform: Transform| transform.rotation * Vec3::Y;
+        assert!(forward(swung).z < forward(rest).z);
+        assert!(swung.translation.y < rest.translation.y);
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::{
+    ecs::{
+        event::EventWriter,
+        query::With,
+        system::{Commands, Local, Query, Res, ResMut, Resource},
+    },
+    gizmos::gizmos::Gizmos,
+    input::{mouse::MouseButton, ButtonInput},
+    math::{I64Vec3, Vec3},
+    render::{camera::Camera, color::Color},
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    block::BlockType,
+    chunks::chunk_loader::ChunkLoader,
+    game_mode::{CreativeBlock, GameMode},
+    inventory::Inventory,
+    particles::BlockBroken,
+    settings::Settings,
+    world::{RaycastHit, World},
+};
+
+/// The block the camera is currently looking at within reach. Breaking, placing and the
+/// outline all read this so they always agree on which block is targeted.
+#[derive(Resource, Default)]
+pub struct TargetBlock(pub Option<RaycastHit>);
+
+pub fn update_target_block(
+    mut target: ResMut<TargetBlock>,
+    mut world: ResMut<World>,
+    camera_query: Query<&GlobalTransform, With<Camera>>,
+    settings_query: Query<&Settings>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        target.0 = None;
+        return;
+    };
+
+    let reach_distance = settings_query
+        .get_single()
+        .map(|settings| settings.player.reach_distance)
+        .unwrap_or_default();
+
+    target.0 = world.raycast(camera.translation(), camera.forward(), reach_distance);
+}
+
+/// Whether a held mouse button should interact this frame. A fresh press always does,
+/// while holding the button repeats once every `cooldown` seconds.
+fn interaction_ready(
+    mouse: &ButtonInput<MouseButton>,
+    button: MouseButton,
+    now: f32,
+    last_interaction: &mut Option<f32>,
+    cooldown: f32,
+) -> bool {
+    let ready = mouse.just_pressed(button)
+        || (mouse.pressed(button) && last_interaction.is_none_or(|last| now - last >= cooldown));
+    if ready {
+        *last_interaction = Some(now);
+    }
+    ready
+}
+
+/// The block being mined and how long it has been mined for.
+#[derive(Default)]
+pub struct Mining {
+    block: Option<I64Vec3>,
+    seconds: f32,
+}
+
+/// Breaks the target block once it has been mined for long enough with the selected tool.
+/// Blocks broken with too weak a tool take longer and drop nothing.
+#[allow(clippy::too_many_arguments)]
+pub fn break_block(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    target: Res<TargetBlock>,
+    game_mode: Res<GameMode>,
+    inventory: Res<Inventory>,
+    mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    mut broken_events: EventWriter<BlockBroken>,
+    settings_query: Query<&Settings>,
+    mut mining: Local<Mining>,
+    mut last_break: Local<Option<f32>>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    let Some(hit) = target.0.filter(|_| mouse.pressed(MouseButton::Left)) else {
+        *mining = Mining::default();
+        return;
+    };
+
+    // looking at another block starts mining over
+    if mining.block != Some(hit.block) {
+        *mining = Mining {
+            block: Some(hit.block),
+            seconds: 0.0,
+        };
+    }
+    mining.seconds += time.delta_seconds();
+
+    let tool = inventory.selected_tool();
+    if mining.seconds < game_mode.break_time(hit.block_type, tool) {
+        return;
+    }
+
+    if !interaction_ready(
+        &mouse,
+        MouseButton::Left,
+        time.elapsed_seconds(),
+        &mut last_break,
+        settings.player.interaction_cooldown,
+    ) {
+        return;
+    }
+
+    if world.set_block(hit.block, BlockType::Air) {
+        chunk_loader.mark_block_dirty(&mut commands, &world, hit.block);
+        *mining = Mining::default();
+        // the block drops as an item for the player to pick up, if the tool was good enough
+        broken_events.send(BlockBroken {
+            block: hit.block,
+            block_type: hit.block_type,
+            harvested: game_mode.uses_items() && hit.block_type.harvestable_with(tool),
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_block(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    target: Res<TargetBlock>,
+    game_mode: Res<GameMode>,
+    mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    mut inventory: ResMut<Inventory>,
+    creative_block: Res<CreativeBlock>,
+    settings_query: Query<&Settings>,
+    mut last_place: Local<Option<f32>>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    if !interaction_ready(
+        &mouse,
+        MouseButton::Right,
+        time.elapsed_seconds(),
+        &mut last_place,
+        settings.player.interaction_cooldown,
+    ) {
+        return;
+    }
+
+    // a zero normal means the camera is inside the target block
+    let Some(hit) = target.0.filter(|hit| hit.normal != I64Vec3::ZERO) else {
+        return;
+    };
+
+    // survival places the selected block from the inventory, while creative places
+    // whichever block was picked, whatever the inventory holds
+    let block = if game_mode.uses_items() {
+        inventory.selected_block()
+    } else {
+        Some(creative_block.0)
+    };
+    let Some(block) = block else {
+        return;
+    };
+
+    let position = hit.block + hit.normal;
+    if world.set_block(position, block) {
+        chunk_loader.mark_block_dirty(&mut commands, &world, position);
+        if game_mode.uses_items() {
+            inventory.take_selected();
+        }
+    }
+}
+
+pub fn draw_block_outline(target: Res<TargetBlock>, mut gizmos: Gizmos) {
+    let Some(hit) = target.0 else {
+        return;
+    };
+
+    let centre = Vec3::new(hit.block.x as f32, hit.block.y as f32, hit.block.z as f32);
+    gizmos.cuboid(
+        Transform::from_translation(centre).with_scale(Vec3::splat(1.01)),
+        Color::BLACK,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{mouse::MouseButton, ButtonInput};
+
+    use super::interaction_ready;
+
+    #[test]
+    fn test_held_button_repeats_after_cooldown() {
+        let mut mouse = ButtonInput::<MouseButton>::default();
+        let mut last = None;
+        mouse.press(MouseButton::Left);
+        assert!(interaction_ready(
+            &mouse,
+            MouseButton::Left,
+            0.0,
+            &mut last,
+            0.25
+        ));
+
+        mouse.clear();
+        assert!(!interaction_ready(
+            &mouse,
+            MouseButton::Left,
+            0.1,
+            &mut last,
+            0.25
+        ));
+        assert!(interaction_ready(
+            &mouse,
+            MouseButton::Left,
+            0.3,
+            &mut last,
+            0.25
+        ));
+        assert!(!interaction_ready(
+            &mouse,
+            MouseButton::Left,
+            0.4,
+            &mut last,
+            0.25
+        ));
+    }
+
+    #[test]
+    fn test_released_button_does_not_interact() {
+        let mouse = ButtonInput::<MouseButton>::default();
+        let mut last = None;
+        assert!(!interaction_ready(
+            &mouse,
+            MouseButton::Left,
+            1.0,
+            &mut last,
+            0.25
+        ));
+        assert_eq!(None, last);
+    }
+}
@@ -0,0 +1,311 @@
+use bevy::{
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::EventReader,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::BuildChildren,
+    input::{keyboard::KeyCode, mouse::MouseWheel, ButtonInput},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, BackgroundColor, BorderColor, JustifyContent, PositionType, Style, UiRect, Val,
+    },
+    utils::default,
+};
+
+use crate::{
+    block::BlockType,
+    item::{Item, ToolTier},
+    player::PlayerMovement,
+};
+
+pub const HOTBAR_SLOTS: usize = 9;
+pub const STACK_LIMIT: u32 = 64;
+
+const SLOT_KEYS: [KeyCode; HOTBAR_SLOTS] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+}
+
+/// Items held by the player. The first `HOTBAR_SLOTS` slots make up the hotbar, and the
+/// selected slot is the one placed or mined with.
+#[derive(Resource)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    selected: usize,
+    stack_limit: u32,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(HOTBAR_SLOTS, STACK_LIMIT)
+    }
+}
+
+impl Inventory {
+    pub fn new(capacity: usize, stack_limit: u32) -> Self {
+        Self {
+            slots: vec![None; capacity],
+            selected: 0,
+            stack_limit,
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, slot: usize) {
+        if slot < self.slots.len() {
+            self.selected = slot;
+        }
+    }
+
+    /// Adds one item, topping up an existing stack before starting a new one.
+    /// Returns `false` if there is no room for it.
+    pub fn add(&mut self, item: Item) -> bool {
+        let stack_limit = item.stack_limit(self.stack_limit);
+        if let Some(stack) = self
+            .slots
+            .iter_mut()
+            .flatten()
+            .find(|stack| stack.item == item && stack.count < stack_limit)
+        {
+            stack.count += 1;
+            return true;
+        }
+
+        if let Some(slot) = self.slots.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(ItemStack { item, count: 1 });
+            return true;
+        }
+
+        false
+    }
+
+    pub fn selected_item(&self) -> Option<Item> {
+        self.slots[self.selected].map(|stack| stack.item)
+    }
+
+    /// The block in the selected slot, or `None` if it is empty or holds a tool.
+    pub fn selected_block(&self) -> Option<BlockType> {
+        match self.selected_item() {
+            Some(Item::Block(block)) => Some(block),
+            _ => None,
+        }
+    }
+
+    /// The tool blocks are mined with, which is bare hands unless a tool is selected.
+    pub fn selected_tool(&self) -> ToolTier {
+        self.selected_item()
+            .map(|item| item.tool())
+            .unwrap_or_default()
+    }
+
+    /// Removes one item from the selected slot, returning `None` if the slot is empty.
+    pub fn take_selected(&mut self) -> Option<Item> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        let item = stack.item;
+
+        stack.count -= 1;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(item)
+    }
+}
+
+#[derive(Component)]
+pub struct HotbarSlot(usize);
+
+#[derive(Component)]
+pub struct HotbarText(usize);
+
+pub fn setup_hotbar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                bottom: Val::Px(8.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for i in 0..HOTBAR_SLOTS {
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Px(56.0),
+                                height: Val::Px(56.0),
+                                border: UiRect::all(Val::Px(2.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+                            border_color: BorderColor(Color::GRAY),
+                            ..default()
+                        },
+                        HotbarSlot(i),
+                    ))
+                    .with_children(|slot| {
+                        slot.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 14.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ),
+                            HotbarText(i),
+                        ));
+                    });
+            }
+        });
+}
+
+pub fn select_hotbar_slot(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel_evr: EventReader<MouseWheel>,
+    mut inventory: ResMut<Inventory>,
+    player_query: Query<&PlayerMovement>,
+) {
+    if let Some(slot) = SLOT_KEYS.iter().position(|key| keys.just_pressed(*key)) {
+        inventory.select(slot);
+    }
+
+    // the scroll wheel sets flying speed instead while flying
+    let flying = player_query.iter().any(|movement| movement.is_flying());
+    let scroll: f32 = wheel_evr.read().map(|ev| ev.y).sum();
+    if scroll != 0.0 && !flying {
+        let offset = if scroll > 0.0 { HOTBAR_SLOTS - 1 } else { 1 };
+        let slot = (inventory.selected() + offset) % HOTBAR_SLOTS;
+        inventory.select(slot);
+    }
+}
+
+pub fn update_hotbar(
+    inventory: Res<Inventory>,
+    mut slot_query: Query<(&HotbarSlot, &mut BorderColor)>,
+    mut text_query: Query<(&HotbarText, &mut Text)>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+
+    for (slot, mut border) in slot_query.iter_mut() {
+        border.0 = if slot.0 == inventory.selected() {
+            Color::WHITE
+        } else {
+            Color::GRAY
+        };
+    }
+
+    for (slot, mut text) in text_query.iter_mut() {
+        text.sections[0].value = match inventory.slots().get(slot.0).copied().flatten() {
+            Some(stack) => format!("{}\n{}", stack.item, stack.count),
+            None => String::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block::BlockType,
+        item::{Item, ToolTier},
+    };
+
+    use super::{Inventory, ItemStack};
+
+    #[test]
+    fn test_add_fills_stack_then_next_slot() {
+        let mut inventory = Inventory::new(3, 64);
+        for _ in 0..65 {
+            assert!(inventory.add(Item::Block(BlockType::Stone)));
+        }
+
+        assert_eq!(
+            Some(ItemStack {
+                item: Item::Block(BlockType::Stone),
+                count: 64
+            }),
+            inventory.slots()[0]
+        );
+        assert_eq!(
+            Some(ItemStack {
+                item: Item::Block(BlockType::Stone),
+                count: 1
+            }),
+            inventory.slots()[1]
+        );
+    }
+
+    #[test]
+    fn test_add_fails_when_full() {
+        let mut inventory = Inventory::new(1, 2);
+        assert!(inventory.add(Item::Block(BlockType::Sand)));
+        assert!(inventory.add(Item::Block(BlockType::Sand)));
+        assert!(!inventory.add(Item::Block(BlockType::Sand)));
+        assert!(!inventory.add(Item::Block(BlockType::Grass)));
+    }
+
+    #[test]
+    fn test_take_selected_consumes_stack() {
+        let mut inventory = Inventory::new(2, 64);
+        inventory.add(Item::Block(BlockType::Grass));
+        inventory.add(Item::Block(BlockType::Snow));
+
+        assert_eq!(
+            Some(Item::Block(BlockType::Grass)),
+            inventory.take_selected()
+        );
+        assert_eq!(None, inventory.slots()[0]);
+        assert_eq!(None, inventory.take_selected());
+
+        inventory.select(1);
+        assert_eq!(
+            Some(Item::Block(BlockType::Snow)),
+            inventory.take_selected()
+        );
+    }
+
+    #[test]
+    fn test_tools_do_not_stack() {
+        let mut inventory = Inventory::new(2, 64);
+        assert!(inventory.add(Item::Tool(ToolTier::Pickaxe)));
+        assert!(inventory.add(Item::Tool(ToolTier::Pickaxe)));
+        assert!(!inventory.add(Item::Tool(ToolTier::Pickaxe)));
+
+        assert_eq!(ToolTier::Pickaxe, inventory.selected_tool());
+        assert_eq!(None, inventory.selected_block());
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use crate::block::BlockType;
+
+/// Tools in order of strength. A tool can harvest every block a weaker tool can.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum ToolTier {
+    /// No tool, mining with bare hands.
+    #[default]
+    Hand,
+    Pickaxe,
+}
+
+/// Something that can be held in an inventory slot.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Item {
+    Block(BlockType),
+    Tool(ToolTier),
+}
+
+impl Item {
+    /// Most of the item that fits in a single slot, given the inventory's stack limit.
+    pub fn stack_limit(&self, limit: u32) -> u32 {
+        match self {
+            Self::Block(_) => limit,
+            Self::Tool(_) => 1,
+        }
+    }
+
+    /// The tool the item mines with, which is bare hands for anything but a tool.
+    pub fn tool(&self) -> ToolTier {
+        match self {
+            Self::Block(_) => ToolTier::Hand,
+            Self::Tool(tier) => *tier,
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Block(block) => write!(f, "{:?}", block),
+            Self::Tool(tier) => write!(f, "{:?}", tier),
+        }
+    }
+}
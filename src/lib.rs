@@ -0,0 +1,28 @@
+pub mod block;
+pub mod chunks;
+pub mod compass;
+pub mod console;
+pub mod cursor;
+pub mod drops;
+pub mod frame_rate;
+pub mod free_camera;
+pub mod game_mode;
+pub mod held_item;
+pub mod interaction;
+pub mod inventory;
+pub mod item;
+pub mod loading;
+pub mod new_world;
+pub mod particles;
+pub mod pause;
+pub mod physics;
+pub mod player;
+pub mod save;
+pub mod screenshot;
+pub mod settings;
+pub mod sky;
+pub mod state;
+pub mod ui_scale;
+pub mod underwater;
+pub mod util;
+pub mod world;
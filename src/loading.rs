@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With},
+        schedule::NextState,
+        system::{Commands, Query, Res, ResMut},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    log::info,
+    math::{I64Vec3, Vec3},
+    render::{camera::Camera, color::Color},
+    text::{Text, TextStyle},
+    transform::components::GlobalTransform,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, BackgroundColor, JustifyContent, Style, Val,
+    },
+    utils::default,
+};
+
+use crate::{
+    chunks::{
+        chunk::ChunkCoordinate,
+        chunk_loader::{Chunk, ChunkLoader, DirtyChunk, GenerateChunkData, GenerateChunkMesh},
+    },
+    settings::Settings,
+    state::AppState,
+    world::World,
+};
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+pub fn setup_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgb(0.1, 0.1, 0.1)),
+                z_index: bevy::ui::ZIndex::Global(10),
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Loading world... 0%",
+                    TextStyle {
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                LoadingText,
+            ));
+        });
+}
+
+/// Tracks how many chunks around spawn have finished loading and starts play once they
+/// all have, so the player never gains control before the ground beneath them exists.
+#[allow(clippy::type_complexity)]
+pub fn update_loading(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut world: ResMut<World>,
+    chunk_loader: Res<ChunkLoader>,
+    camera_query: Query<&GlobalTransform, With<Camera>>,
+    chunks_query: Query<(
+        &Chunk,
+        Has<GenerateChunkData>,
+        Has<DirtyChunk>,
+        Has<GenerateChunkMesh>,
+    )>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+    settings_query: Query<&Settings>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+
+    let camera_pos = camera.translation();
+    let spawn_chunk = world.block_to_chunk_coordinate(block_position(camera_pos));
+
+    let pending: HashSet<ChunkCoordinate> = chunks_query
+        .iter()
+        .filter(|(chunk, generating, dirty, meshing)| {
+            *generating || *meshing || (*dirty && chunk_loader.is_visible(chunk.coord()))
+        })
+        .map(|(chunk, ..)| chunk.coord())
+        .collect();
+
+    let radius = settings_query
+        .get_single()
+        .copied()
+        .unwrap_or_default()
+        .world
+        .spawn_radius as i64;
+    let mut total = 0;
+    let mut loaded = 0;
+    for x in -radius..=radius {
+        for y in -radius..=radius {
+            for z in -radius..=radius {
+                let coord = ChunkCoordinate(spawn_chunk.0 + I64Vec3::new(x, y, z));
+                if !chunk_loader.in_view(coord, &world) {
+                    continue;
+                }
+
+                total += 1;
+                if world.is_chunk_generated(coord) && !pending.contains(&coord) {
+                    loaded += 1;
+                }
+            }
+        }
+    }
+
+    let percent = loaded * 100 / total.max(1);
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Loading world... {}%", percent);
+    }
+
+    if loaded == total {
+        info!("loaded {} chunks around spawn", total);
+        next_state.set(AppState::Playing);
+    }
+}
+
+pub fn despawn_loading_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn block_position(pos: Vec3) -> I64Vec3 {
+    (pos + Vec3::splat(0.5)).floor().as_i64vec3()
+}
@@ -1,38 +1,174 @@
-use std::error::Error;
+use std::{error::Error, sync::Arc};
 
-use settings::Settings;
-
-mod block;
-mod chunks;
-mod player;
-mod settings;
-mod util;
-mod world;
-
-use bevy::prelude::*;
-use chunks::{
-    chunk_loader::{
-        gather_chunks, generate_chunks, load_chunks, mark_chunks, unload_chunks, ChunkLoader,
+use bevy::{
+    prelude::*,
+    render::{
+        renderer::RenderDevice,
+        settings::{WgpuFeatures, WgpuSettings, WgpuSettingsPriority},
+        RenderPlugin,
     },
-    material::ChunkMaterial,
 };
-use player::{player_look, player_move, PlayerBundle};
+use rustcraft::{
+    block::ATLAS_TILES,
+    chunks::{
+        chunk_loader::{
+            fade_chunks, gather_chunks, generate_chunks, load_chunks, mark_chunks, reload_chunks,
+            remesh_chunks, save_chunks_on_exit, sort_translucent_chunks, unload_chunks,
+            update_chunk_visibility, ChunkLoader, MeshingTaskPool,
+        },
+        debug::{
+            draw_chunk_gizmos, draw_chunk_origin_gizmos, toggle_chunk_gizmos, ChunkGizmos,
+            ChunkOriginGizmos,
+        },
+        material::{
+            check_block_atlas, configure_block_atlas, cycle_cull_mode, cycle_render_style,
+            ChunkMaterial, CullMode, PlantMaterial, RenderStyle, SwayExtension,
+        },
+    },
+    compass::{setup_compass, toggle_compass, update_compass},
+    console::{
+        console_closed, console_input, setup_console, toggle_console, update_console_text, Console,
+    },
+    cursor::{
+        cursor_grabbed, grab_cursor, grab_cursor_on_click, release_cursor,
+        release_cursor_on_focus_loss,
+    },
+    drops::{pick_up_drops, setup_drops, spawn_block_drops, update_drops},
+    frame_rate::{configure_present_mode, limit_frame_rate},
+    free_camera::{free_camera_look, free_camera_move, toggle_free_camera},
+    game_mode::{cycle_creative_block, toggle_game_mode, CreativeBlock},
+    held_item::{animate_held_item, setup_held_item, update_held_item},
+    interaction::{break_block, draw_block_outline, place_block, update_target_block, TargetBlock},
+    inventory::{select_hotbar_slot, setup_hotbar, update_hotbar, Inventory},
+    item::{Item, ToolTier},
+    loading::{despawn_loading_screen, setup_loading_screen, update_loading},
+    new_world::{new_world, request_new_world, NewWorld},
+    particles::{setup_particles, spawn_break_particles, update_particles, BlockBroken},
+    pause::{chunk_loading_active, despawn_pause_menu, setup_pause_menu, toggle_pause},
+    player::{player_look, player_move, respawn_from_void, PlayerBundle},
+    save::{WorldSave, SAVE_DIR},
+    screenshot::{take_screenshot, TakingScreenshot},
+    settings::{overlay_settings, Settings},
+    sky::{follow_camera_sky, setup_sky},
+    state::AppState,
+    ui_scale::scale_ui_to_window,
+    underwater::{setup_underwater_overlay, update_underwater},
+    util::frustum::update_on_screen,
+};
+
+const SETTINGS_FILE: &str = "assets/settings.toml";
+/// Environment variable naming the settings profile to use, like the `--profile` argument.
+const PROFILE_ENV: &str = "RUSTCRAFT_PROFILE";
+/// Distance in blocks the tops of plants sway by.
+const PLANT_SWAY: f32 = 0.08;
 
-fn read_settings(file: &str) -> Result<Settings, Box<dyn Error>> {
+fn read_settings_table(file: &str) -> Result<toml::Table, Box<dyn Error>> {
     let settings_str = std::fs::read_to_string(file)?;
-    let settings = toml::from_str(&settings_str)?;
+    Ok(toml::from_str(&settings_str)?)
+}
+
+fn parse_settings(table: toml::Table) -> Result<Settings, Box<dyn Error>> {
+    let settings: Settings = toml::Value::Table(table).try_into()?;
+    settings.validate()?;
     Ok(settings)
 }
 
+/// Name of the settings profile chosen with `--profile <name>` or the `RUSTCRAFT_PROFILE`
+/// environment variable, with the argument taking precedence.
+fn settings_profile() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next();
+        }
+        if let Some(profile) = arg.strip_prefix("--profile=") {
+            return Some(profile.to_string());
+        }
+    }
+    std::env::var(PROFILE_ENV).ok()
+}
+
+/// Reads `assets/settings.toml`, with the keys set in the chosen profile's
+/// `assets/settings.<profile>.toml` on top. The profile is ignored if it can't be read.
+fn load_settings() -> Settings {
+    let base = read_settings_table(SETTINGS_FILE).expect("Failed to read settings.toml");
+    if let Some(profile) = settings_profile() {
+        let file = format!("assets/settings.{}.toml", profile);
+        let mut table = base.clone();
+        match read_settings_table(&file).and_then(|profile_table| {
+            overlay_settings(&mut table, profile_table);
+            parse_settings(table)
+        }) {
+            Ok(settings) => {
+                info!("using settings profile '{}'", profile);
+                return settings;
+            }
+            Err(e) => warn!("failed to read settings profile '{}': {}", file, e),
+        }
+    }
+    parse_settings(base).expect("Failed to read settings.toml")
+}
+
+/// Opens the world save, reusing its seed if it has one. The world is still playable,
+/// just without saving, if the save can't be opened.
+fn open_world() -> rustcraft::world::World {
+    let save = match WorldSave::open(SAVE_DIR) {
+        Ok(save) => save,
+        Err(e) => {
+            warn!("failed to open world save, edits will not be saved: {}", e);
+            return rustcraft::world::World::new();
+        }
+    };
+
+    let mut world = match save.load_seed() {
+        Ok(Some(seed)) => rustcraft::world::World::with_seed(seed),
+        Ok(None) => rustcraft::world::World::new(),
+        Err(e) => {
+            warn!("failed to read world seed, using a new one: {}", e);
+            rustcraft::world::World::new()
+        }
+    };
+    if let Err(e) = save.save_seed(world.seed()) {
+        warn!("failed to save world seed: {}", e);
+    }
+    world.save = Some(Arc::new(save));
+    world
+}
+
 fn setup_scene(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut chunk_materials: ResMut<Assets<ChunkMaterial>>,
+    mut plant_materials: ResMut<Assets<PlantMaterial>>,
+    render_device: Res<RenderDevice>,
+    mut inventory: ResMut<Inventory>,
 ) {
-    let game_world = crate::world::World::new();
+    let mut settings = load_settings();
+    if !settings
+        .renderer
+        .render_style
+        .is_supported(render_device.features())
+    {
+        warn!(
+            "{:?} render style is not supported by this GPU, drawing textured instead",
+            settings.renderer.render_style
+        );
+        settings.renderer.render_style = RenderStyle::Textured;
+    }
+
+    let mut game_world = open_world();
+    game_world.border = settings.world.border;
+    game_world.set_terrain(settings.terrain);
     info!("world seed is {}", game_world.seed());
-    let spawn = Vec3::new(0.0, 20.0, 0.0);
+    let spawn = game_world.spawn_point();
+    match game_world.pregenerate(spawn, settings.world.spawn_radius) {
+        Ok(generated) => info!("generated {} chunks around spawn", generated),
+        Err(e) => warn!("failed to generate chunks around spawn: {}", e),
+    }
     commands.insert_resource(game_world);
+    commands.insert_resource(settings.player.game_mode);
+    // the player starts with a pickaxe so stone can be harvested
+    inventory.add(Item::Tool(ToolTier::Pickaxe));
 
     info!("spawned at {:?}, {:?}, {:?}", spawn.x, spawn.y, spawn.z);
 
@@ -47,23 +183,62 @@ fn setup_scene(
         })
         .id();
 
-    let render_distance = 32;
     let camera = commands
         .spawn((Camera3dBundle {
-            transform: Transform::from_xyz(0.0, 2.0, 0.0),
+            transform: Transform::from_xyz(0.0, settings.player.eye_height(), 0.0),
+            projection: PerspectiveProjection {
+                near: settings.renderer.near_plane,
+                far: settings.renderer.far_plane(),
+                ..default()
+            }
+            .into(),
             ..default()
         },))
         .id();
     commands.entity(player).push_children(&[camera]);
+    commands.insert_resource(settings.renderer.msaa());
+    // anything not covered by the sky dome shows the horizon colour
+    commands.insert_resource(ClearColor(settings.sky.clear_color()));
 
-    let chunk_material_handle = chunk_materials.add(ChunkMaterial {
+    let chunk_material = ChunkMaterial {
+        fade: 1.0,
+        lod_fade: Vec2::new(
+            settings.renderer.lod_fade_start,
+            settings.renderer.lod_fade_end,
+        ),
+        view_distance: settings.renderer.view_distance(),
+        tile_colors: [Vec4::ONE; ATLAS_TILES],
         color: Color::WHITE,
         texture: Some(asset_server.load::<Image>("textures/blocks.png")),
+        alpha_mode: AlphaMode::Mask(0.5),
+        style: settings.renderer.render_style,
+        cull_mode: CullMode::default(),
+    };
+    // water's tint and opacity go on the translucent material, which the shader applies to
+    // water alone, as glass takes its colour and opacity from its texture
+    let translucent_material_handle = chunk_materials.add(ChunkMaterial {
+        alpha_mode: AlphaMode::Blend,
+        color: settings.water.material_color(),
+        ..chunk_material.clone()
     });
-    let chunk_loader = ChunkLoader::new(render_distance as u32, chunk_material_handle);
+    let plant_material_handle = plant_materials.add(PlantMaterial {
+        base: chunk_material.clone(),
+        extension: SwayExtension {
+            strength: PLANT_SWAY,
+        },
+    });
+    let chunk_material_handle = chunk_materials.add(chunk_material);
+    let chunk_loader = ChunkLoader::new(
+        settings.renderer,
+        chunk_material_handle,
+        translucent_material_handle,
+        plant_material_handle,
+    );
     commands.insert_resource(chunk_loader);
+    if let Some(pool) = MeshingTaskPool::new(settings.renderer.meshing_threads) {
+        commands.insert_resource(pool);
+    }
 
-    let settings = read_settings("assets/settings.toml").expect("Failed to read settings.toml");
     commands.spawn(settings);
 }
 
@@ -72,6 +247,21 @@ fn main() {
         .add_plugins((
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
+                .set(RenderPlugin {
+                    // the wireframe render style needs line polygons, so take them from the
+                    // adapter's features when it has them, leaving every other feature off
+                    // and the limits at their defaults
+                    render_creation: WgpuSettings {
+                        priority: WgpuSettingsPriority::Functionality,
+                        disabled_features: Some(
+                            WgpuFeatures::all() - WgpuFeatures::POLYGON_MODE_LINE,
+                        ),
+                        constrained_limits: Some(WgpuSettings::default().limits),
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         present_mode: bevy::window::PresentMode::AutoNoVsync,
@@ -80,17 +270,139 @@ fn main() {
                     ..default()
                 }),
             MaterialPlugin::<ChunkMaterial>::default(),
+            MaterialPlugin::<PlantMaterial>::default(),
         ))
-        .insert_resource(ClearColor(Color::rgb_u8(135, 206, 235)))
-        .insert_resource(Msaa::Off)
-        .add_systems(Startup, setup_scene)
+        .init_state::<AppState>()
+        .init_resource::<Console>()
+        .init_resource::<TargetBlock>()
+        .init_resource::<Inventory>()
+        .init_resource::<CreativeBlock>()
+        .init_resource::<ChunkGizmos>()
+        .init_resource::<ChunkOriginGizmos>()
+        .init_resource::<TakingScreenshot>()
+        .add_systems(
+            Startup,
+            (
+                setup_scene,
+                setup_console,
+                setup_hotbar,
+                setup_sky.after(setup_scene),
+                configure_present_mode.after(setup_scene),
+                setup_underwater_overlay,
+                setup_particles,
+                setup_drops,
+                setup_compass,
+                setup_held_item.after(setup_scene),
+            ),
+        )
+        .add_event::<NewWorld>()
+        .add_event::<BlockBroken>()
+        .add_systems(OnEnter(AppState::Loading), setup_loading_screen)
+        .add_systems(OnExit(AppState::Loading), despawn_loading_screen)
+        .add_systems(OnEnter(AppState::Playing), grab_cursor)
+        .add_systems(
+            OnEnter(AppState::Paused),
+            (setup_pause_menu, release_cursor),
+        )
+        .add_systems(OnExit(AppState::Paused), despawn_pause_menu)
+        .add_systems(Last, (save_chunks_on_exit, limit_frame_rate))
         .add_systems(
             Update,
             (
-                (gather_chunks, generate_chunks, mark_chunks, load_chunks).before(unload_chunks),
-                unload_chunks,
-                player_move,
-                player_look,
+                (
+                    gather_chunks,
+                    generate_chunks,
+                    update_chunk_visibility,
+                    mark_chunks,
+                    load_chunks,
+                    fade_chunks,
+                    sort_translucent_chunks,
+                )
+                    .before(unload_chunks)
+                    .run_if(chunk_loading_active),
+                unload_chunks.run_if(chunk_loading_active),
+                (reload_chunks, remesh_chunks)
+                    .after(unload_chunks)
+                    .run_if(console_closed),
+                (
+                    request_new_world
+                        .run_if(in_state(AppState::Playing))
+                        .run_if(console_closed),
+                    new_world,
+                )
+                    .chain()
+                    .after(unload_chunks),
+                (
+                    check_block_atlas,
+                    configure_block_atlas,
+                    cycle_render_style.run_if(console_closed),
+                    cycle_cull_mode.run_if(console_closed),
+                ),
+                (
+                    toggle_game_mode.before(player_move),
+                    cycle_creative_block,
+                    player_move,
+                    player_look.run_if(cursor_grabbed),
+                    respawn_from_void.after(player_move),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(console_closed),
+                (
+                    toggle_free_camera,
+                    free_camera_move,
+                    free_camera_look.run_if(cursor_grabbed),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(console_closed),
+                (toggle_console, console_input, update_console_text).chain(),
+                (
+                    update_target_block,
+                    (break_block, place_block)
+                        .run_if(in_state(AppState::Playing))
+                        .run_if(console_closed)
+                        .run_if(cursor_grabbed),
+                    draw_block_outline,
+                    // after interactions, so the click that grabs the cursor doesn't break a block
+                    grab_cursor_on_click.run_if(in_state(AppState::Playing)),
+                )
+                    .chain(),
+                (
+                    select_hotbar_slot
+                        .run_if(in_state(AppState::Playing))
+                        .run_if(console_closed),
+                    update_hotbar,
+                    update_held_item,
+                    animate_held_item.run_if(in_state(AppState::Playing)),
+                )
+                    .chain(),
+                update_loading.run_if(in_state(AppState::Loading)),
+                take_screenshot
+                    .run_if(console_closed)
+                    .before(draw_chunk_gizmos)
+                    .before(draw_chunk_origin_gizmos)
+                    .before(update_compass),
+                toggle_pause.run_if(console_closed),
+                follow_camera_sky,
+                (release_cursor_on_focus_loss, scale_ui_to_window),
+                update_underwater,
+                (toggle_compass.run_if(console_closed), update_compass).chain(),
+                (
+                    toggle_chunk_gizmos.run_if(console_closed),
+                    (draw_chunk_gizmos, draw_chunk_origin_gizmos),
+                )
+                    .chain(),
+                (
+                    spawn_break_particles.after(break_block),
+                    update_particles.run_if(in_state(AppState::Playing)),
+                ),
+                (
+                    spawn_block_drops.after(break_block),
+                    update_on_screen,
+                    update_drops.run_if(in_state(AppState::Playing)),
+                    pick_up_drops.run_if(in_state(AppState::Playing)),
+                )
+                    .chain(),
             ),
         )
         .run();
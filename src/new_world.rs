@@ -0,0 +1,123 @@
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{Or, With},
+        schedule::NextState,
+        system::{Commands, Local, Query, Res, ResMut},
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{keyboard::KeyCode, ButtonInput},
+    log::{error, info, warn},
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    chunks::chunk_loader::{Chunk, ChunkLoader},
+    drops::ItemDrop,
+    player::PlayerMovement,
+    settings::Settings,
+    state::AppState,
+    world::World,
+};
+
+const NEW_WORLD_KEY: KeyCode = KeyCode::F9;
+/// Seconds within which the new world key has to be pressed a second time to confirm.
+const CONFIRM_WINDOW: f32 = 3.0;
+
+/// Replaces the current world with a freshly generated one, using a random seed unless
+/// one is given.
+#[derive(Event)]
+pub struct NewWorld {
+    pub seed: Option<u32>,
+}
+
+/// Whether a press at `now` confirms one made at `armed_at`. An unconfirmed press arms the
+/// next one instead.
+fn confirm_press(armed_at: &mut Option<f32>, now: f32) -> bool {
+    let confirmed = armed_at.is_some_and(|armed_at| now - armed_at <= CONFIRM_WINDOW);
+    *armed_at = (!confirmed).then_some(now);
+    confirmed
+}
+
+/// Asks for a new world when the key is pressed twice in a row, as the new world replaces
+/// the saved one along with every edit made to it.
+pub fn request_new_world(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut events: EventWriter<NewWorld>,
+    mut armed_at: Local<Option<f32>>,
+) {
+    if !keys.just_pressed(NEW_WORLD_KEY) {
+        return;
+    }
+
+    if confirm_press(&mut armed_at, time.elapsed_seconds()) {
+        events.send(NewWorld { seed: None });
+    } else {
+        warn!(
+            "press {:?} again within {} seconds to replace this world and its saved edits",
+            NEW_WORLD_KEY, CONFIRM_WINDOW
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn new_world(
+    mut commands: Commands,
+    mut events: EventReader<NewWorld>,
+    mut world: ResMut<World>,
+    mut chunk_loader: ResMut<ChunkLoader>,
+    mut next_state: ResMut<NextState<AppState>>,
+    // drops belong to the old world too
+    chunks_query: Query<Entity, Or<(With<Chunk>, With<ItemDrop>)>>,
+    mut player_query: Query<(&mut Transform, &mut PlayerMovement)>,
+    settings_query: Query<&Settings>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    for entity in chunks_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    chunk_loader.reset();
+
+    let seed = event.seed.unwrap_or_else(rand::random);
+    if let Err(e) = world.reset(seed) {
+        error!("failed to clear world save: {}", e);
+    }
+
+    let spawn = world.spawn_point();
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    if let Err(e) = world.pregenerate(spawn, settings.world.spawn_radius) {
+        error!("failed to generate chunks around spawn: {}", e);
+    }
+    for (mut transform, mut movement) in player_query.iter_mut() {
+        transform.translation = spawn;
+        movement.stop();
+    }
+
+    // hold the player on the loading screen until the new terrain around them is ready
+    next_state.set(AppState::Loading);
+    info!("created new world with seed {}", seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{confirm_press, CONFIRM_WINDOW};
+
+    #[test]
+    fn test_new_world_needs_a_second_press() {
+        let mut armed_at = None;
+        assert!(!confirm_press(&mut armed_at, 1.0));
+        assert!(confirm_press(&mut armed_at, 2.0));
+
+        // a confirmed press doesn't arm the next one
+        assert!(!confirm_press(&mut armed_at, 2.5));
+        // and a second press too late only arms again
+        assert!(!confirm_press(&mut armed_at, 3.0 + CONFIRM_WINDOW));
+        assert!(confirm_press(&mut armed_at, 4.0 + CONFIRM_WINDOW));
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::{
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::{primitives::Cuboid, I64Vec3, Vec3},
+    pbr::{AlphaMode, PbrBundle, StandardMaterial},
+    render::{
+        mesh::{Mesh, Meshable, VertexAttributeValues},
+        texture::Image,
+    },
+    time::Time,
+    transform::components::Transform,
+    utils::{default, HashMap},
+};
+
+use crate::{
+    block::{BlockType, ATLAS_TILES},
+    settings::Settings,
+};
+
+/// Fraction of a block's atlas tile shown on each particle, so particles look like chips
+/// of the block rather than shrunken copies of it.
+const PARTICLE_TILE_FRACTION: f32 = 0.25;
+
+/// Sent when the player breaks a block.
+#[derive(Event)]
+pub struct BlockBroken {
+    pub block: I64Vec3,
+    pub block_type: BlockType,
+    /// Whether the block was broken with a tool good enough for it to drop.
+    pub harvested: bool,
+}
+
+/// A short-lived chip of a block, thrown out when the block breaks.
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec3,
+    /// Seconds left before the particle is despawned.
+    lifetime: f32,
+}
+
+/// The material shared by every particle, and a mesh per block type textured with a
+/// corner of that block's atlas tile.
+#[derive(Resource)]
+pub struct ParticleAssets {
+    material: Handle<StandardMaterial>,
+    meshes: HashMap<BlockType, Handle<Mesh>>,
+}
+
+pub fn setup_particles(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(asset_server.load::<Image>("textures/blocks.png")),
+        alpha_mode: AlphaMode::Mask(0.5),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    commands.insert_resource(ParticleAssets {
+        material,
+        meshes: HashMap::new(),
+    });
+}
+
+pub fn spawn_break_particles(
+    mut commands: Commands,
+    mut events: EventReader<BlockBroken>,
+    mut particle_assets: ResMut<ParticleAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings_query: Query<&Settings>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+
+    for event in events.read() {
+        if event.block_type == BlockType::Air {
+            continue;
+        }
+
+        let mesh = particle_assets
+            .meshes
+            .entry(event.block_type)
+            .or_insert_with(|| {
+                meshes.add(block_cube_mesh(event.block_type, PARTICLE_TILE_FRACTION))
+            })
+            .clone();
+        let centre = event.block.as_vec3();
+        for _ in 0..settings.particles.count {
+            let offset = random_vec3() * 0.3;
+            let velocity = Vec3::new(offset.x, offset.y.abs() + 0.5, offset.z) * 8.0;
+            commands.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: particle_assets.material.clone(),
+                    transform: Transform::from_translation(centre + offset)
+                        .with_scale(Vec3::splat(settings.particles.size)),
+                    ..default()
+                },
+                Particle {
+                    velocity,
+                    lifetime: settings.particles.lifetime * (0.5 + rand::random::<f32>() * 0.5),
+                },
+            ));
+        }
+    }
+}
+
+/// Moves particles under gravity and despawns them once their lifetime runs out. Only runs
+/// while playing, so particles hang in the air while paused.
+pub fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles_query: Query<(Entity, &mut Particle, &mut Transform)>,
+    settings_query: Query<&Settings>,
+) {
+    let gravity = settings_query
+        .get_single()
+        .map(|settings| settings.player.gravity)
+        .unwrap_or_default();
+    let delta = time.delta_seconds();
+
+    for (entity, mut particle, mut transform) in particles_query.iter_mut() {
+        particle.lifetime -= delta;
+        if particle.lifetime <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y += gravity * delta;
+        transform.translation += particle.velocity * delta;
+    }
+}
+
+/// A unit cube with every face textured with the same corner of the block's atlas tile,
+/// covering `tile_fraction` of the tile's width and height. Air has no tile, so its cube
+/// keeps the uvs it was built with.
+pub fn block_cube_mesh(block: BlockType, tile_fraction: f32) -> Mesh {
+    let mut mesh = Cuboid::new(1.0, 1.0, 1.0).mesh();
+    let Some(tile) = block.texture_index().map(|tile| tile as f32) else {
+        return mesh;
+    };
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for uv in uvs.iter_mut() {
+            *uv = [
+                (tile + uv[0] * tile_fraction) / ATLAS_TILES as f32,
+                uv[1] * tile_fraction,
+            ];
+        }
+    }
+    mesh
+}
+
+/// A random vector with each component between -1 and 1.
+fn random_vec3() -> Vec3 {
+    Vec3::new(rand::random(), rand::random(), rand::random()) * 2.0 - Vec3::ONE
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::{Mesh, VertexAttributeValues};
+
+    use crate::block::{BlockType, ATLAS_TILES};
+
+    use super::{block_cube_mesh, PARTICLE_TILE_FRACTION};
+
+    #[test]
+    fn test_particle_mesh_stays_within_block_tile() {
+        let mesh = block_cube_mesh(BlockType::Sand, PARTICLE_TILE_FRACTION);
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0)
+        else {
+            panic!("particle mesh has no uvs");
+        };
+
+        let tile_width = 1.0 / ATLAS_TILES as f32;
+        let tile_start = BlockType::Sand.texture_index().unwrap() as f32 * tile_width;
+        for uv in uvs {
+            assert!(uv[0] >= tile_start && uv[0] <= tile_start + tile_width);
+            assert!(uv[1] >= 0.0 && uv[1] <= 1.0);
+        }
+    }
+}
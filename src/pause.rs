@@ -0,0 +1,87 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{NextState, State},
+        system::{Commands, Query, Res, ResMut},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{keyboard::KeyCode, ButtonInput},
+    render::color::Color,
+    text::TextStyle,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, BackgroundColor, FlexDirection, JustifyContent, Style, Val,
+    },
+    utils::default,
+};
+
+use crate::{settings::Settings, state::AppState};
+
+const PAUSE_KEY: KeyCode = KeyCode::Escape;
+
+#[derive(Component)]
+pub struct PauseMenu;
+
+pub fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keys.just_pressed(PAUSE_KEY) {
+        return;
+    }
+
+    match state.get() {
+        AppState::Playing => next_state.set(AppState::Paused),
+        AppState::Paused => next_state.set(AppState::Playing),
+        AppState::Loading => {}
+    }
+}
+
+/// Whether chunks should keep loading, which they always do unless the game is paused
+/// and `load_chunks_while_paused` is off.
+pub fn chunk_loading_active(state: Res<State<AppState>>, settings_query: Query<&Settings>) -> bool {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    *state.get() != AppState::Paused || settings.world.load_chunks_while_paused
+}
+
+pub fn setup_pause_menu(mut commands: Commands) {
+    let text_style = |font_size| TextStyle {
+        font_size,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+                z_index: bevy::ui::ZIndex::Global(5),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Paused", text_style(48.0)));
+            parent.spawn(TextBundle::from_section(
+                "Press Escape to resume",
+                text_style(20.0),
+            ));
+        });
+}
+
+pub fn despawn_pause_menu(mut commands: Commands, menu_query: Query<Entity, With<PauseMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,280 @@
+use bevy::math::{BVec3, I64Vec3, Vec3};
+
+use crate::world::World;
+
+/// Largest distance moved in a single collision step, so fast movement can't tunnel
+/// through a block.
+const MAX_STEP: f32 = 0.25;
+/// Gap left between a body and the block it collided with.
+const SKIN: f32 = 0.001;
+
+/// Moves an axis-aligned box through the world one axis at a time, stopping it against
+/// collidable blocks. `position` is the centre of the bottom face of the box. Returns the
+/// new position and which axes were blocked.
+pub fn move_and_collide(
+    world: &mut World,
+    position: Vec3,
+    size: Vec3,
+    movement: Vec3,
+) -> (Vec3, BVec3) {
+    let steps = (movement.abs().max_element() / MAX_STEP).ceil().max(1.0);
+    let step = movement / steps;
+
+    let mut position = position;
+    let mut blocked = [false; 3];
+    for _ in 0..steps as u32 {
+        for axis in 0..3 {
+            if blocked[axis] || step[axis] == 0.0 {
+                continue;
+            }
+
+            position[axis] += step[axis];
+            if let Some(limit) = collision_limit(world, position, size, axis, step[axis]) {
+                position[axis] = limit;
+                blocked[axis] = true;
+            }
+        }
+    }
+
+    (position, BVec3::new(blocked[0], blocked[1], blocked[2]))
+}
+
+/// Like [`move_and_collide`], but a box blocked horizontally also tries stepping up onto
+/// whatever blocked it, by up to `step_height` blocks, and keeps the step if it gets further.
+pub fn move_and_step(
+    world: &mut World,
+    position: Vec3,
+    size: Vec3,
+    movement: Vec3,
+    step_height: f32,
+) -> (Vec3, BVec3) {
+    let (moved, blocked) = move_and_collide(world, position, size, movement);
+    if step_height <= 0.0 || !(blocked.x || blocked.z) {
+        return (moved, blocked);
+    }
+
+    // climb, move across at the raised height, then settle back down onto the step
+    let (raised, _) = move_and_collide(world, position, size, Vec3::Y * step_height);
+    let horizontal = Vec3::new(movement.x, 0.0, movement.z);
+    let (across, stepped_blocked) = move_and_collide(world, raised, size, horizontal);
+    let (landed, landed_blocked) =
+        move_and_collide(world, across, size, Vec3::NEG_Y * (raised.y - position.y));
+
+    let progress = |end: Vec3| Vec3::new(end.x - position.x, 0.0, end.z - position.z).length();
+    if progress(landed) > progress(moved) + SKIN {
+        (
+            landed,
+            BVec3::new(stepped_blocked.x, landed_blocked.y, stepped_blocked.z),
+        )
+    } else {
+        (moved, blocked)
+    }
+}
+
+/// Returns the furthest position along `axis` the box can occupy without overlapping a
+/// collidable block, or `None` if it doesn't overlap any. Ungenerated blocks are not
+/// collidable.
+fn collision_limit(
+    world: &mut World,
+    position: Vec3,
+    size: Vec3,
+    axis: usize,
+    direction: f32,
+) -> Option<f32> {
+    let (min, max) = bounds(position, size);
+    let (min_block, max_block) = (block_containing(min), block_containing(max));
+
+    let mut limit: Option<f32> = None;
+    for x in min_block.x..=max_block.x {
+        for y in min_block.y..=max_block.y {
+            for z in min_block.z..=max_block.z {
+                let block = I64Vec3::new(x, y, z);
+                let Some(block_type) = world.get_block(block).filter(|b| b.is_collidable()) else {
+                    continue;
+                };
+
+                // blocks are centred on integer coordinates, and shapes like slabs only
+                // fill the bottom of their cell
+                let bottom = block.y as f32 - 0.5;
+                let top = bottom + block_type.shape().collision_height();
+                if min.y >= top {
+                    continue;
+                }
+                let face = if axis == 1 && direction < 0.0 {
+                    top
+                } else {
+                    block[axis] as f32 - 0.5 * direction.signum()
+                };
+                let offset = if direction > 0.0 {
+                    max[axis] - position[axis]
+                } else {
+                    min[axis] - position[axis]
+                };
+                let candidate = face - offset - SKIN * direction.signum();
+                limit = Some(match limit {
+                    Some(limit) if direction > 0.0 => limit.min(candidate),
+                    Some(limit) => limit.max(candidate),
+                    None => candidate,
+                });
+            }
+        }
+    }
+
+    limit
+}
+
+fn bounds(position: Vec3, size: Vec3) -> (Vec3, Vec3) {
+    let half = Vec3::new(size.x / 2.0, 0.0, size.z / 2.0);
+    (
+        position - half,
+        position + half + Vec3::new(0.0, size.y, 0.0),
+    )
+}
+
+fn block_containing(point: Vec3) -> I64Vec3 {
+    (point + Vec3::splat(0.5)).floor().as_i64vec3()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{I64Vec3, Vec3};
+
+    use crate::{
+        block::BlockType,
+        chunks::chunk::{ChunkCoordinate, ChunkData},
+        world::World,
+    };
+
+    use super::{move_and_collide, move_and_step};
+
+    const SIZE: Vec3 = Vec3::new(0.6, 1.8, 0.6);
+
+    fn world_with_floor() -> World {
+        let mut world = World::new();
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+        for x in 0..16 {
+            for z in 0..16 {
+                world.set_block(I64Vec3::new(x, 0, z), BlockType::Stone);
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn test_falling_stops_on_floor() {
+        let mut world = world_with_floor();
+
+        let (position, blocked) = move_and_collide(
+            &mut world,
+            Vec3::new(8.0, 3.0, 8.0),
+            SIZE,
+            Vec3::new(0.0, -10.0, 0.0),
+        );
+
+        assert!(blocked.y);
+        assert!((position.y - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_wall_blocks_horizontal_movement() {
+        let mut world = world_with_floor();
+        world.set_block(I64Vec3::new(10, 1, 8), BlockType::Stone);
+
+        let (position, blocked) = move_and_collide(
+            &mut world,
+            Vec3::new(8.0, 0.51, 8.0),
+            SIZE,
+            Vec3::new(5.0, 0.0, 0.0),
+        );
+
+        assert!(blocked.x);
+        assert!(!blocked.y);
+        assert!((position.x - (9.5 - 0.3)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_steps_up_single_block_ledge() {
+        let mut world = world_with_floor();
+        for x in 10..16 {
+            for z in 0..16 {
+                world.set_block(I64Vec3::new(x, 1, z), BlockType::Stone);
+            }
+        }
+        let start = Vec3::new(8.0, 0.501, 8.0);
+        let movement = Vec3::new(3.0, -0.1, 0.0);
+
+        let (position, blocked) = move_and_step(&mut world, start, SIZE, movement, 1.0);
+        assert!(!blocked.x);
+        assert!(blocked.y);
+        assert!((position.y - 1.5).abs() < 0.01);
+        assert!((position.x - 11.0).abs() < 0.01);
+
+        // without stepping, or at a ledge two blocks high, the ledge stops the player
+        let (_, blocked) = move_and_step(&mut world, start, SIZE, movement, 0.0);
+        assert!(blocked.x);
+        for z in 0..16 {
+            world.set_block(I64Vec3::new(10, 2, z), BlockType::Stone);
+        }
+        let (_, blocked) = move_and_step(&mut world, start, SIZE, movement, 1.0);
+        assert!(blocked.x);
+    }
+
+    #[test]
+    fn test_slabs_collide_as_half_blocks() {
+        let mut world = world_with_floor();
+        for x in 10..16 {
+            for z in 0..16 {
+                world.set_block(I64Vec3::new(x, 1, z), BlockType::StoneSlab);
+            }
+        }
+
+        // lands on top of the slab rather than a block's height up
+        let (position, blocked) = move_and_collide(
+            &mut world,
+            Vec3::new(10.0, 3.0, 8.0),
+            SIZE,
+            Vec3::new(0.0, -10.0, 0.0),
+        );
+        assert!(blocked.y);
+        assert!((position.y - 1.0).abs() < 0.01);
+
+        // and half a block is low enough to step onto, where a full block isn't
+        let start = Vec3::new(8.0, 0.501, 8.0);
+        let movement = Vec3::new(3.0, -0.1, 0.0);
+        let (position, blocked) = move_and_step(&mut world, start, SIZE, movement, 0.6);
+        assert!(!blocked.x);
+        assert!((position.y - 1.0).abs() < 0.01);
+        for x in 10..16 {
+            for z in 0..16 {
+                world.set_block(I64Vec3::new(x, 1, z), BlockType::Stone);
+            }
+        }
+        let (_, blocked) = move_and_step(&mut world, start, SIZE, movement, 0.6);
+        assert!(blocked.x);
+    }
+
+    #[test]
+    fn test_tall_grass_is_walked_through() {
+        let mut world = world_with_floor();
+        world.set_block(I64Vec3::new(10, 1, 8), BlockType::TallGrass);
+
+        let start = Vec3::new(8.0, 0.51, 8.0);
+        let movement = Vec3::new(5.0, 0.0, 0.0);
+        let (position, blocked) = move_and_collide(&mut world, start, SIZE, movement);
+
+        assert!(!blocked.x);
+        assert!((position - (start + movement)).length() < 0.001);
+    }
+
+    #[test]
+    fn test_free_movement_is_unblocked() {
+        let mut world = world_with_floor();
+
+        let movement = Vec3::new(1.0, 2.0, -1.0);
+        let start = Vec3::new(8.0, 1.0, 8.0);
+        let (position, blocked) = move_and_collide(&mut world, start, SIZE, movement);
+
+        assert!(!blocked.any());
+        assert!((position - (start + movement)).length() < 0.001);
+    }
+}
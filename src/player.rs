@@ -4,16 +4,32 @@ use bevy::{
         component::Component,
         event::EventReader,
         query::{With, Without},
-        system::{Query, Res},
+        schedule::NextState,
+        system::{Query, Res, ResMut},
     },
     hierarchy::Parent,
-    input::{keyboard::KeyCode, mouse::MouseMotion, ButtonInput},
-    math::Vec3,
+    input::{
+        keyboard::KeyCode,
+        mouse::{MouseMotion, MouseWheel},
+        ButtonInput,
+    },
+    log::{error, warn},
+    math::{Vec2, Vec3},
     render::camera::Camera,
     time::Time,
     transform::{components::Transform, TransformBundle},
 };
 
+use serde::Deserialize;
+
+use crate::{
+    game_mode::GameMode,
+    physics::move_and_step,
+    settings::{PlayerSettings, Settings},
+    state::AppState,
+    world::World,
+};
+
 #[derive(Bundle, Default)]
 pub struct PlayerBundle {
     pub marker: Player,
@@ -25,90 +41,500 @@ pub struct PlayerBundle {
 #[derive(Component, Default)]
 pub struct Player {}
 
+const FLY_KEY: KeyCode = KeyCode::KeyF;
+const FORWARD_KEY: KeyCode = KeyCode::KeyW;
+/// Factor flying speed is scaled by for each notch of the scroll wheel.
+const FLY_SPEED_SCROLL_STEP: f32 = 1.25;
+/// Range the scroll wheel can scale flying speed over.
+const MIN_FLY_SPEED_SCALE: f32 = 0.1;
+const MAX_FLY_SPEED_SCALE: f32 = 10.0;
+
+/// How the player starts sprinting.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SprintMode {
+    /// Sprint while the sprint key is held along with forward.
+    #[default]
+    Hold,
+    /// Sprint after tapping forward twice in quick succession, until forward is released.
+    DoubleTap,
+}
+
 #[derive(Component)]
 pub struct PlayerMovement {
-    move_speed: f32,
+    walk_speed: f32,
+    jump_velocity: f32,
+    vertical_velocity: f32,
+    flying: bool,
+    grounded: bool,
+    sprinting: bool,
+    /// When forward was last pressed, for spotting double taps.
+    last_forward_press: Option<f32>,
+    /// Scale on flying speed picked with the scroll wheel.
+    fly_speed_scale: f32,
 }
 
 impl Default for PlayerMovement {
     fn default() -> Self {
-        Self { move_speed: 20.0 }
+        Self {
+            walk_speed: 5.0,
+            jump_velocity: 5.0,
+            vertical_velocity: 0.0,
+            flying: true,
+            grounded: false,
+            sprinting: false,
+            last_forward_press: None,
+            fly_speed_scale: 1.0,
+        }
     }
 }
 
+impl PlayerMovement {
+    /// Starts or stops sprinting from this frame's input. Sprinting only lasts while
+    /// moving forward.
+    fn update_sprint(&mut self, settings: &PlayerSettings, keys: &ButtonInput<KeyCode>, now: f32) {
+        if !keys.pressed(FORWARD_KEY) {
+            self.sprinting = false;
+            return;
+        }
+
+        match settings.sprint_mode {
+            SprintMode::Hold => self.sprinting = keys.pressed(settings.sprint_key),
+            SprintMode::DoubleTap => {
+                if keys.just_pressed(FORWARD_KEY) {
+                    let double_tap = self
+                        .last_forward_press
+                        .is_some_and(|last| now - last <= settings.double_tap_window);
+                    // a third tap starts a new double tap rather than completing another
+                    self.last_forward_press = if double_tap { None } else { Some(now) };
+                    self.sprinting = double_tap;
+                }
+            }
+        }
+    }
+
+    fn speed_multiplier(&self, settings: &PlayerSettings) -> f32 {
+        if self.sprinting {
+            settings.sprint_multiplier
+        } else {
+            1.0
+        }
+    }
+
+    pub fn is_flying(&self) -> bool {
+        self.flying
+    }
+
+    /// Scales flying speed up for scrolling up and down for scrolling down, a step per
+    /// notch.
+    fn scroll_fly_speed(&mut self, notches: f32) {
+        self.fly_speed_scale = (self.fly_speed_scale * FLY_SPEED_SCROLL_STEP.powf(notches))
+            .clamp(MIN_FLY_SPEED_SCALE, MAX_FLY_SPEED_SCALE);
+    }
+
+    /// Flying speed in blocks per second, before sprinting.
+    fn fly_speed(&self, settings: &PlayerSettings) -> f32 {
+        settings.fly_speed * settings.fly_speed_multiplier * self.fly_speed_scale
+    }
+
+    /// Cancels any fall in progress, e.g. after teleporting.
+    pub fn stop(&mut self) {
+        self.vertical_velocity = 0.0;
+        self.grounded = false;
+    }
+}
+
+/// Direction to move in from the movement keys, in the mover's own space where -z is
+/// forward.
+pub fn movement_input(keys: &ButtonInput<KeyCode>) -> Vec3 {
+    let mut input = Vec3::ZERO;
+    if keys.pressed(KeyCode::KeyA) {
+        input.x = -1.0;
+    } else if keys.pressed(KeyCode::KeyD) {
+        input.x = 1.0;
+    }
+
+    if keys.pressed(FORWARD_KEY) {
+        input.z = -1.0;
+    } else if keys.pressed(KeyCode::KeyS) {
+        input.z = 1.0;
+    }
+    input
+}
+
+/// Vertical direction to fly in: 1 for up, -1 for down or 0.
+pub fn fly_vertical_input(keys: &ButtonInput<KeyCode>) -> f32 {
+    if keys.pressed(KeyCode::Space) {
+        1.0
+    } else if keys.pressed(KeyCode::ShiftLeft) {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+/// Yaw and pitch in radians to turn by for a frame's mouse movement of `delta`.
+pub fn look_angles(delta: Vec2, settings: &PlayerSettings, delta_seconds: f32) -> (f32, f32) {
+    let turn = settings.mouse_sensitivity * delta_seconds;
+    (-delta.x * turn, settings.pitch_sign() * delta.y * turn)
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn player_move(
     time: Res<Time>,
-    mut player_query: Query<(&PlayerMovement, &mut Transform)>,
-    camera_query: Query<(&Parent, &Transform), (With<Camera>, Without<PlayerMovement>)>,
     keys: Res<ButtonInput<KeyCode>>,
+    mut wheel_evr: EventReader<MouseWheel>,
+    game_mode: Res<GameMode>,
+    mut world: ResMut<World>,
+    settings_query: Query<&Settings>,
+    mut player_query: Query<(&mut PlayerMovement, &mut Transform)>,
+    camera_query: Query<(&Parent, &Transform), (With<Camera>, Without<PlayerMovement>)>,
 ) {
-    let (parent, camera_transform) = camera_query.get_single().expect("camera does not exist");
+    // the camera has no parent while detached as a free camera
+    let Ok((parent, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
     let (player_movement, player_transform) = &mut player_query
         .get_mut(parent.get())
         .expect("player does not exist");
+    let settings = settings_query.get_single().copied().unwrap_or_default();
 
-    let move_speed = player_movement.move_speed;
+    if keys.just_pressed(FLY_KEY) && game_mode.can_fly() {
+        player_movement.flying = !player_movement.flying;
+        player_movement.vertical_velocity = 0.0;
+    }
+    // leaving creative mode drops the player out of the air
+    if player_movement.flying && !game_mode.can_fly() {
+        player_movement.flying = false;
+        player_movement.vertical_velocity = 0.0;
+    }
 
-    let mut movement_vector = Vec3::ZERO;
-    if keys.pressed(KeyCode::KeyA) {
-        movement_vector.x = -move_speed;
-    } else if keys.pressed(KeyCode::KeyD) {
-        movement_vector.x = move_speed;
+    player_movement.update_sprint(&settings.player, &keys, time.elapsed_seconds());
+    let sprint = player_movement.speed_multiplier(&settings.player);
+
+    let input = movement_input(&keys);
+
+    // the scroll wheel picks the hotbar slot while walking
+    let scroll: f32 = wheel_evr.read().map(|ev| ev.y).sum();
+    if player_movement.flying {
+        if scroll != 0.0 {
+            player_movement.scroll_fly_speed(scroll.signum());
+        }
+        let move_speed = player_movement.fly_speed(&settings.player) * sprint;
+        let vertical_movement = Vec3::Y * fly_vertical_input(&keys) * move_speed;
+
+        let final_movement = player_transform.rotation
+            * camera_transform.rotation
+            * (input * move_speed)
+            * time.delta_seconds()
+            + (vertical_movement * time.delta_seconds());
+        player_transform.translation =
+            world.clamp_to_border(player_transform.translation + final_movement);
+        return;
     }
 
-    if keys.pressed(KeyCode::KeyW) {
-        movement_vector.z = -move_speed;
-    } else if keys.pressed(KeyCode::KeyS) {
-        movement_vector.z = move_speed;
+    // hold still without falling until the chunks the player is standing in and on have
+    // generated, rather than treating them as air and falling through the world
+    let below = player_transform.translation - Vec3::Y;
+    if !world.is_position_loaded(player_transform.translation) || !world.is_position_loaded(below) {
+        player_movement.vertical_velocity = 0.0;
+        return;
     }
+    let feet = world
+        .block_at(player_transform.translation)
+        .unwrap_or_default();
+    let body = world
+        .block_at(player_transform.translation + Vec3::Y)
+        .unwrap_or_default();
+    let speed_multiplier = feet.speed_multiplier().min(body.speed_multiplier()) * sprint;
 
-    let mut vertical_movement = Vec3::ZERO;
-    if keys.pressed(KeyCode::Space) {
-        vertical_movement.y = move_speed;
-    } else if keys.pressed(KeyCode::ShiftLeft) {
-        vertical_movement.y = -move_speed;
+    if player_movement.grounded && keys.pressed(KeyCode::Space) {
+        player_movement.vertical_velocity = player_movement.jump_velocity;
     }
 
-    let final_movement = player_transform.rotation
-        * camera_transform.rotation
-        * movement_vector
-        * time.delta_seconds()
-        + (vertical_movement * time.delta_seconds());
-    player_transform.translation += final_movement;
+    player_movement.vertical_velocity = (player_movement.vertical_velocity
+        + settings.player.gravity * time.delta_seconds())
+    .max(-settings.player.terminal_velocity);
+
+    // walking ignores the camera's pitch so looking up or down doesn't slow the player
+    let horizontal =
+        player_transform.rotation * (input * player_movement.walk_speed * speed_multiplier);
+    let movement = Vec3::new(
+        horizontal.x,
+        player_movement.vertical_velocity,
+        horizontal.z,
+    ) * time.delta_seconds();
+
+    // only step up from the ground, so the player can't climb walls mid-jump
+    let step_height = if player_movement.grounded {
+        settings.player.step_height
+    } else {
+        0.0
+    };
+    let (position, blocked) = move_and_step(
+        &mut world,
+        player_transform.translation,
+        settings.player.collision_size(),
+        movement,
+        step_height,
+    );
+    player_transform.translation = world.clamp_to_border(position);
+    player_movement.grounded = blocked.y && movement.y < 0.0;
+    if blocked.y {
+        player_movement.vertical_velocity = 0.0;
+    }
+}
+
+/// Returns a player who has fallen out of the world, e.g. through a chunk that failed to
+/// load, to the spawn point, generating the ground there again before they land on it.
+pub fn respawn_from_void(
+    mut world: ResMut<World>,
+    mut next_state: ResMut<NextState<AppState>>,
+    settings_query: Query<&Settings>,
+    mut player_query: Query<(&mut Transform, &mut PlayerMovement)>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    for (mut transform, mut movement) in player_query.iter_mut() {
+        if transform.translation.y >= settings.world.void_height {
+            continue;
+        }
+
+        let spawn = world.spawn_point();
+        warn!(
+            "player fell out of the world at {:?}, returning to spawn",
+            transform.translation
+        );
+        if let Err(e) = world.pregenerate(spawn, settings.world.spawn_radius) {
+            error!("failed to generate chunks around spawn: {}", e);
+        }
+        transform.translation = spawn;
+        movement.stop();
+        // wait on the loading screen until the chunks around spawn are meshed
+        next_state.set(AppState::Loading);
+    }
 }
 
+/// Frame time the mouse smoothing amount is defined at, so smoothing feels the same at
+/// any frame rate.
+const SMOOTHING_FRAME_TIME: f32 = 1.0 / 60.0;
+
 #[derive(Component)]
 pub struct PlayerLook {
-    sensitivity: f32,
+    /// Mouse movement applied last frame, after smoothing.
+    smoothed_delta: Vec2,
 }
 
 impl Default for PlayerLook {
     fn default() -> Self {
-        Self { sensitivity: 0.1 }
+        Self {
+            smoothed_delta: Vec2::ZERO,
+        }
+    }
+}
+
+/// Blends this frame's mouse movement with the previous smoothed movement. `smoothing` is
+/// the share of the previous movement kept after a 60th of a second, so 0 passes `raw`
+/// straight through.
+fn smooth_mouse_delta(previous: Vec2, raw: Vec2, smoothing: f32, delta_seconds: f32) -> Vec2 {
+    let smoothing = smoothing.clamp(0.0, 0.99);
+    if smoothing == 0.0 {
+        return raw;
     }
+    let kept = smoothing.powf(delta_seconds / SMOOTHING_FRAME_TIME);
+    previous * kept + raw * (1.0 - kept)
 }
 
+#[allow(clippy::type_complexity)]
 pub fn player_look(
     time: Res<Time>,
-    mut player_query: Query<(&PlayerLook, &mut Transform)>,
+    mut player_query: Query<(&mut PlayerLook, &mut Transform)>,
     mut camera_query: Query<(&Parent, &mut Transform), (With<Camera>, Without<PlayerLook>)>,
     mut motion_evr: EventReader<MouseMotion>,
+    settings_query: Query<&Settings>,
 ) {
-    let (parent, camera_transform) = &mut camera_query
-        .get_single_mut()
-        .expect("camera does not exist");
+    let Ok((parent, camera_transform)) = &mut camera_query.get_single_mut() else {
+        return;
+    };
+    let settings = settings_query.get_single().copied().unwrap_or_default();
     let (player_look, player_transform) = &mut player_query
         .get_mut(parent.get())
         .expect("player does not exist");
 
-    for ev in motion_evr.read() {
-        player_transform.rotate_axis(
-            Vec3::new(0.0, 1.0, 0.0),
-            -ev.delta.x * player_look.sensitivity * time.delta_seconds(),
+    let raw: Vec2 = motion_evr.read().map(|ev| ev.delta).sum();
+    let delta = smooth_mouse_delta(
+        player_look.smoothed_delta,
+        raw,
+        settings.player.mouse_smoothing,
+        time.delta_seconds(),
+    );
+    player_look.smoothed_delta = delta;
+
+    let (yaw, pitch) = look_angles(delta, &settings.player, time.delta_seconds());
+    player_transform.rotate_axis(Vec3::new(0.0, 1.0, 0.0), yaw);
+    camera_transform.rotate_axis(Vec3::new(1.0, 0.0, 0.0), pitch);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::{schedule::NextState, system::RunSystemOnce},
+        input::{keyboard::KeyCode, ButtonInput},
+        math::{I64Vec3, Vec2, Vec3},
+        transform::components::Transform,
+    };
+
+    use crate::{
+        block::BlockType,
+        chunks::chunk::{ChunkCoordinate, ChunkData},
+        physics::move_and_collide,
+        settings::PlayerSettings,
+        state::AppState,
+        world::World,
+    };
+
+    use super::{
+        respawn_from_void, smooth_mouse_delta, PlayerMovement, SprintMode, FORWARD_KEY,
+        MAX_FLY_SPEED_SCALE, SMOOTHING_FRAME_TIME,
+    };
+
+    #[test]
+    fn test_hold_sprint_needs_key_and_forward() {
+        let settings = PlayerSettings::default();
+        let mut movement = PlayerMovement::default();
+        let mut keys = ButtonInput::<KeyCode>::default();
+
+        keys.press(settings.sprint_key);
+        movement.update_sprint(&settings, &keys, 0.0);
+        assert!(!movement.sprinting);
+
+        keys.press(FORWARD_KEY);
+        movement.update_sprint(&settings, &keys, 0.1);
+        assert!(movement.sprinting);
+
+        keys.release(settings.sprint_key);
+        movement.update_sprint(&settings, &keys, 0.2);
+        assert!(!movement.sprinting);
+    }
+
+    #[test]
+    fn test_double_tap_sprint_within_window() {
+        let settings = PlayerSettings {
+            sprint_mode: SprintMode::DoubleTap,
+            ..Default::default()
+        };
+        let mut movement = PlayerMovement::default();
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mut tap = |movement: &mut PlayerMovement, now: f32| {
+            keys.press(FORWARD_KEY);
+            movement.update_sprint(&settings, &keys, now);
+            let sprinting = movement.sprinting;
+            keys.clear();
+            keys.release(FORWARD_KEY);
+            movement.update_sprint(&settings, &keys, now);
+            keys.clear();
+            sprinting
+        };
+
+        assert!(!tap(&mut movement, 0.0));
+        assert!(tap(&mut movement, 0.2));
+        // too slow to count as a double tap
+        assert!(!tap(&mut movement, 1.0));
+        assert!(!tap(&mut movement, 1.5));
+    }
+
+    #[test]
+    fn test_no_smoothing_passes_raw_delta() {
+        let raw = Vec2::new(3.0, -2.0);
+        assert_eq!(
+            raw,
+            smooth_mouse_delta(Vec2::new(10.0, 10.0), raw, 0.0, SMOOTHING_FRAME_TIME)
         );
-        camera_transform.rotate_axis(
-            Vec3::new(1.0, 0.0, 0.0),
-            -ev.delta.y * player_look.sensitivity * time.delta_seconds(),
+    }
+
+    #[test]
+    fn test_smoothing_is_frame_rate_independent() {
+        let raw = Vec2::new(4.0, 0.0);
+        let once = smooth_mouse_delta(Vec2::ZERO, raw, 0.5, SMOOTHING_FRAME_TIME);
+        let half = smooth_mouse_delta(Vec2::ZERO, raw, 0.5, SMOOTHING_FRAME_TIME / 2.0);
+        let twice = smooth_mouse_delta(half, raw, 0.5, SMOOTHING_FRAME_TIME / 2.0);
+
+        assert_eq!(Vec2::new(2.0, 0.0), once);
+        assert!((once - twice).length() < 0.001);
+    }
+
+    #[test]
+    fn test_scrolling_scales_fly_speed_within_limits() {
+        let settings = PlayerSettings {
+            fly_speed_multiplier: 2.0,
+            ..PlayerSettings::default()
+        };
+        let mut movement = PlayerMovement::default();
+        let base = movement.fly_speed(&settings);
+        assert_eq!(2.0 * settings.fly_speed, base);
+
+        movement.scroll_fly_speed(1.0);
+        assert!(movement.fly_speed(&settings) > base);
+        movement.scroll_fly_speed(-2.0);
+        assert!(movement.fly_speed(&settings) < base);
+
+        movement.scroll_fly_speed(100.0);
+        assert_eq!(base * MAX_FLY_SPEED_SCALE, movement.fly_speed(&settings));
+    }
+
+    #[test]
+    fn test_collision_width_decides_fitting_through_gaps() {
+        let mut world = World::new();
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+        // a wall across x = 8 with a one block gap at z = 8
+        for y in 1..4 {
+            for z in 0..16 {
+                if z != 8 {
+                    world.set_block(I64Vec3::new(8, y, z), BlockType::Stone);
+                }
+            }
+        }
+
+        let mut fits = |collision_width| {
+            let settings = PlayerSettings {
+                collision_width,
+                ..PlayerSettings::default()
+            };
+            let start = Vec3::new(6.0, 1.0, 8.0);
+            let (_, blocked) = move_and_collide(
+                &mut world,
+                start,
+                settings.collision_size(),
+                Vec3::new(4.0, 0.0, 0.0),
+            );
+            !blocked.x
+        };
+        assert!(fits(0.6));
+        assert!(!fits(1.2));
+    }
+
+    #[test]
+    fn test_falling_out_of_world_returns_to_spawn() {
+        let mut app_world = bevy::ecs::world::World::new();
+        app_world.insert_resource(World::with_seed(1));
+        app_world.init_resource::<NextState<AppState>>();
+        let player = app_world
+            .spawn((
+                Transform::from_xyz(40.0, -100.0, 40.0),
+                PlayerMovement::default(),
+            ))
+            .id();
+
+        app_world.run_system_once(respawn_from_void);
+
+        let spawn = app_world.resource_mut::<World>().spawn_point();
+        let position = app_world.get::<Transform>(player).unwrap().translation;
+        assert_eq!(spawn, position);
+        assert!(app_world
+            .resource_mut::<World>()
+            .is_position_loaded(position));
+        assert_eq!(
+            Some(AppState::Loading),
+            app_world.resource::<NextState<AppState>>().0
         );
     }
 }
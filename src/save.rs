@@ -0,0 +1,331 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+    sync::RwLock,
+};
+
+use bevy::math::{I64Vec3, U16Vec3};
+
+use crate::{
+    block::BlockType,
+    chunks::chunk::{ChunkCoordinate, ChunkData},
+};
+
+pub const SAVE_DIR: &str = "saves/world";
+
+/// Marks a chunk file as starting with a format version. Files from before versioning
+/// have no header and are read as version 0.
+const CHUNK_MAGIC: [u8; 4] = *b"RCCK";
+/// Version of the chunk format written by this build. Bump it whenever block ids or the
+/// layout change, and teach [`saved_block`] how to read the old ids.
+pub const CHUNK_FORMAT_VERSION: u16 = 1;
+
+/// A world save on disk, holding the world seed and every chunk that has been edited.
+#[derive(Debug)]
+pub struct WorldSave {
+    dir: PathBuf,
+    /// Every chunk with a file in the save, so checking for one doesn't touch the disk.
+    saved_chunks: RwLock<HashSet<ChunkCoordinate>>,
+}
+
+impl WorldSave {
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        let chunks = dir.join("chunks");
+        fs::create_dir_all(&chunks)?;
+
+        let mut saved_chunks = HashSet::new();
+        for entry in fs::read_dir(chunks)? {
+            let name = entry?.file_name();
+            if let Some(coord) = name.to_str().and_then(chunk_coordinate) {
+                saved_chunks.insert(coord);
+            }
+        }
+        Ok(Self {
+            dir,
+            saved_chunks: RwLock::new(saved_chunks),
+        })
+    }
+
+    pub fn load_seed(&self) -> io::Result<Option<u32>> {
+        match fs::read_to_string(self.dir.join("seed")) {
+            Ok(seed) => seed
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save_seed(&self, seed: u32) -> io::Result<()> {
+        fs::write(self.dir.join("seed"), seed.to_string())
+    }
+
+    /// Deletes every saved chunk, e.g. when the world is replaced by a new one.
+    pub fn clear_chunks(&self) -> io::Result<()> {
+        let chunks = self.dir.join("chunks");
+        match fs::remove_dir_all(&chunks) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        if let Ok(mut saved_chunks) = self.saved_chunks.write() {
+            saved_chunks.clear();
+        }
+        fs::create_dir_all(chunks)
+    }
+
+    /// Whether a chunk has been saved, without reading the disk.
+    pub fn has_chunk(&self, coord: ChunkCoordinate) -> bool {
+        // if the index can't be read, loading the chunk will find out
+        self.saved_chunks
+            .read()
+            .map_or(true, |saved_chunks| saved_chunks.contains(&coord))
+    }
+
+    pub fn save_chunk(&self, coord: ChunkCoordinate, chunk_data: &ChunkData) -> io::Result<()> {
+        // write then rename so a crash mid-save can't leave a truncated chunk behind
+        let path = self.chunk_path(coord);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, encode_chunk(chunk_data))?;
+        fs::rename(tmp, path)?;
+        if let Ok(mut saved_chunks) = self.saved_chunks.write() {
+            saved_chunks.insert(coord);
+        }
+        Ok(())
+    }
+
+    /// Loads a saved chunk, or returns `None` if it has never been saved.
+    pub fn load_chunk(&self, coord: ChunkCoordinate) -> io::Result<Option<ChunkData>> {
+        match fs::read(self.chunk_path(coord)) {
+            Ok(bytes) => decode_chunk(&bytes).map(Some),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn chunk_path(&self, coord: ChunkCoordinate) -> PathBuf {
+        let c = coord.0;
+        self.dir
+            .join("chunks")
+            .join(format!("{}_{}_{}.chunk", c.x, c.y, c.z))
+    }
+}
+
+/// Parses a chunk's coordinate from its file name, as written by [`WorldSave::chunk_path`].
+fn chunk_coordinate(file_name: &str) -> Option<ChunkCoordinate> {
+    let mut parts = file_name.strip_suffix(".chunk")?.split('_');
+    let mut next = || parts.next()?.parse().ok();
+    let coord = I64Vec3::new(next()?, next()?, next()?);
+    Some(ChunkCoordinate(coord))
+}
+
+/// Encodes a chunk as a format header, its size, its column temperatures, then each of
+/// its blocks as a local position and block id. All values are little endian.
+fn encode_chunk(chunk_data: &ChunkData) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend(CHUNK_MAGIC);
+    bytes.extend(CHUNK_FORMAT_VERSION.to_le_bytes());
+    bytes.extend(chunk_data.size.to_le_bytes());
+
+    let temperatures = chunk_data.temperatures();
+    bytes.extend((temperatures.len() as u32).to_le_bytes());
+    for temperature in temperatures {
+        bytes.extend(temperature.to_le_bytes());
+    }
+
+    let blocks = chunk_data.blocks();
+    bytes.extend((blocks.len() as u32).to_le_bytes());
+    for (coord, block) in blocks.iter() {
+        for component in coord.to_array() {
+            bytes.extend(component.to_le_bytes());
+        }
+        bytes.push(block.id());
+    }
+    bytes
+}
+
+fn decode_chunk(bytes: &[u8]) -> io::Result<ChunkData> {
+    let (version, bytes) = match bytes.strip_prefix(&CHUNK_MAGIC) {
+        Some(rest) => {
+            let mut reader = Reader { bytes: rest };
+            (u16::from_le_bytes(reader.take()?), reader.bytes)
+        }
+        None => (0, bytes),
+    };
+    if version > CHUNK_FORMAT_VERSION {
+        return Err(invalid_data("chunk was saved by a newer version"));
+    }
+    let mut reader = Reader { bytes };
+
+    let mut chunk_data = ChunkData::default();
+    chunk_data.size = u16::from_le_bytes(reader.take()?);
+    if chunk_data.size == 0 {
+        return Err(invalid_data("chunk has no size"));
+    }
+
+    let temperature_count = u32::from_le_bytes(reader.take()?) as usize;
+    if temperature_count > 0
+        && temperature_count != chunk_data.size as usize * chunk_data.size as usize
+    {
+        return Err(invalid_data("temperature count does not match chunk size"));
+    }
+    for i in 0..temperature_count {
+        let (x, z) = (i % chunk_data.size as usize, i / chunk_data.size as usize);
+        let temperature = f32::from_le_bytes(reader.take()?);
+        chunk_data.set_temperature_at(x as u16, z as u16, temperature);
+    }
+
+    let block_count = u32::from_le_bytes(reader.take()?);
+    for _ in 0..block_count {
+        let coord = U16Vec3::new(
+            u16::from_le_bytes(reader.take()?),
+            u16::from_le_bytes(reader.take()?),
+            u16::from_le_bytes(reader.take()?),
+        );
+        let [id] = reader.take()?;
+        let block = saved_block(version, id).ok_or_else(|| invalid_data("unknown block id"))?;
+        if !chunk_data.set_block_at(coord, block) {
+            return Err(invalid_data("block outside chunk"));
+        }
+    }
+
+    chunk_data.dirty = false;
+    chunk_data.update_visibility();
+    Ok(chunk_data)
+}
+
+/// Maps a block id saved in an older chunk format to the block it now is. Versions 0 and 1
+/// share the current ids; when ids are reassigned, the old version gets its own table here.
+fn saved_block(version: u16, id: u8) -> Option<BlockType> {
+    match version {
+        0 | 1 => BlockType::from_id(id),
+        _ => None,
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        if self.bytes.len() < N {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+        let (value, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(value.try_into().unwrap())
+    }
+}
+
+/// A directory under the system temp directory for a test's save, removed when dropped so
+/// it's cleaned up even when the test fails.
+#[cfg(test)]
+pub struct TestSaveDir(PathBuf);
+
+#[cfg(test)]
+impl Default for TestSaveDir {
+    fn default() -> Self {
+        Self(std::env::temp_dir().join(format!("rustcraft-test-{}", rand::random::<u32>())))
+    }
+}
+
+#[cfg(test)]
+impl TestSaveDir {
+    pub fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestSaveDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{I64Vec3, U16Vec3};
+
+    use crate::{
+        block::BlockType,
+        chunks::chunk::{ChunkCoordinate, ChunkData},
+    };
+
+    use super::{
+        decode_chunk, encode_chunk, TestSaveDir, WorldSave, CHUNK_FORMAT_VERSION, CHUNK_MAGIC,
+    };
+
+    #[test]
+    fn test_chunk_round_trip() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 3), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(15, 0, 7), BlockType::Lava);
+        chunk_data.set_temperature_at(4, 5, 0.75);
+
+        let decoded = decode_chunk(&encode_chunk(&chunk_data)).unwrap();
+
+        assert_eq!(chunk_data.blocks(), decoded.blocks());
+        assert_eq!(0.75, decoded.temperature_at(4, 5));
+        assert!(!decoded.dirty);
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_chunk() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 3), BlockType::Stone);
+
+        let bytes = encode_chunk(&chunk_data);
+        assert!(decode_chunk(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_decode_reads_unversioned_chunk() {
+        let mut chunk_data = ChunkData::default();
+        chunk_data.set_block_at(U16Vec3::new(1, 2, 3), BlockType::Sand);
+
+        // chunks saved before versioning have no header
+        let bytes = encode_chunk(&chunk_data);
+        let decoded = decode_chunk(&bytes[CHUNK_MAGIC.len() + 2..]).unwrap();
+
+        assert_eq!(chunk_data.blocks(), decoded.blocks());
+    }
+
+    #[test]
+    fn test_decode_rejects_newer_version() {
+        let mut bytes = encode_chunk(&ChunkData::default());
+        let version = (CHUNK_FORMAT_VERSION + 1).to_le_bytes();
+        bytes[CHUNK_MAGIC.len()..CHUNK_MAGIC.len() + 2].copy_from_slice(&version);
+
+        assert!(decode_chunk(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_saved_chunks_are_indexed() {
+        let dir = TestSaveDir::default();
+        let coord = ChunkCoordinate(I64Vec3::new(-3, 2, 10));
+
+        let save = WorldSave::open(dir.path()).unwrap();
+        assert!(!save.has_chunk(coord));
+        save.save_chunk(coord, &ChunkData::default()).unwrap();
+        assert!(save.has_chunk(coord));
+
+        // files already in the save are found when it's opened again
+        let reopened = WorldSave::open(dir.path()).unwrap();
+        assert!(reopened.has_chunk(coord));
+        assert!(!reopened.has_chunk(ChunkCoordinate(I64Vec3::ZERO)));
+
+        reopened.clear_chunks().unwrap();
+        assert!(!reopened.has_chunk(coord));
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        query::With,
+        system::{Local, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::{error, info},
+    render::view::{screenshot::ScreenshotManager, Visibility},
+    window::PrimaryWindow,
+};
+
+use crate::compass::Compass;
+
+const SCREENSHOT_KEY: KeyCode = KeyCode::F2;
+const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Whether the current frame is being saved as a screenshot, so debug overlays can leave it
+/// out. The chunk gizmos skip drawing for the frame.
+#[derive(Resource, Default)]
+pub struct TakingScreenshot(pub bool);
+
+/// Saves the current frame to a timestamped PNG. Only runs while the console is closed,
+/// so the console never appears in screenshots. The compass and chunk gizmos are hidden
+/// for the captured frame and come back on the next one.
+pub fn take_screenshot(
+    keys: Res<ButtonInput<KeyCode>>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut taking_screenshot: ResMut<TakingScreenshot>,
+    mut compass_query: Query<&mut Visibility, With<Compass>>,
+    mut compass_visibility: Local<Option<Visibility>>,
+) {
+    if let Some(visibility) = compass_visibility.take() {
+        for mut compass in compass_query.iter_mut() {
+            *compass = visibility;
+        }
+    }
+    taking_screenshot.0 = false;
+
+    if !keys.just_pressed(SCREENSHOT_KEY) {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    if let Err(e) = fs::create_dir_all(SCREENSHOT_DIR) {
+        error!("failed to create screenshot directory: {}", e);
+        return;
+    }
+
+    let path = screenshot_path();
+    match screenshot_manager.save_screenshot_to_disk(window, &path) {
+        Ok(()) => {
+            info!("saved screenshot to {}", path.display());
+            taking_screenshot.0 = true;
+            for mut compass in compass_query.iter_mut() {
+                *compass_visibility = Some(*compass);
+                *compass = Visibility::Hidden;
+            }
+        }
+        Err(e) => error!("failed to take screenshot: {}", e),
+    }
+}
+
+fn screenshot_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    PathBuf::from(SCREENSHOT_DIR).join(format!("screenshot-{}.png", timestamp))
+}
@@ -1,18 +1,546 @@
-use bevy::ecs::component::Component;
+use bevy::{
+    ecs::component::Component,
+    input::keyboard::KeyCode,
+    log::warn,
+    math::Vec3,
+    render::{color::Color, view::Msaa},
+    window::PresentMode,
+};
 use serde::Deserialize;
 
+use crate::{
+    chunks::{
+        chunk::CHUNK_SIZE,
+        chunk_loader::ChunkLoadStrategy,
+        generate::{curve::HeightCurve, generator::BedrockFloor, preset::WorldPreset},
+        material::{ColorPalette, RenderStyle},
+    },
+    game_mode::GameMode,
+    player::SprintMode,
+};
+
+/// Distance in blocks from the top of the player's collision box down to their eyes.
+const EYE_OFFSET: f32 = 0.2;
+
 #[derive(Default, Deserialize, Clone, Copy, Component)]
+#[serde(default)]
 pub struct Settings {
     pub renderer: RendererSettings,
+    pub player: PlayerSettings,
+    pub world: WorldSettings,
+    pub terrain: TerrainSettings,
+    pub sky: SkySettings,
+    pub water: WaterSettings,
+    pub underwater: UnderwaterSettings,
+    pub particles: ParticleSettings,
+}
+
+impl Settings {
+    /// Checks for values the game can't run with, describing the first one found.
+    pub fn validate(&self) -> Result<(), String> {
+        let player = &self.player;
+        if player.collision_width <= 0.0 || player.collision_height <= 0.0 {
+            return Err(format!(
+                "player collision box must have a positive size, got {} by {}",
+                player.collision_width, player.collision_height
+            ));
+        }
+        let renderer = &self.renderer;
+        for (name, value) in [
+            ("max_generation_tasks", renderer.max_generation_tasks),
+            ("max_meshing_tasks", renderer.max_meshing_tasks),
+            ("max_upload_vertices", renderer.max_upload_vertices),
+        ] {
+            if value == 0 {
+                return Err(format!("renderer {} must be at least 1", name));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
 pub struct RendererSettings {
     pub render_distance: u32,
+    /// Most memory in megabytes that loaded chunk data may take up, where 0 is unlimited.
+    /// Past it the farthest chunks are unloaded, even within render distance.
+    pub max_chunk_memory: u32,
+    /// Generate mipmaps for the block atlas to reduce shimmering on distant terrain.
+    pub mipmaps: bool,
+    /// Anisotropic filtering level for the block atlas, where 1 disables it.
+    pub anisotropy: u16,
+    /// Skip meshing chunks that are enclosed by solid terrain and can't be seen.
+    pub occlusion_culling: bool,
+    /// Multisample anti-aliasing sample count: 1 (off), 2, 4 or 8.
+    pub msaa: u32,
+    /// Average normals across the faces meeting at each vertex for smooth rather than
+    /// faceted lighting.
+    pub smooth_normals: bool,
+    /// Distance in blocks at which terrain starts blending towards flat colours.
+    pub lod_fade_start: f32,
+    /// Distance in blocks at which terrain is drawn entirely in flat colours.
+    pub lod_fade_end: f32,
+    /// Most vertices in a single chunk mesh. Chunks with more are split into several meshes.
+    pub max_mesh_vertices: usize,
+    /// How chunks are drawn: "textured", "flat", "colored" or "wireframe". F7 cycles
+    /// between them.
+    pub render_style: RenderStyle,
+    /// Block colour palette: "default", or "deuteranopia", "protanopia" or "tritanopia" to
+    /// keep terrain types distinguishable with those colour vision deficiencies.
+    pub palette: ColorPalette,
+    /// Chunks further than render distance that loaded chunks are kept to before being
+    /// unloaded, so chunks at the edge don't load and unload over and over as the camera
+    /// moves back and forth across a chunk boundary.
+    pub unload_margin: u32,
+    /// Most chunk generation tasks in flight at once. New chunks wait until some finish.
+    pub max_generation_tasks: usize,
+    /// Most chunk meshing tasks in flight at once. Dirty chunks wait until some finish.
+    pub max_meshing_tasks: usize,
+    /// Threads set aside for meshing chunks, so meshing keeps up however many chunks are
+    /// waiting to generate. 0 meshes on the same threads as generation, where the two
+    /// task caps set the balance between them.
+    pub meshing_threads: usize,
+    /// Most chunk mesh vertices uploaded to the gpu in a frame, spreading bursts of newly
+    /// meshed chunks over several frames. At least one chunk is always uploaded.
+    pub max_upload_vertices: usize,
+    /// Add tangents to chunk meshes, needed by normal mapped materials.
+    pub tangents: bool,
+    /// Wait for the display to refresh before presenting each frame, preventing tearing
+    /// and limiting the frame rate to the refresh rate.
+    pub vsync: bool,
+    /// Most frames per second to render, where 0 is uncapped.
+    pub max_fps: u32,
+    /// Order chunks are generated in: "spiral", "forward_cone" or "nearest_first".
+    pub chunk_load_strategy: ChunkLoadStrategy,
+    /// Camera speed in blocks per second above which chunks are loaded towards where the
+    /// camera is heading rather than where it's looking, so fast flight doesn't spend
+    /// generation on chunks it has already passed. 0 disables this.
+    pub fast_camera_speed: f32,
+    /// Render distance while the camera is moving faster than `fast_camera_speed`. It
+    /// expands back to `render_distance` once the camera slows down.
+    pub fast_render_distance: u32,
+    /// Distance in blocks to the camera's near plane. Larger values reduce z-fighting on
+    /// distant terrain but clip blocks right in front of the camera.
+    pub near_plane: f32,
+    /// Draw plants as instances of a shared model so they sway in the wind, rather than
+    /// baking them into chunk meshes.
+    pub instanced_plants: bool,
 }
 
 impl Default for RendererSettings {
     fn default() -> Self {
-        Self { render_distance: 8 }
+        Self {
+            render_distance: 8,
+            max_chunk_memory: 1024,
+            mipmaps: true,
+            anisotropy: 1,
+            occlusion_culling: true,
+            msaa: 1,
+            smooth_normals: false,
+            lod_fade_start: 160.0,
+            lod_fade_end: 320.0,
+            max_mesh_vertices: 65536,
+            render_style: RenderStyle::Textured,
+            palette: ColorPalette::Default,
+            unload_margin: 1,
+            max_generation_tasks: 512,
+            max_meshing_tasks: 256,
+            meshing_threads: 2,
+            max_upload_vertices: 262144,
+            tangents: false,
+            vsync: false,
+            max_fps: 0,
+            chunk_load_strategy: ChunkLoadStrategy::ForwardCone,
+            fast_camera_speed: 24.0,
+            fast_render_distance: 4,
+            near_plane: 0.1,
+            instanced_plants: true,
+        }
+    }
+}
+
+impl RendererSettings {
+    pub fn msaa(&self) -> Msaa {
+        match self.msaa {
+            1 => Msaa::Off,
+            2 => Msaa::Sample2,
+            4 => Msaa::Sample4,
+            8 => Msaa::Sample8,
+            samples => {
+                warn!("unsupported msaa sample count {}, disabling msaa", samples);
+                Msaa::Off
+            }
+        }
+    }
+
+    /// Distance in blocks from the camera to the furthest point of any chunk within
+    /// render distance.
+    pub fn view_distance(&self) -> f32 {
+        ((self.render_distance + 1) * CHUNK_SIZE as u32) as f32 * 3f32.sqrt()
+    }
+
+    /// Radius of the sky dome, just beyond the furthest chunks.
+    pub fn sky_radius(&self) -> f32 {
+        self.view_distance() + CHUNK_SIZE as f32
+    }
+
+    /// Distance in blocks to the camera's far plane, which takes in the sky dome and
+    /// everything within it.
+    pub fn far_plane(&self) -> f32 {
+        self.sky_radius() * 1.1
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct PlayerSettings {
+    /// Maximum distance in blocks at which blocks can be targeted, broken and placed.
+    pub reach_distance: f32,
+    /// Vertical acceleration applied to the player while not flying, in blocks per second squared.
+    pub gravity: f32,
+    /// Fastest speed the player can fall at, in blocks per second.
+    pub terminal_velocity: f32,
+    /// Flip vertical mouse look, so moving the mouse up looks down.
+    pub invert_y: bool,
+    /// Seconds between repeated breaks or placements while a mouse button is held.
+    pub interaction_cooldown: f32,
+    /// How much mouse look is smoothed, from 0 (raw input) up to but not including 1.
+    pub mouse_smoothing: f32,
+    /// How fast the camera turns for mouse movement, for both the player and the free
+    /// camera.
+    pub mouse_sensitivity: f32,
+    /// How sprinting is started: "hold" the sprint key, or "double_tap" forward.
+    pub sprint_mode: SprintMode,
+    /// Key held to sprint in the "hold" sprint mode, e.g. "ControlLeft".
+    pub sprint_key: KeyCode,
+    /// Longest time in seconds between the two presses of a double tap.
+    pub double_tap_window: f32,
+    /// Multiplier on movement speed while sprinting.
+    pub sprint_multiplier: f32,
+    /// Flying speed in blocks per second, for both the player and the free camera.
+    pub fly_speed: f32,
+    /// Multiplier on flying speed, separate from walking speed. The scroll wheel scales it
+    /// up or down while flying.
+    pub fly_speed_multiplier: f32,
+    /// Mode the game starts in: "survival", which can't fly, or "creative" for flying,
+    /// instant breaking and placing any block. F1 switches between them while playing.
+    pub game_mode: GameMode,
+    /// Width in blocks of the player's collision box, along both x and z. Under 1 fits
+    /// through one block gaps.
+    pub collision_width: f32,
+    /// Height in blocks of the player's collision box. Under 2 fits through two block high
+    /// gaps.
+    pub collision_height: f32,
+    /// Tallest ledge in blocks the player walks straight up onto without jumping, where 0
+    /// turns stepping off.
+    pub step_height: f32,
+}
+
+impl PlayerSettings {
+    /// Size of the player's collision box, whose bottom centre is the player's position.
+    pub fn collision_size(&self) -> Vec3 {
+        Vec3::new(
+            self.collision_width,
+            self.collision_height,
+            self.collision_width,
+        )
+    }
+
+    /// Height of the player's eyes, and so the camera, above the player's position. Just
+    /// under the top of the collision box, or halfway up a box too short for that.
+    pub fn eye_height(&self) -> f32 {
+        (self.collision_height - EYE_OFFSET).max(self.collision_height / 2.0)
+    }
+
+    /// Sign applied to vertical mouse movement when pitching the camera.
+    pub fn pitch_sign(&self) -> f32 {
+        if self.invert_y {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            reach_distance: 5.0,
+            gravity: -9.8,
+            terminal_velocity: 50.0,
+            invert_y: false,
+            interaction_cooldown: 0.25,
+            mouse_smoothing: 0.2,
+            mouse_sensitivity: 0.1,
+            sprint_mode: SprintMode::Hold,
+            sprint_key: KeyCode::ControlLeft,
+            double_tap_window: 0.3,
+            sprint_multiplier: 1.6,
+            fly_speed: 20.0,
+            fly_speed_multiplier: 1.0,
+            game_mode: GameMode::Survival,
+            collision_width: 0.6,
+            collision_height: 1.8,
+            step_height: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct WorldSettings {
+    /// Distance in blocks from the origin along x and z past which the player can't move
+    /// and no chunks are generated. Much larger values lose precision in positions.
+    pub border: i64,
+    /// Keep generating and meshing chunks while the game is paused.
+    pub load_chunks_while_paused: bool,
+    /// Radius in chunks around the spawn that is generated before play starts, and that
+    /// the loading screen waits on.
+    pub spawn_radius: u32,
+    /// Height in blocks below which a falling player is returned to the spawn point.
+    pub void_height: f32,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        Self {
+            border: 100_000,
+            load_chunks_while_paused: true,
+            spawn_radius: 2,
+            void_height: -64.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct TerrainSettings {
+    /// Style of world to generate: "default", "amplified", "flat" or "islands". The
+    /// preset shapes the terrain and adjusts the heights below.
+    pub preset: WorldPreset,
+    /// Multiplier on terrain height. Lower values give gentler terrain, higher values
+    /// give dramatic mountains.
+    pub height_scale: f64,
+    /// Control points of `[noise, height]` mapping terrain noise, mostly between 0 and 1, to
+    /// a fraction of the world height. The default straight line gives rolling hills, while
+    /// a shallow start and steep end give flat plains and sharper mountains.
+    pub height_curve: HeightCurve,
+    /// Floor of bedrock at the bottom of the world: "noisy" for an uneven floor up to four
+    /// blocks thick, "flat" for a single layer, or "none" for no floor.
+    pub bedrock: BedrockFloor,
+    /// Height from which gently sloped terrain is covered in snow.
+    pub snow_height: i64,
+    /// Height from which steep terrain is bare stone.
+    pub stone_height: i64,
+    /// Height from which terrain is grass rather than sand, other than on beaches.
+    pub grass_height: i64,
+    /// Height above sea level up to which shorelines are covered in sand.
+    pub beach_height: i64,
+    /// Distance in blocks from the water's edge that beaches reach inland, where 0
+    /// disables beaches.
+    pub beach_width: i64,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        Self {
+            preset: WorldPreset::Default,
+            height_scale: 1.0,
+            height_curve: HeightCurve::default(),
+            bedrock: BedrockFloor::Noisy,
+            snow_height: 90,
+            stone_height: 70,
+            grass_height: 36,
+            beach_height: 6,
+            beach_width: 4,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct SkySettings {
+    /// Colour of the sky at the horizon, as 8-bit rgb.
+    pub horizon_color: [u8; 3],
+    /// Colour of the sky straight overhead, as 8-bit rgb.
+    pub zenith_color: [u8; 3],
+}
+
+impl SkySettings {
+    pub fn clear_color(&self) -> Color {
+        let [r, g, b] = self.horizon_color;
+        Color::rgb_u8(r, g, b)
+    }
+}
+
+impl Default for SkySettings {
+    fn default() -> Self {
+        Self {
+            horizon_color: [135, 206, 235],
+            zenith_color: [50, 110, 200],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct WaterSettings {
+    /// Tint multiplied with the water texture, as 8-bit rgb.
+    pub color: [u8; 3],
+    /// Opacity of water, from 0 (invisible) to 1, multiplied with the texture's alpha.
+    pub opacity: f32,
+}
+
+impl WaterSettings {
+    pub fn material_color(&self) -> Color {
+        let [r, g, b] = self.color;
+        Color::rgb_u8(r, g, b).with_a(self.opacity.clamp(0.0, 1.0))
+    }
+}
+
+impl Default for WaterSettings {
+    fn default() -> Self {
+        Self {
+            color: [255, 255, 255],
+            opacity: 0.8,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct UnderwaterSettings {
+    /// Colour of the fog and tint while the camera is underwater, as 8-bit rgb.
+    pub fog_color: [u8; 3],
+    /// How quickly the view fades into the fog colour with distance.
+    pub fog_density: f32,
+    /// Opacity of the fullscreen tint, from 0 (none) to 1.
+    pub overlay_opacity: f32,
+}
+
+impl Default for UnderwaterSettings {
+    fn default() -> Self {
+        Self {
+            fog_color: [20, 60, 120],
+            fog_density: 0.15,
+            overlay_opacity: 0.3,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ParticleSettings {
+    /// Number of particles thrown out when a block breaks.
+    pub count: u32,
+    /// Longest time a particle lasts, in seconds.
+    pub lifetime: f32,
+    /// Width of each particle in blocks.
+    pub size: f32,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            count: 12,
+            lifetime: 1.0,
+            size: 0.12,
+        }
+    }
+}
+
+/// Sets the keys in `profile` over those in `base`, merging tables key by key so a profile
+/// only needs the settings it changes.
+pub fn overlay_settings(base: &mut toml::Table, profile: toml::Table) {
+    for (key, value) in profile {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(profile_table)) => {
+                overlay_settings(base_table, profile_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{overlay_settings, PlayerSettings, RendererSettings, Settings};
+
+    #[test]
+    fn test_collision_box_must_have_positive_size() {
+        assert!(Settings::default().validate().is_ok());
+
+        let flat = Settings {
+            player: PlayerSettings {
+                collision_height: 0.0,
+                ..PlayerSettings::default()
+            },
+            ..Settings::default()
+        };
+        assert!(flat.validate().is_err());
+        let thin = Settings {
+            player: PlayerSettings {
+                collision_width: -0.5,
+                ..PlayerSettings::default()
+            },
+            ..Settings::default()
+        };
+        assert!(thin.validate().is_err());
+    }
+
+    #[test]
+    fn test_task_and_upload_caps_must_be_positive() {
+        let no_uploads = Settings {
+            renderer: RendererSettings {
+                max_upload_vertices: 0,
+                ..RendererSettings::default()
+            },
+            ..Settings::default()
+        };
+        assert!(no_uploads.validate().is_err());
+        let no_meshing = Settings {
+            renderer: RendererSettings {
+                max_meshing_tasks: 0,
+                ..RendererSettings::default()
+            },
+            ..Settings::default()
+        };
+        assert!(no_meshing.validate().is_err());
+    }
+
+    #[test]
+    fn test_eyes_follow_collision_height() {
+        let player = PlayerSettings {
+            collision_height: 0.9,
+            ..PlayerSettings::default()
+        };
+        assert!((player.eye_height() - 0.7).abs() < 1e-6);
+        assert!((PlayerSettings::default().eye_height() - 1.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_profile_overlays_only_its_keys() {
+        let mut base: toml::Table = toml::from_str(
+            "[renderer]\nrender_distance = 16\nvsync = true\n[player]\ninvert_y = true\n",
+        )
+        .unwrap();
+        let profile = toml::from_str("[renderer]\nrender_distance = 8\n").unwrap();
+        overlay_settings(&mut base, profile);
+
+        let settings: Settings = toml::Value::Table(base).try_into().unwrap();
+        assert_eq!(8, settings.renderer.render_distance);
+        assert!(settings.renderer.vsync);
+        assert!(settings.player.invert_y);
     }
 }
@@ -0,0 +1,90 @@
+use bevy::{
+    asset::Assets,
+    ecs::{
+        component::Component,
+        query::{With, Without},
+        system::{Commands, Query, ResMut},
+    },
+    math::primitives::Sphere,
+    pbr::{PbrBundle, StandardMaterial},
+    render::{
+        camera::Camera,
+        color::Color,
+        mesh::{Mesh, Meshable, VertexAttributeValues},
+    },
+    transform::components::{GlobalTransform, Transform},
+    utils::default,
+};
+
+use crate::settings::{Settings, SkySettings};
+
+/// Marks the sky dome, which follows the camera so it always surrounds the view.
+#[derive(Component)]
+pub struct Sky;
+
+pub fn setup_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings_query: Query<&Settings>,
+) {
+    let settings = settings_query.get_single().copied().unwrap_or_default();
+    // the dome must stay within the camera's far plane but outside the furthest chunks
+    let radius = settings.renderer.sky_radius();
+
+    let mut mesh = Sphere::new(radius).mesh().uv(32, 16);
+    let colors: Vec<[f32; 4]> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions
+            .iter()
+            .map(|p| sky_color(&settings.sky, p[1] / radius))
+            .collect(),
+        _ => vec![],
+    };
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                // the dome is seen from the inside
+                cull_mode: None,
+                ..default()
+            }),
+            ..default()
+        },
+        Sky,
+    ));
+}
+
+pub fn follow_camera_sky(
+    camera_query: Query<&GlobalTransform, With<Camera>>,
+    mut sky_query: Query<&mut Transform, (With<Sky>, Without<Camera>)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    for mut transform in sky_query.iter_mut() {
+        transform.translation = camera.translation();
+    }
+}
+
+/// Blends from the horizon colour to the zenith colour by `height`, the sine of the angle
+/// above the horizon. Everything below the horizon is the horizon colour.
+fn sky_color(sky: &SkySettings, height: f32) -> [f32; 4] {
+    let horizon = Color::rgb_u8(
+        sky.horizon_color[0],
+        sky.horizon_color[1],
+        sky.horizon_color[2],
+    );
+    let zenith = Color::rgb_u8(
+        sky.zenith_color[0],
+        sky.zenith_color[1],
+        sky.zenith_color[2],
+    );
+    let t = height.clamp(0.0, 1.0).sqrt();
+
+    let (horizon, zenith) = (horizon.as_linear_rgba_f32(), zenith.as_linear_rgba_f32());
+    [0, 1, 2, 3].map(|i| horizon[i] + (zenith[i] - horizon[i]) * t)
+}
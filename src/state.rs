@@ -0,0 +1,11 @@
+use bevy::ecs::schedule::States;
+
+#[derive(States, Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AppState {
+    /// Waiting for the chunks around spawn to generate and mesh.
+    #[default]
+    Loading,
+    Playing,
+    /// The pause menu is open and the player can't move or interact.
+    Paused,
+}
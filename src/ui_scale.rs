@@ -0,0 +1,54 @@
+use bevy::{
+    ecs::{
+        event::EventReader,
+        query::With,
+        system::{Query, ResMut},
+    },
+    ui::UiScale,
+    window::{PrimaryWindow, WindowResized},
+};
+
+/// Window height in logical pixels at which the HUD is drawn at its natural size.
+const REFERENCE_HEIGHT: f32 = 720.0;
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 2.0;
+
+/// Scale the HUD is drawn at in a window `height` logical pixels tall, so it takes up the
+/// same share of the screen at any size. `None` for a minimised window, which has no height
+/// to fit and should keep the scale it had.
+fn ui_scale_for(height: f32) -> Option<f32> {
+    (height > 0.0).then(|| (height / REFERENCE_HEIGHT).clamp(MIN_UI_SCALE, MAX_UI_SCALE))
+}
+
+/// Rescales the HUD whenever the primary window is resized. UI nodes lay themselves out
+/// again at the new size on their own, and the camera's perspective projection picks up the
+/// new aspect ratio, so only the scale needs refreshing.
+pub fn scale_ui_to_window(
+    mut resized: EventReader<WindowResized>,
+    window_query: Query<(), With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Some(event) = resized
+        .read()
+        .filter(|event| window_query.contains(event.window))
+        .last()
+    else {
+        return;
+    };
+    if let Some(scale) = ui_scale_for(event.height) {
+        ui_scale.0 = scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ui_scale_for, MAX_UI_SCALE, REFERENCE_HEIGHT};
+
+    #[test]
+    fn test_ui_scale_follows_window_height() {
+        assert_eq!(Some(1.0), ui_scale_for(REFERENCE_HEIGHT));
+        assert_eq!(Some(1.5), ui_scale_for(REFERENCE_HEIGHT * 1.5));
+        assert_eq!(Some(MAX_UI_SCALE), ui_scale_for(REFERENCE_HEIGHT * 10.0));
+        assert_eq!(None, ui_scale_for(0.0));
+    }
+}
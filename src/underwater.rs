@@ -0,0 +1,81 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        system::{Commands, Query, ResMut},
+    },
+    pbr::{FogFalloff, FogSettings},
+    render::{camera::Camera, color::Color, view::Visibility},
+    transform::components::GlobalTransform,
+    ui::{node_bundles::NodeBundle, BackgroundColor, PositionType, Style, Val},
+    utils::default,
+};
+
+use crate::{block::BlockType, settings::Settings, world::World};
+
+/// Fullscreen tint shown while the camera is underwater.
+#[derive(Component)]
+pub struct UnderwaterOverlay;
+
+pub fn setup_underwater_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        UnderwaterOverlay,
+    ));
+}
+
+/// Fogs and tints the view while the camera is inside a water block.
+pub fn update_underwater(
+    mut commands: Commands,
+    mut world: ResMut<World>,
+    camera_query: Query<(Entity, &GlobalTransform, Option<&FogSettings>), With<Camera>>,
+    mut overlay_query: Query<(&mut Visibility, &mut BackgroundColor), With<UnderwaterOverlay>>,
+    settings_query: Query<&Settings>,
+) {
+    let Ok((camera, transform, fog)) = camera_query.get_single() else {
+        return;
+    };
+
+    let underwater = world.block_at(transform.translation()) == Some(BlockType::Water);
+    if underwater == fog.is_some() {
+        return;
+    }
+
+    let settings = settings_query
+        .get_single()
+        .copied()
+        .unwrap_or_default()
+        .underwater;
+    let [r, g, b] = settings.fog_color;
+    let color = Color::rgb_u8(r, g, b);
+    if underwater {
+        commands.entity(camera).insert(FogSettings {
+            color,
+            falloff: FogFalloff::Exponential {
+                density: settings.fog_density,
+            },
+            ..default()
+        });
+    } else {
+        commands.entity(camera).remove::<FogSettings>();
+    }
+
+    for (mut visibility, mut background) in overlay_query.iter_mut() {
+        *visibility = if underwater {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        *background = BackgroundColor(color.with_a(settings.overlay_opacity));
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::{
+    core_pipeline::core_3d::Camera3d,
+    ecs::{component::Component, query::With, system::Query},
+    render::primitives::{Aabb, Frustum},
+    transform::components::GlobalTransform,
+};
+
+/// Whether an entity was inside the camera's view last frame. Systems doing purely visual
+/// work, like animation, can skip entities that are off screen.
+#[derive(Component)]
+pub struct OnScreen(pub bool);
+
+impl Default for OnScreen {
+    // entities count as on screen until their bounds are known
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Whether a box in an entity's local space is at least partly inside `frustum`.
+pub fn in_frustum(frustum: &Frustum, aabb: &Aabb, transform: &GlobalTransform) -> bool {
+    frustum.intersects_obb(aabb, &transform.affine(), true, true)
+}
+
+pub fn update_on_screen(
+    camera_query: Query<&Frustum, With<Camera3d>>,
+    mut entities_query: Query<(&Aabb, &GlobalTransform, &mut OnScreen)>,
+) {
+    let Ok(frustum) = camera_query.get_single() else {
+        return;
+    };
+
+    for (aabb, transform, mut on_screen) in entities_query.iter_mut() {
+        on_screen.0 = in_frustum(frustum, aabb, transform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        math::{Mat4, Vec3},
+        render::primitives::{Aabb, Frustum},
+        transform::components::{GlobalTransform, Transform},
+    };
+
+    use super::in_frustum;
+
+    #[test]
+    fn test_in_frustum() {
+        // looking down -z from the origin
+        let projection = Mat4::perspective_rh(1.0, 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_view_projection(&projection);
+        let aabb = Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5));
+        let at = |x, y, z| GlobalTransform::from_xyz(x, y, z);
+
+        assert!(in_frustum(&frustum, &aabb, &at(0.0, 0.0, -10.0)));
+        assert!(!in_frustum(&frustum, &aabb, &at(0.0, 0.0, 10.0)));
+        assert!(!in_frustum(&frustum, &aabb, &at(50.0, 0.0, -10.0)));
+        assert!(!in_frustum(&frustum, &aabb, &at(0.0, 0.0, -200.0)));
+    }
+
+    #[test]
+    fn test_in_frustum_of_moved_camera() {
+        // a camera away from the origin looking down +z, built the way bevy builds the
+        // frustum of a camera from its transform
+        let camera = Transform::from_xyz(5.0, 20.0, -5.0).looking_to(Vec3::Z, Vec3::Y);
+        let projection = Mat4::perspective_rh(1.0, 16.0 / 9.0, 0.1, 100.0);
+        let view_projection = projection * camera.compute_matrix().inverse();
+        let frustum = Frustum::from_view_projection_custom_far(
+            &view_projection,
+            &camera.translation,
+            &camera.back(),
+            100.0,
+        );
+        let aabb = Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5));
+        let at = |x, y, z| GlobalTransform::from_xyz(x, y, z);
+
+        // in front, including just inside the far plane and at the edge of the view
+        assert!(in_frustum(&frustum, &aabb, &at(5.0, 20.0, 5.0)));
+        assert!(in_frustum(&frustum, &aabb, &at(5.0, 20.0, 94.0)));
+        assert!(in_frustum(&frustum, &aabb, &at(5.0, 29.0, 15.0)));
+        // behind the camera
+        assert!(!in_frustum(&frustum, &aabb, &at(5.0, 20.0, -15.0)));
+        // far beyond the far plane
+        assert!(!in_frustum(&frustum, &aabb, &at(5.0, 20.0, 500.0)));
+        // off to the side
+        assert!(!in_frustum(&frustum, &aabb, &at(60.0, 20.0, 15.0)));
+    }
+}
@@ -1,2 +1,3 @@
+pub mod frustum;
 pub mod octree;
 pub mod primitives;
@@ -112,7 +112,7 @@ impl<Data> Octree<Data> {
                 closest_child = child;
             }
         }
-        return closest_child;
+        closest_child
     }
 
     pub fn subdivide(&mut self, octant: usize) {
@@ -166,7 +166,7 @@ impl<Data> Octree<Data> {
             i += 1;
         }
 
-        return self.get_node(current_id);
+        self.get_node(current_id)
     }
 
     pub fn get_node_by_id(&self, id: usize) -> Arc<RwLock<OctreeNode<Data>>> {
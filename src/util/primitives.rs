@@ -135,3 +135,44 @@ pub fn cube() -> Vec<Vertex> {
         },
     ]
 }
+
+/// The bottom half of a cube. Side faces sample the matching half of the texture.
+pub fn slab() -> Vec<Vertex> {
+    cube()
+        .into_iter()
+        .map(|mut v| {
+            if v.position[1] > 0.0 {
+                v.position[1] = 0.0;
+                if v.normal[1] == 0.0 {
+                    v.uv[1] = 0.5;
+                }
+            }
+            v
+        })
+        .collect()
+}
+
+/// Two quads crossing diagonally through the block, each emitted with both windings so
+/// they are visible from either side.
+pub fn cross() -> Vec<Vertex> {
+    let diagonals = [([-0.5, -0.5], [0.5, 0.5]), ([-0.5, 0.5], [0.5, -0.5])];
+
+    let mut vertices = vec![];
+    for ([x0, z0], [x1, z1]) in diagonals {
+        let quad = [
+            ([x0, 0.5, z0], [0.0, 0.0]),
+            ([x0, -0.5, z0], [0.0, 1.0]),
+            ([x1, 0.5, z1], [1.0, 0.0]),
+            ([x1, -0.5, z1], [1.0, 1.0]),
+        ];
+        let mirrored = [quad[2], quad[3], quad[0], quad[1]];
+        for (position, uv) in quad.into_iter().chain(mirrored) {
+            vertices.push(Vertex {
+                position,
+                normal: [0.0, 1.0, 0.0],
+                uv,
+            });
+        }
+    }
+    vertices
+}
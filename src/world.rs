@@ -1,33 +1,119 @@
 use std::{
-    fmt::Debug,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::{Debug, Display},
+    io,
     sync::{Arc, RwLock},
 };
 
 use bevy::{
     ecs::system::Resource,
-    math::{I64Vec3, Vec3},
+    log::warn,
+    math::{I64Vec2, I64Vec3, U16Vec3, Vec3},
 };
 
-use crate::chunks::generate::noise::NoiseGenerator;
+use crate::{
+    block::{BlockShape, BlockType},
+    chunks::generate::{
+        generator::{column_height, generate_chunk, SEA_LEVEL},
+        noise::NoiseGenerator,
+    },
+    save::WorldSave,
+    settings::TerrainSettings,
+};
 
 use super::chunks::chunk::{ChunkCoordinate, ChunkData, ChunkOctree};
 
+/// Distance in blocks between the columns checked when searching for a spawn point.
+const SPAWN_SEARCH_STEP: i64 = 16;
+/// Number of rings of columns checked around the origin before giving up on finding land.
+const SPAWN_SEARCH_RINGS: i64 = 64;
+/// The dimension players start in, whose terrain is seeded by the world seed alone.
+pub const OVERWORLD: u32 = 0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastHit {
+    pub block: I64Vec3,
+    /// Normal of the face that was hit, pointing back towards the ray origin.
+    pub normal: I64Vec3,
+    pub block_type: BlockType,
+}
+
+#[derive(Debug)]
+pub enum WorldError {
+    /// A thread panicked while holding the noise generator lock.
+    NoiseGeneratorPoisoned,
+    /// A chunk's data was needed but has not been generated.
+    ChunkNotGenerated(ChunkCoordinate),
+    /// A chunk task panicked.
+    TaskPanicked(String),
+    /// Reading or writing the world save failed.
+    Io(io::Error),
+}
+
+impl Display for WorldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoiseGeneratorPoisoned => write!(f, "noise generator lock is poisoned"),
+            Self::ChunkNotGenerated(coord) => write!(f, "chunk {:?} is not generated", coord.0),
+            Self::TaskPanicked(message) => write!(f, "task panicked: {}", message),
+            Self::Io(e) => write!(f, "save failed: {}", e),
+        }
+    }
+}
+
+impl Error for WorldError {}
+
 #[derive(Resource)]
 pub struct World {
     seed: u32,
+    /// Which dimension of the world this is, offsetting the noise seed so each dimension
+    /// has its own terrain.
+    dimension: u32,
     pub height: u64,
+    /// Distance in blocks from the origin along x and z that the world extends to.
+    pub border: i64,
+    pub terrain: TerrainSettings,
     chunks: ChunkOctree,
     pub noise_generator: Arc<RwLock<NoiseGenerator>>,
+    /// Where edited chunks are saved, if anywhere.
+    pub save: Option<Arc<WorldSave>>,
+    /// Chunks with edits that haven't been saved yet.
+    edited_chunks: HashSet<ChunkCoordinate>,
+    /// Chunks loaded outside the chunk loader, like those searched for a spawn point, which
+    /// the loader unloads once they're out of range unless it has taken them over.
+    untracked_chunks: HashSet<ChunkCoordinate>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
-        let seed = rand::random();
+        Self::with_seed(rand::random())
+    }
+
+    pub fn with_seed(seed: u32) -> Self {
+        Self::new_with_seed(seed, OVERWORLD)
+    }
+
+    /// A world in the given dimension. Dimensions sharing a seed generate different terrain,
+    /// with the noise seeded by `seed ^ dimension`.
+    pub fn new_with_seed(seed: u32, dimension: u32) -> Self {
         Self {
             seed,
+            dimension,
             height: 256,
+            border: 100_000,
+            terrain: TerrainSettings::default(),
             chunks: ChunkOctree::default(),
-            noise_generator: Arc::new(RwLock::new(NoiseGenerator::new(seed))),
+            noise_generator: Arc::new(RwLock::new(NoiseGenerator::new(seed ^ dimension))),
+            save: None,
+            edited_chunks: HashSet::new(),
+            untracked_chunks: HashSet::new(),
         }
     }
 
@@ -35,6 +121,253 @@ impl World {
         self.seed
     }
 
+    pub fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    /// Seed of the terrain noise, the world seed offset by the dimension.
+    fn noise_seed(&self) -> u32 {
+        self.seed ^ self.dimension
+    }
+
+    /// Sets how terrain is generated, applying the chosen preset's noise and thresholds.
+    /// Only chunks generated afterwards are affected.
+    pub fn set_terrain(&mut self, terrain: TerrainSettings) {
+        self.terrain = terrain.preset.terrain(terrain);
+        self.noise_generator = Arc::new(RwLock::new(NoiseGenerator::with_params(
+            self.noise_seed(),
+            terrain.preset.noise_params(),
+        )));
+    }
+
+    /// Where the player starts in the world: on the nearest land above sea level to the
+    /// origin, found by spiralling outwards a column at a time. Falls back to the sea
+    /// surface at the origin if there is no land nearby.
+    pub fn spawn_point(&mut self) -> Vec3 {
+        let border = self.border;
+        let columns = spiral(SPAWN_SEARCH_RINGS)
+            .map(|column| column * SPAWN_SEARCH_STEP)
+            .filter(|column| column.x.abs() <= border && column.y.abs() <= border);
+        for column in columns {
+            // the terrain noise rules out most columns without generating any chunks
+            let height = match self.noise_generator.write() {
+                Ok(mut noise) => column_height(&mut noise, column, self.height, self.terrain),
+                Err(_) => break,
+            };
+            if height as i64 <= SEA_LEVEL {
+                continue;
+            }
+
+            if let Some(surface) = self
+                .surface_height(column.x, column.y)
+                .filter(|surface| *surface >= SEA_LEVEL)
+            {
+                // stand on top of the surface block
+                return Vec3::new(column.x as f32, (surface + 1) as f32, column.y as f32);
+            }
+        }
+        Vec3::new(0.0, SEA_LEVEL as f32, 0.0)
+    }
+
+    /// Generates or loads every chunk within `radius` chunks of `position` straight away,
+    /// so the ground around the spawn is solid before the player gains control. Returns
+    /// how many chunks were newly generated or loaded.
+    pub fn pregenerate(&mut self, position: Vec3, radius: u32) -> Result<usize, WorldError> {
+        let center = self.block_to_chunk_coordinate(position.round().as_i64vec3());
+        let radius = radius as i64;
+        let mut generated = 0;
+        for x in -radius..=radius {
+            for y in -radius..=radius {
+                for z in -radius..=radius {
+                    let coord = ChunkCoordinate(center.0 + I64Vec3::new(x, y, z));
+                    if !self.is_chunk_within_border(coord) || self.is_chunk_generated(coord) {
+                        continue;
+                    }
+                    self.load_untracked_chunk(coord)?;
+                    generated += 1;
+                }
+            }
+        }
+        Ok(generated)
+    }
+
+    /// Loads or generates a chunk straight away, with its neighbours so it can be meshed
+    /// straight away too, for gameplay that can't wait for the chunk loader.
+    pub fn force_load_chunk(
+        &mut self,
+        chunk_coord: ChunkCoordinate,
+    ) -> Result<Arc<ChunkData>, WorldError> {
+        for neighbour in chunk_coord.adjacent() {
+            if self.is_chunk_within_border(neighbour) {
+                self.load_untracked_chunk(neighbour)?;
+            }
+        }
+        self.load_untracked_chunk(chunk_coord)
+    }
+
+    /// Highest y terrain can reach: the top of the height curve at the scaled world
+    /// height, plus one for plants.
+    fn terrain_top(&self) -> i64 {
+        let max_height = self.terrain.height_curve.max_height();
+        (max_height * self.height as f64 * self.terrain.height_scale).ceil() as i64 + 1
+    }
+
+    /// Returns the y of the highest solid block in the column at `x`, `z`, generating or
+    /// loading any of its chunks that aren't loaded yet. Returns `None` if the column has
+    /// no solid blocks or its chunks fail to generate.
+    pub fn surface_height(&mut self, x: i64, z: i64) -> Option<i64> {
+        let size = self.chunks.chunk_size as i64;
+        let top_chunk = self.block_to_chunk_coordinate(I64Vec3::new(x, self.terrain_top(), z));
+        let bottom_chunk =
+            self.block_to_chunk_coordinate(I64Vec3::new(x, -(self.height as i64), z));
+
+        for chunk_y in (bottom_chunk.0.y..=top_chunk.0.y).rev() {
+            let chunk_coord = ChunkCoordinate(I64Vec3::new(top_chunk.0.x, chunk_y, top_chunk.0.z));
+            let chunk_data = match self.load_untracked_chunk(chunk_coord) {
+                Ok(chunk_data) => chunk_data,
+                Err(e) => {
+                    warn!("failed to generate chunk {:?}: {}", chunk_coord.0, e);
+                    return None;
+                }
+            };
+
+            let local = self.block_to_local_coordinate(I64Vec3::new(x, chunk_y * size, z));
+            let surface = (0..chunk_data.size).rev().find(|y| {
+                chunk_data
+                    .get_block_at(U16Vec3::new(local.x, *y, local.z))
+                    .is_some_and(|block| block.is_solid())
+            });
+            if let Some(y) = surface {
+                return Some(chunk_y * size + y as i64);
+            }
+        }
+        None
+    }
+
+    /// The most common surface block in the column of chunks containing `chunk_coord`,
+    /// taking the highest block in each column of blocks that isn't air or a plant. Only
+    /// loaded chunks are read, so this is cheap enough to colour a map by. Returns air if
+    /// none of the column is loaded.
+    pub fn chunk_surface_sample(&mut self, chunk_coord: ChunkCoordinate) -> BlockType {
+        let size = self.chunks.chunk_size;
+        let top_chunk = self
+            .block_to_chunk_coordinate(I64Vec3::new(0, self.terrain_top(), 0))
+            .0
+            .y;
+        let bottom_chunk = self
+            .block_to_chunk_coordinate(I64Vec3::new(0, -(self.height as i64), 0))
+            .0
+            .y;
+        let chunks: Vec<_> = (bottom_chunk..=top_chunk)
+            .rev()
+            .filter_map(|chunk_y| {
+                let coord = I64Vec3::new(chunk_coord.0.x, chunk_y, chunk_coord.0.z);
+                self.get_chunk_data(ChunkCoordinate(coord))
+            })
+            .collect();
+
+        let mut counts: HashMap<BlockType, u32> = HashMap::new();
+        for x in 0..size {
+            for z in 0..size {
+                let surface = chunks.iter().find_map(|chunk_data| {
+                    (0..chunk_data.size)
+                        .rev()
+                        .filter_map(|y| chunk_data.get_block_at(U16Vec3::new(x, y, z)))
+                        .find(|block| {
+                            *block != BlockType::Air && block.shape() != BlockShape::Cross
+                        })
+                });
+                if let Some(block) = surface {
+                    *counts.entry(block).or_default() += 1;
+                }
+            }
+        }
+        // ties go to the highest id so the result doesn't depend on iteration order
+        counts
+            .into_iter()
+            .max_by_key(|(block, count)| (*count, block.id()))
+            .map(|(block, _)| block)
+            .unwrap_or_default()
+    }
+
+    /// Returns a chunk's data, loading it from the save or generating it first if it
+    /// isn't loaded yet.
+    pub fn load_or_generate_chunk(
+        &mut self,
+        chunk_coord: ChunkCoordinate,
+    ) -> Result<Arc<ChunkData>, WorldError> {
+        if let Some(chunk_data) = self.get_chunk_data(chunk_coord) {
+            return Ok(chunk_data);
+        }
+
+        let saved = match &self.save {
+            Some(save) if save.has_chunk(chunk_coord) => {
+                save.load_chunk(chunk_coord).map_err(WorldError::Io)?
+            }
+            _ => None,
+        };
+        let chunk_data = match saved {
+            Some(chunk_data) => chunk_data,
+            None => generate_chunk(
+                self.noise_generator.clone(),
+                chunk_coord,
+                self.height,
+                self.terrain,
+            )?,
+        };
+        Ok(self.insert_chunk(chunk_coord, chunk_data))
+    }
+
+    /// Loads or generates a chunk outside the chunk loader, remembering it if it wasn't
+    /// loaded already so the loader can unload it later.
+    fn load_untracked_chunk(
+        &mut self,
+        chunk_coord: ChunkCoordinate,
+    ) -> Result<Arc<ChunkData>, WorldError> {
+        let loaded = self.is_chunk_generated(chunk_coord);
+        let chunk_data = self.load_or_generate_chunk(chunk_coord)?;
+        if !loaded {
+            self.untracked_chunks.insert(chunk_coord);
+        }
+        Ok(chunk_data)
+    }
+
+    /// Chunks loaded outside the chunk loader that it hasn't taken over yet.
+    pub fn untracked_chunks(&self) -> impl Iterator<Item = ChunkCoordinate> + '_ {
+        self.untracked_chunks.iter().copied()
+    }
+
+    /// Hands a chunk loaded outside the chunk loader over to the loader.
+    pub fn track_chunk(&mut self, chunk_coord: ChunkCoordinate) {
+        self.untracked_chunks.remove(&chunk_coord);
+    }
+
+    /// Whether a chunk has been saved, so it should be loaded rather than generated.
+    pub fn chunk_exists_on_disk(&self, chunk_coord: ChunkCoordinate) -> bool {
+        self.save
+            .as_ref()
+            .is_some_and(|save| save.has_chunk(chunk_coord))
+    }
+
+    /// Replaces the world with a new one generated from `seed`, discarding every chunk and
+    /// any saved edits.
+    pub fn reset(&mut self, seed: u32) -> Result<(), WorldError> {
+        self.seed = seed;
+        self.noise_generator = Arc::new(RwLock::new(NoiseGenerator::with_params(
+            self.noise_seed(),
+            self.terrain.preset.noise_params(),
+        )));
+        self.chunks = ChunkOctree::default();
+        self.edited_chunks.clear();
+        self.untracked_chunks.clear();
+
+        if let Some(save) = &self.save {
+            save.clear_chunks().map_err(WorldError::Io)?;
+            save.save_seed(seed).map_err(WorldError::Io)?;
+        }
+        Ok(())
+    }
+
     pub fn insert_chunk(
         &mut self,
         chunk_coord: ChunkCoordinate,
@@ -47,8 +380,58 @@ impl World {
         self.chunks.get_chunk_data(chunk_coord)
     }
 
-    pub fn clear_chunk(&mut self, chunk_coord: ChunkCoordinate) {
-        self.chunks.clear_chunk(chunk_coord)
+    /// Every loaded chunk along with its data, in no particular order.
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = (ChunkCoordinate, Arc<ChunkData>)> + '_ {
+        self.chunks.chunks()
+    }
+
+    /// Rough number of bytes taken up by loaded chunk data.
+    pub fn chunk_memory(&self) -> usize {
+        self.chunks.memory()
+    }
+
+    /// Discards a chunk's data, saving it first if it has been edited.
+    pub fn clear_chunk(&mut self, chunk_coord: ChunkCoordinate) -> Result<(), WorldError> {
+        let saved = self.save_chunk(chunk_coord);
+        self.chunks.clear_chunk(chunk_coord);
+        self.untracked_chunks.remove(&chunk_coord);
+        saved
+    }
+
+    /// Discards all generated chunk data, saving any edited chunks first.
+    pub fn clear_chunks(&mut self) -> Result<(), WorldError> {
+        let saved = self.flush_saves();
+        self.chunks = ChunkOctree::default();
+        self.untracked_chunks.clear();
+        saved
+    }
+
+    /// Saves every edited chunk that hasn't been saved yet. Chunks that fail to save are
+    /// kept so they can be retried, and the first error is returned.
+    pub fn flush_saves(&mut self) -> Result<(), WorldError> {
+        let edited: Vec<_> = self.edited_chunks.iter().copied().collect();
+        let mut result = Ok(());
+        for chunk_coord in edited {
+            if let Err(e) = self.save_chunk(chunk_coord) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    fn save_chunk(&mut self, chunk_coord: ChunkCoordinate) -> Result<(), WorldError> {
+        if !self.edited_chunks.contains(&chunk_coord) {
+            return Ok(());
+        }
+
+        if let (Some(save), Some(chunk_data)) =
+            (self.save.clone(), self.get_chunk_data(chunk_coord))
+        {
+            save.save_chunk(chunk_coord, &chunk_data)
+                .map_err(WorldError::Io)?;
+        }
+        self.edited_chunks.remove(&chunk_coord);
+        Ok(())
     }
 
     pub fn adjacent_chunk_data(
@@ -66,11 +449,25 @@ impl World {
         self.chunks.get_chunk_data(chunk_coord).is_some()
     }
 
-    pub fn is_chunk_empty(&mut self, chunk_coord: ChunkCoordinate) -> bool {
-        self.chunks
-            .get_chunk_data(chunk_coord)
-            .map(|chunk_data| chunk_data.empty())
-            .unwrap_or(false)
+    /// Whether any part of a chunk lies inside the world border.
+    pub fn is_chunk_within_border(&self, chunk_coord: ChunkCoordinate) -> bool {
+        let size = self.chunks.chunk_size as i64;
+        let min = chunk_coord.0 * size;
+        let max = min + I64Vec3::splat(size - 1);
+        max.x >= -self.border
+            && min.x <= self.border
+            && max.z >= -self.border
+            && min.z <= self.border
+    }
+
+    /// Clamps a position horizontally to lie inside the world border.
+    pub fn clamp_to_border(&self, position: Vec3) -> Vec3 {
+        let border = self.border as f32;
+        Vec3::new(
+            position.x.clamp(-border, border),
+            position.y,
+            position.z.clamp(-border, border),
+        )
     }
 
     pub fn chunk_to_world(&self, chunk_coord: ChunkCoordinate) -> Vec3 {
@@ -78,31 +475,643 @@ impl World {
     }
 
     pub fn block_to_chunk_coordinate(&self, block_coord: I64Vec3) -> ChunkCoordinate {
-        (block_coord / self.chunks.chunk_size as i64).into()
+        block_coord
+            .div_euclid(I64Vec3::splat(self.chunks.chunk_size as i64))
+            .into()
+    }
+
+    fn block_to_local_coordinate(&self, block_coord: I64Vec3) -> U16Vec3 {
+        block_coord
+            .rem_euclid(I64Vec3::splat(self.chunks.chunk_size as i64))
+            .as_u16vec3()
+    }
+
+    /// Returns the block at a world position, or `None` if its chunk is not generated.
+    pub fn get_block(&mut self, block_coord: I64Vec3) -> Option<BlockType> {
+        let chunk_coord = self.block_to_chunk_coordinate(block_coord);
+        let local = self.block_to_local_coordinate(block_coord);
+        self.get_chunk_data(chunk_coord)
+            .and_then(|chunk_data| chunk_data.get_block_at(local))
+    }
+
+    /// Returns the block containing a point, or `None` if its chunk is not generated.
+    pub fn block_at(&mut self, position: Vec3) -> Option<BlockType> {
+        // blocks are centred on integer coordinates
+        self.get_block((position + Vec3::splat(0.5)).floor().as_i64vec3())
+    }
+
+    /// Whether the chunk containing a point has been generated, so its blocks are known
+    /// rather than assumed to be air.
+    pub fn is_position_loaded(&mut self, position: Vec3) -> bool {
+        let block = (position + Vec3::splat(0.5)).floor().as_i64vec3();
+        let chunk_coord = self.block_to_chunk_coordinate(block);
+        self.is_chunk_generated(chunk_coord)
+    }
+
+    /// Replaces the block at a world position. Returns `false` if its chunk is not generated.
+    pub fn set_block(&mut self, block_coord: I64Vec3, block_type: BlockType) -> bool {
+        let chunk_coord = self.block_to_chunk_coordinate(block_coord);
+        let local = self.block_to_local_coordinate(block_coord);
+        let Some(chunk_data) = self.get_chunk_data(chunk_coord) else {
+            return false;
+        };
+
+        let mut chunk_data = (*chunk_data).clone();
+        if !chunk_data.set_block_at(local, block_type) {
+            return false;
+        }
+        chunk_data.update_visibility();
+        self.insert_chunk(chunk_coord, chunk_data);
+        self.edited_chunks.insert(chunk_coord);
+        true
+    }
+
+    /// Sets many blocks at once, copying each affected chunk once rather than once per
+    /// block. Blocks in chunks that aren't generated are skipped. Returns the blocks that
+    /// were set, for marking their chunks dirty in one go.
+    pub fn set_blocks(&mut self, edits: &[(I64Vec3, BlockType)]) -> Vec<I64Vec3> {
+        let mut by_chunk: HashMap<ChunkCoordinate, Vec<(U16Vec3, I64Vec3, BlockType)>> =
+            HashMap::new();
+        for (block_coord, block_type) in edits {
+            let chunk_coord = self.block_to_chunk_coordinate(*block_coord);
+            let local = self.block_to_local_coordinate(*block_coord);
+            by_chunk
+                .entry(chunk_coord)
+                .or_default()
+                .push((local, *block_coord, *block_type));
+        }
+
+        let mut set = vec![];
+        for (chunk_coord, chunk_edits) in by_chunk {
+            let Some(chunk_data) = self.get_chunk_data(chunk_coord) else {
+                continue;
+            };
+
+            let mut chunk_data = (*chunk_data).clone();
+            let before = set.len();
+            for (local, block_coord, block_type) in chunk_edits {
+                if chunk_data.set_block_at(local, block_type) {
+                    set.push(block_coord);
+                }
+            }
+            if set.len() > before {
+                chunk_data.update_visibility();
+                self.insert_chunk(chunk_coord, chunk_data);
+                self.edited_chunks.insert(chunk_coord);
+            }
+        }
+        set
+    }
+
+    /// Steps through blocks along a ray, returning the first solid block within
+    /// `max_distance`. Blocks are centred on integer coordinates.
+    pub fn raycast(
+        &mut self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+
+        let start = origin + Vec3::splat(0.5);
+        let mut block = start.floor().as_i64vec3();
+        let mut normal = I64Vec3::ZERO;
+
+        let mut step = I64Vec3::ZERO;
+        let mut t_max = Vec3::splat(f32::INFINITY);
+        let t_delta = (1.0 / direction).abs();
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                t_max[axis] = (start[axis].floor() + 1.0 - start[axis]) / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                t_max[axis] = (start[axis] - start[axis].floor()) / -direction[axis];
+            }
+        }
+
+        loop {
+            if let Some(block_type) = self.get_block(block) {
+                if block_type.is_targetable() {
+                    return Some(RaycastHit {
+                        block,
+                        normal,
+                        block_type,
+                    });
+                }
+            }
+
+            let axis = if t_max.x <= t_max.y && t_max.x <= t_max.z {
+                0
+            } else if t_max.y <= t_max.z {
+                1
+            } else {
+                2
+            };
+            if t_max[axis] > max_distance {
+                return None;
+            }
+
+            block[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+            normal = I64Vec3::ZERO;
+            normal[axis] = -step[axis];
+        }
+    }
+
+    /// Returns the chunk containing a block plus any neighbouring chunks whose meshes
+    /// border it, i.e. every chunk that needs re-meshing when the block changes.
+    pub fn affected_chunks(&self, block_coord: I64Vec3) -> Vec<ChunkCoordinate> {
+        let chunk_coord = self.block_to_chunk_coordinate(block_coord);
+        let local = self.block_to_local_coordinate(block_coord);
+        let max = self.chunks.chunk_size - 1;
+
+        let mut chunks = vec![chunk_coord];
+        for (axis, offset) in [
+            (local.x, I64Vec3::X),
+            (local.y, I64Vec3::Y),
+            (local.z, I64Vec3::Z),
+        ] {
+            if axis == 0 {
+                chunks.push(ChunkCoordinate(chunk_coord.0 - offset));
+            } else if axis == max {
+                chunks.push(ChunkCoordinate(chunk_coord.0 + offset));
+            }
+        }
+        chunks
     }
 }
 
 impl Debug for World {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("World").field("seed", &self.seed).finish()
+        f.debug_struct("World")
+            .field("seed", &self.seed)
+            .field("dimension", &self.dimension)
+            .finish()
     }
 }
 
+/// Every point in a square spiral out from the origin, one ring at a time, up to and
+/// including ring `rings`.
+fn spiral(rings: i64) -> impl Iterator<Item = I64Vec2> {
+    std::iter::once(I64Vec2::ZERO).chain((1..=rings).flat_map(|ring| {
+        // walk each side of the ring, stopping short of the next corner
+        (0..8 * ring).map(move |i| {
+            let (side, offset) = (i / (2 * ring), i % (2 * ring));
+            match side {
+                0 => I64Vec2::new(-ring + offset, -ring),
+                1 => I64Vec2::new(ring, -ring + offset),
+                2 => I64Vec2::new(ring - offset, ring),
+                _ => I64Vec2::new(-ring, ring - offset),
+            }
+        })
+    }))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use bevy::math::{I64Vec2, I64Vec3, U16Vec3, Vec3};
+
+    use crate::{
+        block::BlockType,
+        chunks::{
+            chunk::ChunkData,
+            chunk::ChunkSlices,
+            generate::generator::{
+                column_height, generate_chunk, generate_chunk_geometry, SEA_LEVEL,
+            },
+        },
+        save::{TestSaveDir, WorldSave},
+        settings::{RendererSettings, TerrainSettings},
+    };
+
+    use super::{spiral, ChunkCoordinate, RaycastHit, World, OVERWORLD};
 
     #[test]
-    fn test_block_to_chunk_coordinate() {}
+    fn test_block_to_chunk_coordinate() {
+        let world = World::new();
+
+        assert_eq!(
+            ChunkCoordinate(I64Vec3::new(0, 0, 0)),
+            world.block_to_chunk_coordinate(I64Vec3::new(0, 15, 3))
+        );
+        assert_eq!(
+            ChunkCoordinate(I64Vec3::new(-1, 1, -2)),
+            world.block_to_chunk_coordinate(I64Vec3::new(-1, 16, -17))
+        );
+    }
 
     #[test]
-    fn test_is_chunk_generated() {}
+    fn test_clear_chunk_saves_edits() {
+        let dir = TestSaveDir::default();
+        let save = Arc::new(WorldSave::open(dir.path()).unwrap());
+        let coord = ChunkCoordinate(I64Vec3::new(0, 0, 0));
+
+        let mut world = World::new();
+        world.save = Some(save.clone());
+        world.insert_chunk(coord, ChunkData::default());
+        world.set_block(I64Vec3::new(1, 2, 3), BlockType::Stone);
+        world.clear_chunk(coord).unwrap();
+
+        let saved = save.load_chunk(coord).unwrap().unwrap();
+        assert_eq!(
+            Some(BlockType::Stone),
+            saved.get_block_at(U16Vec3::new(1, 2, 3))
+        );
+    }
 
     #[test]
-    fn test_generate_chunk_updates_chunk_data() {}
+    fn test_set_blocks_applies_every_edit() {
+        let mut world = World::new();
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+        world.insert_chunk(ChunkCoordinate(I64Vec3::X), ChunkData::default());
+        let edits = [
+            (I64Vec3::new(1, 2, 3), BlockType::Stone),
+            (I64Vec3::new(15, 2, 3), BlockType::Sand),
+            (I64Vec3::new(16, 2, 3), BlockType::Snow),
+            // not generated, so skipped
+            (I64Vec3::new(-1, 2, 3), BlockType::Stone),
+        ];
+
+        let set = world.set_blocks(&edits);
+
+        assert_eq!(3, set.len());
+        assert!(!set.contains(&I64Vec3::new(-1, 2, 3)));
+        for (block, block_type) in &edits[..3] {
+            assert_eq!(Some(*block_type), world.get_block(*block));
+        }
+    }
+
+    #[test]
+    fn test_set_block_updates_block() {
+        let mut world = World::new();
+        world.insert_chunk(
+            ChunkCoordinate(I64Vec3::new(-1, 0, 0)),
+            ChunkData::default(),
+        );
+
+        assert!(world.set_block(I64Vec3::new(-3, 4, 5), BlockType::Stone));
+        assert_eq!(
+            Some(BlockType::Stone),
+            world.get_block(I64Vec3::new(-3, 4, 5))
+        );
+        assert_eq!(
+            Some(BlockType::Air),
+            world.get_block(I64Vec3::new(-4, 4, 5))
+        );
+    }
+
+    #[test]
+    fn test_set_block_fails_for_ungenerated_chunk() {
+        let mut world = World::new();
+        assert!(!world.set_block(I64Vec3::new(3, 4, 5), BlockType::Stone));
+        assert_eq!(None, world.get_block(I64Vec3::new(3, 4, 5)));
+    }
+
+    #[test]
+    fn test_world_border() {
+        let mut world = World::new();
+        world.border = 100;
+
+        assert!(world.is_chunk_within_border(ChunkCoordinate(I64Vec3::new(6, 0, -7))));
+        assert!(!world.is_chunk_within_border(ChunkCoordinate(I64Vec3::new(7, 0, 0))));
+        assert!(!world.is_chunk_within_border(ChunkCoordinate(I64Vec3::new(0, 0, -8))));
+        assert_eq!(
+            Vec3::new(100.0, 500.0, -100.0),
+            world.clamp_to_border(Vec3::new(250.0, 500.0, -1000.0))
+        );
+    }
 
     #[test]
-    fn test_generate_chunk_mesh_none_for_ungenerated_chunk() {}
+    fn test_affected_chunks_includes_boundary_neighbours() {
+        let world = World::new();
+
+        assert_eq!(
+            vec![ChunkCoordinate(I64Vec3::ZERO)],
+            world.affected_chunks(I64Vec3::new(4, 4, 4))
+        );
+        assert_eq!(
+            vec![
+                ChunkCoordinate(I64Vec3::ZERO),
+                ChunkCoordinate(I64Vec3::new(-1, 0, 0)),
+                ChunkCoordinate(I64Vec3::new(0, 0, 1)),
+            ],
+            world.affected_chunks(I64Vec3::new(0, 4, 15))
+        );
+    }
+
+    #[test]
+    fn test_raycast_hits_first_solid_block() {
+        let mut world = World::new();
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+        world.set_block(I64Vec3::new(3, 2, 2), BlockType::Stone);
+        world.set_block(I64Vec3::new(5, 2, 2), BlockType::Stone);
+
+        let hit = world.raycast(Vec3::new(0.0, 2.0, 2.0), Vec3::X, 5.0);
+        assert_eq!(
+            Some(RaycastHit {
+                block: I64Vec3::new(3, 2, 2),
+                normal: I64Vec3::new(-1, 0, 0),
+                block_type: BlockType::Stone,
+            }),
+            hit
+        );
+    }
+
+    #[test]
+    fn test_raycast_respects_max_distance() {
+        let mut world = World::new();
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+        world.set_block(I64Vec3::new(2, 8, 2), BlockType::Stone);
+
+        assert!(world
+            .raycast(Vec3::new(2.0, 2.0, 2.0), Vec3::Y, 5.0)
+            .is_none());
+        assert_eq!(
+            I64Vec3::new(0, -1, 0),
+            world
+                .raycast(Vec3::new(2.0, 2.0, 2.0), Vec3::Y, 6.0)
+                .unwrap()
+                .normal
+        );
+    }
+
+    #[test]
+    fn test_is_chunk_generated() {
+        let mut world = World::new();
+        let coord = ChunkCoordinate(I64Vec3::new(1, -2, 3));
+        assert!(!world.is_chunk_generated(coord));
+
+        world.insert_chunk(coord, ChunkData::default());
+        assert!(world.is_chunk_generated(coord));
+        assert!(!world.is_chunk_generated(ChunkCoordinate(I64Vec3::ZERO)));
+    }
+
+    #[test]
+    fn test_generate_chunk_updates_chunk_data() {
+        // generating with separate generators rules out the noise cache hiding a change
+        let generate = |coord: ChunkCoordinate| {
+            let world = World::with_seed(1234);
+            generate_chunk(
+                world.noise_generator.clone(),
+                coord,
+                world.height,
+                world.terrain,
+            )
+            .unwrap()
+        };
+
+        for coord in [
+            I64Vec3::new(0, 0, 0),
+            I64Vec3::new(-3, 2, 7),
+            I64Vec3::new(5, -4, -1),
+        ] {
+            let (first, second) = (
+                generate(ChunkCoordinate(coord)),
+                generate(ChunkCoordinate(coord)),
+            );
+            assert_eq!(first.blocks(), second.blocks());
+            assert_eq!(first.temperatures(), second.temperatures());
+        }
+    }
+
+    #[test]
+    fn test_dimensions_generate_different_terrain() {
+        let heights = |world: World| -> Vec<u64> {
+            let mut noise = world.noise_generator.write().unwrap();
+            (0..16)
+                .map(|i| I64Vec2::new(i * 97, i * -41))
+                .map(|column| column_height(&mut noise, column, world.height, world.terrain))
+                .collect()
+        };
+
+        let overworld = heights(World::new_with_seed(1234, OVERWORLD));
+        assert_eq!(overworld, heights(World::with_seed(1234)));
+        assert_ne!(overworld, heights(World::new_with_seed(1234, 1)));
+    }
+
+    #[test]
+    fn test_generate_chunk_mesh_some_for_generated_chunk() {
+        let mut world = World::new();
+        let coord = ChunkCoordinate(I64Vec3::ZERO);
+        let mut chunk_data = ChunkData::default();
+        // an L of three blocks, each pair sharing one hidden face
+        chunk_data.set_block_at(U16Vec3::new(1, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(2, 1, 1), BlockType::Stone);
+        chunk_data.set_block_at(U16Vec3::new(2, 2, 1), BlockType::Stone);
+        world.insert_chunk(coord, chunk_data);
+
+        let chunk_data = world.get_chunk_data(coord).unwrap();
+        let meshes = generate_chunk_geometry(
+            chunk_data,
+            world.adjacent_chunk_data(coord),
+            None,
+            ChunkSlices::ALL,
+        )
+        .to_meshes(RendererSettings::default());
+
+        assert_eq!(1, meshes.opaque.len());
+        assert!(meshes.translucent.is_empty());
+        assert_eq!(14 * 4, meshes.opaque[0].count_vertices());
+        assert_eq!(14 * 6, meshes.opaque[0].indices().unwrap().len());
+    }
 
     #[test]
-    fn test_generate_chunk_mesh_some_for_generated_chunk() {}
+    fn test_reset_discards_chunks_and_saves() {
+        let dir = TestSaveDir::default();
+        let save = Arc::new(WorldSave::open(dir.path()).unwrap());
+        let mut world = World::with_seed(1);
+        world.save = Some(save.clone());
+
+        let coord = ChunkCoordinate(I64Vec3::ZERO);
+        world.insert_chunk(coord, ChunkData::default());
+        world.set_block(I64Vec3::new(1, 2, 3), BlockType::Stone);
+        world.flush_saves().unwrap();
+        assert!(save.load_chunk(coord).unwrap().is_some());
+
+        world.reset(2).unwrap();
+
+        assert_eq!(2, world.seed());
+        assert!(!world.is_chunk_generated(coord));
+        assert!(save.load_chunk(coord).unwrap().is_none());
+        assert_eq!(Some(2), save.load_seed().unwrap());
+    }
+
+    #[test]
+    fn test_chunk_surface_sample_finds_most_common_surface() {
+        let mut world = World::with_seed(1);
+        let coord = ChunkCoordinate(I64Vec3::new(2, 0, -1));
+        assert_eq!(BlockType::Air, world.chunk_surface_sample(coord));
+
+        let mut chunk_data = ChunkData::default();
+        for x in 0..16 {
+            for z in 0..16 {
+                let surface = if x < 4 {
+                    BlockType::Sand
+                } else {
+                    BlockType::Grass
+                };
+                chunk_data.set_block_at(U16Vec3::new(x, 3, z), BlockType::Stone);
+                chunk_data.set_block_at(U16Vec3::new(x, 4, z), surface);
+                // plants stand on the surface without covering it
+                chunk_data.set_block_at(U16Vec3::new(x, 5, z), BlockType::TallGrass);
+            }
+        }
+        world.insert_chunk(coord, chunk_data);
+
+        assert_eq!(BlockType::Grass, world.chunk_surface_sample(coord));
+    }
+
+    #[test]
+    fn test_loaded_chunks_match_saved_chunks() {
+        let dir = TestSaveDir::default();
+        let save = Arc::new(WorldSave::open(dir.path()).unwrap());
+        let coords = [
+            ChunkCoordinate(I64Vec3::new(0, 0, 0)),
+            ChunkCoordinate(I64Vec3::new(1, 0, 0)),
+            ChunkCoordinate(I64Vec3::new(-1, 2, 5)),
+            ChunkCoordinate(I64Vec3::new(40, 0, -40)),
+        ];
+
+        let mut world = World::with_seed(1);
+        world.save = Some(save.clone());
+        for (i, coord) in coords.iter().enumerate() {
+            world.insert_chunk(*coord, ChunkData::default());
+            let block = coord.0 * 16 + I64Vec3::new(i as i64, 1, 2);
+            world.set_block(block, BlockType::Stone);
+        }
+        world.flush_saves().unwrap();
+
+        let mut loaded = World::with_seed(1);
+        loaded.save = Some(save);
+        // looking up chunks before they're loaded caches the nodes they'll be stored in
+        for coord in coords {
+            assert!(loaded.get_chunk_data(coord).is_none());
+        }
+        for coord in coords {
+            loaded.load_or_generate_chunk(coord).unwrap();
+        }
+
+        for coord in coords {
+            let saved = world.get_chunk_data(coord).unwrap();
+            let restored = loaded.get_chunk_data(coord).unwrap();
+            assert_eq!(saved.blocks(), restored.blocks());
+        }
+    }
+
+    #[test]
+    fn test_spiral_visits_rings_in_order() {
+        let points: Vec<_> = spiral(3).collect();
+
+        assert_eq!(7 * 7, points.len());
+        assert_eq!(I64Vec2::ZERO, points[0]);
+        assert_eq!(points.len(), points.iter().collect::<HashSet<_>>().len());
+        assert!(points
+            .windows(2)
+            .all(|pair| pair[0].abs().max_element() <= pair[1].abs().max_element()));
+    }
+
+    #[test]
+    fn test_spawn_point_is_on_land() {
+        for seed in 0..8 {
+            let spawn = World::with_seed(seed).spawn_point();
+            assert!(
+                spawn.y > SEA_LEVEL as f32,
+                "seed {} spawned at {}",
+                seed,
+                spawn
+            );
+        }
+    }
+
+    #[test]
+    fn test_spawn_point_falls_back_without_land() {
+        let mut world = World::with_seed(1);
+        world.terrain = TerrainSettings {
+            height_scale: 0.0,
+            ..TerrainSettings::default()
+        };
+
+        assert_eq!(Vec3::new(0.0, SEA_LEVEL as f32, 0.0), world.spawn_point());
+    }
+
+    #[test]
+    fn test_surface_height_finds_highest_solid_block() {
+        let mut world = World::with_seed(1);
+        world.terrain = TerrainSettings {
+            height_scale: 0.0,
+            ..TerrainSettings::default()
+        };
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+        world.set_block(I64Vec3::new(3, 5, 3), BlockType::Stone);
+        world.set_block(I64Vec3::new(3, 9, 3), BlockType::Water);
+        world.set_block(I64Vec3::new(3, 10, 3), BlockType::TallGrass);
+
+        assert_eq!(Some(5), world.surface_height(3, 3));
+
+        world.set_block(I64Vec3::new(3, 12, 3), BlockType::Stone);
+        assert_eq!(Some(12), world.surface_height(3, 3));
+    }
+
+    #[test]
+    fn test_surface_height_generates_column() {
+        let mut world = World::with_seed(1);
+        let spawn = world.spawn_point();
+        let (x, z) = (spawn.x as i64, spawn.z as i64);
+        world.clear_chunks().unwrap();
+
+        assert_eq!(Some(spawn.y as i64 - 1), world.surface_height(x, z));
+        assert!(
+            world.is_chunk_generated(world.block_to_chunk_coordinate(I64Vec3::new(
+                x,
+                spawn.y as i64 - 1,
+                z
+            )))
+        );
+    }
+
+    #[test]
+    fn test_is_position_loaded() {
+        let mut world = World::new();
+        world.insert_chunk(ChunkCoordinate(I64Vec3::ZERO), ChunkData::default());
+
+        assert!(world.is_position_loaded(Vec3::new(8.0, 0.0, 8.0)));
+        assert!(world.is_position_loaded(Vec3::new(15.4, 15.4, 0.0)));
+        assert!(!world.is_position_loaded(Vec3::new(8.0, -0.6, 8.0)));
+        assert!(!world.is_position_loaded(Vec3::new(15.6, 8.0, 8.0)));
+    }
+
+    #[test]
+    fn test_pregenerate_fills_radius() {
+        let mut world = World::with_seed(1);
+
+        assert_eq!(27, world.pregenerate(Vec3::new(8.0, 40.0, 8.0), 1).unwrap());
+        for x in -1..=1 {
+            for y in 1..=3 {
+                for z in -1..=1 {
+                    assert!(world.is_chunk_generated(ChunkCoordinate(I64Vec3::new(x, y, z))));
+                }
+            }
+        }
+        // chunks that already exist are left alone
+        assert_eq!(0, world.pregenerate(Vec3::new(8.0, 40.0, 8.0), 1).unwrap());
+    }
+
+    #[test]
+    fn test_force_load_chunk_loads_neighbours() {
+        let mut world = World::with_seed(1);
+        let coord = ChunkCoordinate(I64Vec3::new(40, 2, -40));
+
+        world.force_load_chunk(coord).unwrap();
+        assert!(world.is_chunk_generated(coord));
+        assert!(coord
+            .adjacent()
+            .iter()
+            .all(|neighbour| world.is_chunk_generated(*neighbour)));
+    }
 }